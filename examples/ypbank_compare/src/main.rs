@@ -3,9 +3,9 @@
 //! Пример команды:
 //! `ypbank_compare --file1 records_example.bin --format1 binary --file2 records_example.csv --format2 csv`
 use clap::{Parser, ValueEnum};
-use std::path::PathBuf;
+use std::{path::PathBuf, process::ExitCode};
 
-use bank_record_converter::{BankRecordConvertor, DataFormat, error::AppError};
+use bank_record_converter::{DataFormat, RecordReader, error::AppError};
 #[derive(Parser, Debug)]
 struct Cli {
     #[arg(long)]
@@ -31,11 +31,74 @@ enum InputDataFormat {
     Bin,
     /// csv format YPBank
     Csv,
+    /// json array of records
+    Json,
+    /// newline-delimited json, one record per line
+    Ndjson,
+    /// ISO 20022 camt.053 bank statement
+    Camt053,
+    /// SWIFT MT940 statement
+    Mt940,
+    /// OFX 2.x bank statement
+    Ofx,
+    /// QIF export for personal finance software
+    Qif,
+    /// BAI2 cash management statement
+    Bai2,
+    /// fixed-width records with the default layout
+    FixedWidth,
+    /// Apache Parquet columnar file
+    #[cfg(feature = "parquet")]
+    Parquet,
+    /// Arrow IPC file
+    #[cfg(feature = "arrow")]
+    Arrow,
+    /// Arrow IPC stream
+    #[cfg(feature = "arrow")]
+    ArrowStream,
+    /// SQLite database file
+    #[cfg(feature = "sqlite")]
+    Sqlite,
 }
 
-fn main() -> Result<(), AppError> {
-    let args = Cli::parse();
+impl From<InputDataFormat> for DataFormat {
+    fn from(format: InputDataFormat) -> Self {
+        match format {
+            InputDataFormat::Txt => DataFormat::TXT,
+            InputDataFormat::Bin => DataFormat::BIN,
+            InputDataFormat::Csv => DataFormat::CSV,
+            InputDataFormat::Json => DataFormat::JSON,
+            InputDataFormat::Ndjson => DataFormat::NDJSON,
+            InputDataFormat::Camt053 => DataFormat::CAMT053,
+            InputDataFormat::Mt940 => DataFormat::MT940,
+            InputDataFormat::Ofx => DataFormat::OFX,
+            InputDataFormat::Qif => DataFormat::QIF,
+            InputDataFormat::Bai2 => DataFormat::BAI2,
+            InputDataFormat::FixedWidth => DataFormat::FIXEDWIDTH,
+            #[cfg(feature = "parquet")]
+            InputDataFormat::Parquet => DataFormat::PARQUET,
+            #[cfg(feature = "arrow")]
+            InputDataFormat::Arrow => DataFormat::ARROW,
+            #[cfg(feature = "arrow")]
+            InputDataFormat::ArrowStream => DataFormat::ARROWSTREAM,
+            #[cfg(feature = "sqlite")]
+            InputDataFormat::Sqlite => DataFormat::SQLITE,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    // Ошибки выводятся через Display, чтобы было видно файл, строку и поле
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
 
+fn run(args: Cli) -> Result<(), AppError> {
     if !std::path::Path::new(&args.file1).exists() {
         return Err(AppError::IOError(std::io::ErrorKind::NotFound.into()));
     }
@@ -47,24 +110,19 @@ fn main() -> Result<(), AppError> {
     let file1 = std::fs::File::open(&args.file1)?;
     let file2 = std::fs::File::open(&args.file2)?;
 
-    let format1 = match &args.format1 {
-        InputDataFormat::Txt => DataFormat::TXT,
-        InputDataFormat::Bin => DataFormat::BIN,
-        InputDataFormat::Csv => DataFormat::CSV,
-    };
-
-    let format2 = match &args.format2 {
-        InputDataFormat::Txt => DataFormat::TXT,
-        InputDataFormat::Bin => DataFormat::BIN,
-        InputDataFormat::Csv => DataFormat::CSV,
-    };
+    let format1 = DataFormat::from(args.format1);
+    let format2 = DataFormat::from(args.format2);
 
-    let records1 = BankRecordConvertor::from_read(file1, &format1)?;
-    let records2 = BankRecordConvertor::from_read(file2, &format2)?;
+    // Файлы читаются потоково и сравниваются запись за записью
+    let records1 =
+        RecordReader::new(file1, &format1)?.with_source(args.file1.display().to_string());
+    let records2 =
+        RecordReader::new(file2, &format2)?.with_source(args.file2.display().to_string());
 
     let mut flag = true;
-    for (mes1, mes2) in records1.iter().zip(records2.iter()) {
-        if !mes1.eq(mes2) {
+    for (mes1, mes2) in records1.zip(records2) {
+        let (mes1, mes2) = (mes1?, mes2?);
+        if !mes1.eq(&mes2) {
             flag = false;
             println!("{:?}/n{:?}", mes1, mes2);
         }
@@ -2,9 +2,16 @@
 //! Програма для конвертации данных из одного формата в другой
 //! Пример запуска: `ypbank_converter --input ../../tests/data/examples_file/records_example.bin -I bin -O txt > output_file.txt`
 use clap::{Parser, ValueEnum};
-use std::{io::Write, path::PathBuf};
+use std::{io::Write, path::PathBuf, process::ExitCode};
 
-use bank_record_converter::{BankRecordConvertor, DataFormat, error::AppError};
+#[cfg(feature = "parquet")]
+use bank_record_converter::RowGroupSize;
+#[cfg(feature = "sqlite")]
+use bank_record_converter::SqliteTable;
+use bank_record_converter::{
+    AmountPolicy, ConvertOptions, Currency, DataFormat, DuplicatePolicy, FixedWidthLayout,
+    TxIdStrategy, convert_stream_with, error::AppError,
+};
 
 /// CLI arguments
 #[derive(Parser, Debug)]
@@ -21,6 +28,58 @@ struct Cli {
     /// output file format
     #[arg(short = 'O', long)]
     output_format: InputDataFormat,
+
+    /// negative amount handling for txt, csv, camt053, mt940, ofx, qif and bai2 output
+    #[arg(long, value_enum, default_value_t = NegativeAmount::Reject)]
+    negative_amount: NegativeAmount,
+
+    /// skip corrupted records and report them to stderr
+    #[arg(long)]
+    recover: bool,
+
+    /// duplicate TX_ID handling
+    #[arg(long, value_enum, default_value_t = Duplicates::Allow)]
+    duplicates: Duplicates,
+
+    /// write the raw text of skipped txt records to this file, implies --recover
+    #[arg(long)]
+    quarantine: Option<PathBuf>,
+
+    /// ISO 4217 currency for records without CURRENCY, required by camt053, mt940, ofx, qif and bai2 output
+    #[arg(long)]
+    currency: Option<String>,
+
+    /// use the current time as the creation time of camt053, ofx and bai2 output instead of the latest record time
+    #[arg(long)]
+    created_now: bool,
+
+    /// TX_ID source for qif input
+    #[arg(long, value_enum, default_value_t = TxId::Number)]
+    tx_id: TxId,
+
+    /// fixed-width layout file, one `NAME,OFFSET,WIDTH,PADDING,ALIGNMENT` column per line
+    #[arg(long)]
+    layout: Option<PathBuf>,
+
+    /// maximum number of rows in a parquet row group
+    #[cfg(feature = "parquet")]
+    #[arg(long, default_value_t = RowGroupSize::default().rows)]
+    row_group_rows: usize,
+
+    /// close a parquet row group early once its buffered data reaches this many bytes
+    #[cfg(feature = "parquet")]
+    #[arg(long)]
+    row_group_bytes: Option<usize>,
+
+    /// sqlite table to write or read records
+    #[cfg(feature = "sqlite")]
+    #[arg(long, default_value_t = SqliteTable::default().name)]
+    table: String,
+
+    /// SELECT statement to read sqlite input instead of the whole table
+    #[cfg(feature = "sqlite")]
+    #[arg(long)]
+    query: Option<String>,
 }
 
 /// Data format
@@ -32,11 +91,114 @@ enum InputDataFormat {
     Bin,
     /// csv format YPBank
     Csv,
+    /// json array of records
+    Json,
+    /// newline-delimited json, one record per line
+    Ndjson,
+    /// ISO 20022 camt.053 bank statement
+    Camt053,
+    /// SWIFT MT940 statement
+    Mt940,
+    /// OFX 2.x bank statement
+    Ofx,
+    /// QIF export for personal finance software
+    Qif,
+    /// BAI2 cash management statement
+    Bai2,
+    /// fixed-width records of a core banking system
+    FixedWidth,
+    /// Apache Parquet columnar file
+    #[cfg(feature = "parquet")]
+    Parquet,
+    /// Arrow IPC file
+    #[cfg(feature = "arrow")]
+    Arrow,
+    /// Arrow IPC stream
+    #[cfg(feature = "arrow")]
+    ArrowStream,
+    /// SQLite database file
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+    /// XLSX spreadsheet, output only
+    #[cfg(feature = "xlsx")]
+    Xlsx,
+}
+
+/// Negative amount handling
+#[derive(ValueEnum, Clone, Debug)]
+enum NegativeAmount {
+    /// fail on a negative amount
+    Reject,
+    /// write the absolute value
+    Absolute,
+}
+
+/// Duplicate TX_ID handling
+#[derive(ValueEnum, Clone, Debug)]
+enum Duplicates {
+    /// do not check
+    Allow,
+    /// drop every record with a repeated TX_ID
+    Reject,
+    /// keep the first record with a TX_ID
+    KeepFirst,
+    /// keep the last record with a TX_ID
+    KeepLast,
+    /// fail on the first repeated TX_ID
+    Fail,
+}
+
+/// TX_ID source for qif input
+#[derive(ValueEnum, Clone, Debug)]
+enum TxId {
+    /// number from the N line
+    Number,
+    /// record ordinal starting from 1
+    Sequence,
+    /// hash of the transaction fields, stable between imports
+    Hash,
+}
+
+impl From<InputDataFormat> for DataFormat {
+    fn from(format: InputDataFormat) -> Self {
+        match format {
+            InputDataFormat::Txt => DataFormat::TXT,
+            InputDataFormat::Bin => DataFormat::BIN,
+            InputDataFormat::Csv => DataFormat::CSV,
+            InputDataFormat::Json => DataFormat::JSON,
+            InputDataFormat::Ndjson => DataFormat::NDJSON,
+            InputDataFormat::Camt053 => DataFormat::CAMT053,
+            InputDataFormat::Mt940 => DataFormat::MT940,
+            InputDataFormat::Ofx => DataFormat::OFX,
+            InputDataFormat::Qif => DataFormat::QIF,
+            InputDataFormat::Bai2 => DataFormat::BAI2,
+            InputDataFormat::FixedWidth => DataFormat::FIXEDWIDTH,
+            #[cfg(feature = "parquet")]
+            InputDataFormat::Parquet => DataFormat::PARQUET,
+            #[cfg(feature = "arrow")]
+            InputDataFormat::Arrow => DataFormat::ARROW,
+            #[cfg(feature = "arrow")]
+            InputDataFormat::ArrowStream => DataFormat::ARROWSTREAM,
+            #[cfg(feature = "sqlite")]
+            InputDataFormat::Sqlite => DataFormat::SQLITE,
+            #[cfg(feature = "xlsx")]
+            InputDataFormat::Xlsx => DataFormat::XLSX,
+        }
+    }
 }
 
-fn main() -> Result<(), AppError> {
-    let args = Cli::parse();
+fn main() -> ExitCode {
+    // Ошибки выводятся через Display, чтобы было видно файл, строку и поле
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
 
+fn run(args: Cli) -> Result<(), AppError> {
     if !std::path::Path::new(&args.input).exists() {
         return Err(AppError::IOError(std::io::ErrorKind::NotFound.into()));
     }
@@ -46,52 +208,77 @@ fn main() -> Result<(), AppError> {
     let stdout = std::io::stdout();
     let mut writer = std::io::BufWriter::new(stdout.lock());
 
-    match (args.input_format, args.output_format) {
-        (InputDataFormat::Txt, InputDataFormat::Txt) => {
-            BankRecordConvertor::from_read(file, &DataFormat::TXT)?
-                .convert_to(&DataFormat::TXT)
-                .write_to(&mut writer)?
-        }
-        (InputDataFormat::Txt, InputDataFormat::Bin) => {
-            BankRecordConvertor::from_read(file, &DataFormat::TXT)?
-                .convert_to(&DataFormat::BIN)
-                .write_to(&mut writer)?
-        }
-        (InputDataFormat::Txt, InputDataFormat::Csv) => {
-            BankRecordConvertor::from_read(file, &DataFormat::TXT)?
-                .convert_to(&DataFormat::CSV)
-                .write_to(&mut writer)?
-        }
-        (InputDataFormat::Bin, InputDataFormat::Txt) => {
-            BankRecordConvertor::from_read(file, &DataFormat::BIN)?
-                .convert_to(&DataFormat::TXT)
-                .write_to(&mut writer)?
-        }
-        (InputDataFormat::Bin, InputDataFormat::Bin) => {
-            BankRecordConvertor::from_read(file, &DataFormat::BIN)?
-                .convert_to(&DataFormat::BIN)
-                .write_to(&mut writer)?
-        }
-        (InputDataFormat::Bin, InputDataFormat::Csv) => {
-            BankRecordConvertor::from_read(file, &DataFormat::BIN)?
-                .convert_to(&DataFormat::CSV)
-                .write_to(&mut writer)?
-        }
-        (InputDataFormat::Csv, InputDataFormat::Txt) => {
-            BankRecordConvertor::from_read(file, &DataFormat::CSV)?
-                .convert_to(&DataFormat::TXT)
-                .write_to(&mut writer)?
-        }
-        (InputDataFormat::Csv, InputDataFormat::Bin) => {
-            BankRecordConvertor::from_read(file, &DataFormat::CSV)?
-                .convert_to(&DataFormat::BIN)
-                .write_to(&mut writer)?
-        }
-        (InputDataFormat::Csv, InputDataFormat::Csv) => {
-            BankRecordConvertor::from_read(file, &DataFormat::CSV)?
-                .convert_to(&DataFormat::CSV)
-                .write_to(&mut writer)?
+    let input_format = DataFormat::from(args.input_format);
+
+    let output_format = DataFormat::from(args.output_format);
+
+    let mut options = ConvertOptions::default();
+    options.amount_policy = match args.negative_amount {
+        NegativeAmount::Reject => AmountPolicy::Reject,
+        NegativeAmount::Absolute => AmountPolicy::Absolute,
+    };
+    options.source = Some(args.input.display().to_string());
+    options.recover = args.recover || args.quarantine.is_some();
+    options.duplicates = match args.duplicates {
+        Duplicates::Allow => DuplicatePolicy::Allow,
+        Duplicates::Reject => DuplicatePolicy::Reject,
+        Duplicates::KeepFirst => DuplicatePolicy::KeepFirst,
+        Duplicates::KeepLast => DuplicatePolicy::KeepLast,
+        Duplicates::Fail => DuplicatePolicy::Fail,
+    };
+    options.default_currency = args
+        .currency
+        .as_deref()
+        .map(Currency::from_code)
+        .transpose()?;
+    if args.created_now {
+        options.created = Some(
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default(),
+        );
+    }
+    options.tx_id_strategy = match args.tx_id {
+        TxId::Number => TxIdStrategy::Number,
+        TxId::Sequence => TxIdStrategy::Sequence(1),
+        TxId::Hash => TxIdStrategy::Hash,
+    };
+    options.fixed_width_layout = match &args.layout {
+        Some(path) => std::fs::read_to_string(path)?.parse()?,
+        None => FixedWidthLayout::default(),
+    };
+    #[cfg(feature = "parquet")]
+    {
+        options.row_group_size = RowGroupSize {
+            rows: args.row_group_rows,
+            bytes: args.row_group_bytes,
+        };
+    }
+    #[cfg(feature = "sqlite")]
+    {
+        options.sqlite_table = SqliteTable {
+            name: args.table,
+            query: args.query,
+        };
+    }
+
+    // Записи конвертируются по одной, файл целиком в память не загружается
+    let report = convert_stream_with(file, &input_format, &mut writer, &output_format, &options)?;
+    for diagnostic in &report.diagnostics {
+        eprintln!("Skipped: {}", diagnostic);
+    }
+    for duplicate in &report.duplicates {
+        eprintln!("Duplicate: {}", duplicate);
+    }
+    if let Some(path) = &args.quarantine {
+        let mut quarantine = std::io::BufWriter::new(std::fs::File::create(path)?);
+        for diagnostic in &report.diagnostics {
+            if let AppError::TxtRecordError(err) = diagnostic {
+                writeln!(quarantine, "{}", err.text)?;
+            }
         }
+        quarantine.flush()?;
     }
 
     writer.flush()?;
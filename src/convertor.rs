@@ -1,15 +1,70 @@
+#[cfg(feature = "arrow")]
+mod arrow_format;
+mod bai2_format;
 mod bin_format;
+mod camt053_format;
 mod csv_format;
+mod datetime;
+mod fixed_width_format;
+mod json_format;
+mod money;
+mod mt940_format;
+mod ndjson_format;
+mod ofx_format;
+#[cfg(feature = "parquet")]
+mod parquet_format;
+mod qif_format;
+#[cfg(feature = "sqlite")]
+mod sqlite_format;
+mod statement;
+#[cfg(test)]
+mod test_records;
 mod txt_format;
+#[cfg(feature = "xlsx")]
+mod xlsx_format;
+mod xml;
+
+use std::borrow::Cow;
 
 use enum_display::EnumDisplay;
 
-use crate::error::{AppError, BinParseError, Result};
+use crate::{
+    DataFormat,
+    error::{AppError, Result},
+};
+
+#[cfg(feature = "arrow")]
+pub use arrow_format::{from_record_batch, record_batch_schema, to_record_batch};
+pub use fixed_width_format::{Alignment, FixedWidthField, FixedWidthLayout, RecordField};
+pub use money::{Currency, Money};
+#[cfg(feature = "parquet")]
+pub use parquet_format::RowGroupSize;
+#[cfg(feature = "sqlite")]
+pub use sqlite_format::{SqliteTable, read_sqlite, write_sqlite};
 
 pub(crate) use {
-    bin_format::BinYPBankRecord, csv_format::CsvYPBankRecord, txt_format::TxtYPBankRecord,
+    bai2_format::{Bai2RecordReader, Bai2RecordWriter, Bai2YPBankRecord},
+    bin_format::{BinRecordReader, BinRecordWriter, BinYPBankRecord, MAX_DESCRIPTION_LEN},
+    camt053_format::{Camt053RecordReader, Camt053RecordWriter, Camt053YPBankRecord},
+    csv_format::{CsvRecordReader, CsvRecordWriter, CsvYPBankRecord},
+    fixed_width_format::{FixedWidthRecordReader, FixedWidthRecordWriter, FixedWidthYPBankRecord},
+    json_format::{JsonRecordReader, JsonRecordWriter, JsonYPBankRecord},
+    mt940_format::{Mt940RecordReader, Mt940RecordWriter, Mt940YPBankRecord},
+    ndjson_format::{NdjsonRecordReader, NdjsonRecordWriter, NdjsonYPBankRecord},
+    ofx_format::{OfxRecordReader, OfxRecordWriter, OfxYPBankRecord},
+    qif_format::{QifRecordReader, QifRecordWriter, QifYPBankRecord},
+    txt_format::{TxtRecordReader, TxtRecordWriter, TxtYPBankRecord},
 };
 
+#[cfg(feature = "arrow")]
+pub(crate) use arrow_format::{ArrowRecordReader, ArrowRecordWriter, ArrowYPBankRecord, IpcFormat};
+#[cfg(feature = "parquet")]
+pub(crate) use parquet_format::{ParquetRecordReader, ParquetRecordWriter, ParquetYPBankRecord};
+#[cfg(feature = "sqlite")]
+pub(crate) use sqlite_format::{SqliteRecordReader, SqliteRecordWriter, SqliteYPBankRecord};
+#[cfg(feature = "xlsx")]
+pub(crate) use xlsx_format::{XlsxRecordWriter, XlsxYPBankRecord};
+
 pub(crate) trait BankRecord {
     fn write_to<W: std::io::Write>(&self, writer: &mut W) -> Result<()>;
     fn push(&mut self, value: Message);
@@ -23,10 +78,13 @@ pub(crate) trait BankRecord {
 #[derive(Debug, Clone, Copy, PartialEq, EnumDisplay, serde::Deserialize, serde::Serialize)]
 #[enum_display(case = "Upper")]
 pub enum TypeTransaction {
+    /// Зачисление
     #[serde(rename = "DEPOSIT")]
     Deposit,
+    /// Перевод
     #[serde(rename = "TRANSFER")]
     Transfer,
+    /// Списание
     #[serde(rename = "WITHDRAWAL")]
     Withdrawal,
 }
@@ -35,23 +93,24 @@ pub enum TypeTransaction {
 #[derive(Debug, Clone, Copy, PartialEq, EnumDisplay, serde::Deserialize, serde::Serialize)]
 #[enum_display(case = "Upper")]
 pub enum StatusTransaction {
+    /// Выполнена
     #[serde(rename = "SUCCESS")]
     Success,
+    /// Отклонена
     #[serde(rename = "FAILURE")]
     Failure,
+    /// В обработке
     #[serde(rename = "PENDING")]
     Pending,
 }
 
 impl TypeTransaction {
-    fn from_u8(value: u8) -> Result<Self> {
+    fn from_u8(value: u8) -> Option<Self> {
         match value {
-            0 => Ok(TypeTransaction::Deposit),
-            1 => Ok(TypeTransaction::Transfer),
-            2 => Ok(TypeTransaction::Withdrawal),
-            _ => Err(AppError::BinParseError(BinParseError::TransactionError(
-                "Invalid transaction type".to_string(),
-            ))),
+            0 => Some(TypeTransaction::Deposit),
+            1 => Some(TypeTransaction::Transfer),
+            2 => Some(TypeTransaction::Withdrawal),
+            _ => None,
         }
     }
 
@@ -65,14 +124,12 @@ impl TypeTransaction {
 }
 
 impl StatusTransaction {
-    fn from_u8(value: u8) -> Result<Self> {
+    fn from_u8(value: u8) -> Option<Self> {
         match value {
-            0 => Ok(StatusTransaction::Success),
-            1 => Ok(StatusTransaction::Failure),
-            2 => Ok(StatusTransaction::Pending),
-            _ => Err(AppError::BinParseError(BinParseError::TransactionError(
-                "Invalid transaction status".to_string(),
-            ))),
+            0 => Some(StatusTransaction::Success),
+            1 => Some(StatusTransaction::Failure),
+            2 => Some(StatusTransaction::Pending),
+            _ => None,
         }
     }
 
@@ -85,6 +142,53 @@ impl StatusTransaction {
     }
 }
 
+/// Правило записи отрицательной суммы в форматы, где AMOUNT неотрицательный (TXT, CSV, camt.053, MT940, OFX, QIF, BAI2)
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum AmountPolicy {
+    /// Ошибка записи
+    #[default]
+    Reject,
+    /// Запись модуля суммы, знак теряется
+    Absolute,
+}
+
+impl AmountPolicy {
+    /// Приведение суммы сообщения к формату без знака
+    pub(crate) fn apply<'a>(
+        self,
+        message: &'a Message,
+        format: &DataFormat,
+    ) -> Result<Cow<'a, Message>> {
+        if message.amount >= 0 {
+            return Ok(Cow::Borrowed(message));
+        }
+        match self {
+            AmountPolicy::Reject => Err(message.negative_amount_error(format)),
+            // Модуль i64::MIN не представим в i64
+            AmountPolicy::Absolute => match message.amount.checked_abs() {
+                Some(amount) => Ok(Cow::Owned(Message {
+                    amount,
+                    ..message.clone()
+                })),
+                None => Err(message.negative_amount_error(format)),
+            },
+        }
+    }
+}
+
+/// Получение TX_ID при чтении QIF, где номер транзакции необязателен
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum TxIdStrategy {
+    /// Номер из строки `N`, запись без числового номера - ошибка
+    #[default]
+    Number,
+    /// Номера по порядку прочитанных записей, начиная с заданного
+    Sequence(u64),
+    /// Хеш счетов отправителя и получателя, даты, суммы, описания и номера: повторное чтение
+    /// того же файла дает те же TX_ID
+    Hash,
+}
+
 /// Структура сообщения
 #[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize, Clone)]
 pub struct Message {
@@ -104,9 +208,9 @@ pub struct Message {
     #[serde(rename = "TO_USER_ID")]
     pub to_user_id: u64,
 
-    /// AMOUNT
+    /// AMOUNT, положительное для зачислений, отрицательное для списаний
     #[serde(rename = "AMOUNT")]
-    pub amount: u64,
+    pub amount: i64,
 
     /// TIMESTAMP
     #[serde(rename = "TIMESTAMP")]
@@ -116,7 +220,27 @@ pub struct Message {
     #[serde(rename = "STATUS")]
     pub status: StatusTransaction,
 
-    /// DESCRIPTION
+    /// DESCRIPTION, текст без кавычек и экранирования формата
     #[serde(rename = "DESCRIPTION")]
     pub description: String,
+
+    /// CURRENCY, необязательный код валюты ISO 4217
+    #[serde(rename = "CURRENCY", default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<Currency>,
+}
+
+impl Message {
+    /// Сумма с валютой, если валюта указана
+    pub fn money(&self) -> Option<Money> {
+        self.currency
+            .map(|currency| Money::new(self.amount, currency))
+    }
+
+    fn negative_amount_error(&self, format: &DataFormat) -> AppError {
+        AppError::NegativeAmountError {
+            tx_id: self.tx_id,
+            amount: self.amount,
+            format: *format,
+        }
+    }
 }
@@ -0,0 +1,714 @@
+use std::{
+    io::{BufReader, Cursor},
+    sync::Arc,
+};
+
+use arrow_array::{
+    Array, ArrayRef, DictionaryArray, Int8Array, Int64Array, RecordBatch, StringArray,
+    TimestampMillisecondArray, UInt64Array,
+    cast::AsArray,
+    types::{Int8Type, Int64Type, TimestampMillisecondType, UInt64Type},
+};
+use arrow_ipc::{
+    reader::{FileReader, StreamReader},
+    writer::{FileWriter, StreamWriter},
+};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef, TimeUnit};
+
+use crate::{
+    DataFormat,
+    convertor::{BankRecord, Currency, Message, StatusTransaction, TypeTransaction},
+    error::{AppError, Location, ParseError, Result},
+};
+
+// Количество строк в одном RecordBatch при записи и чтении файлов
+pub(crate) const BATCH_SIZE: usize = 1024;
+
+// Словари TX_TYPE и STATUS в порядке кодов бинарного формата
+const TX_TYPES: [&str; 3] = ["DEPOSIT", "TRANSFER", "WITHDRAWAL"];
+const STATUSES: [&str; 3] = ["SUCCESS", "FAILURE", "PENDING"];
+
+/// Схема RecordBatch записей YPBank: по колонке на поле записи с его именем,
+/// TX_TYPE и STATUS - строки со словарем, TIMESTAMP - миллисекунды UTC
+pub fn record_batch_schema() -> SchemaRef {
+    let dictionary = DataType::Dictionary(Box::new(DataType::Int8), Box::new(DataType::Utf8));
+    Arc::new(Schema::new(vec![
+        Field::new("TX_ID", DataType::UInt64, false),
+        Field::new("TX_TYPE", dictionary.clone(), false),
+        Field::new("FROM_USER_ID", DataType::UInt64, false),
+        Field::new("TO_USER_ID", DataType::UInt64, false),
+        Field::new("AMOUNT", DataType::Int64, false),
+        Field::new(
+            "TIMESTAMP",
+            DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+            false,
+        ),
+        Field::new("STATUS", dictionary, false),
+        Field::new("DESCRIPTION", DataType::Utf8, false),
+        Field::new("CURRENCY", DataType::Utf8, true),
+    ]))
+}
+
+/// RecordBatch со схемой [`record_batch_schema`] из записей
+pub fn to_record_batch(messages: &[Message]) -> Result<RecordBatch> {
+    let mut batch = Batch::default();
+    for message in messages {
+        batch.push(message, DataFormat::ARROW)?;
+    }
+    Ok(batch.into_record_batch()?)
+}
+
+/// Записи из RecordBatch, первая строка, которая не является записью, - ошибка.
+/// Колонки ищутся по имени, типы приводятся к [`record_batch_schema`].
+pub fn from_record_batch(batch: &RecordBatch) -> Result<Vec<Message>> {
+    let mut rows = Rows::default();
+    rows.set(batch)?;
+    std::iter::from_fn(|| rows.next(AppError::ArrowRecordError)).collect()
+}
+
+/// Колонки RecordBatch, приведенные к типам схемы
+struct Columns {
+    tx_id: UInt64Array,
+    tx_type: StringArray,
+    from_user_id: UInt64Array,
+    to_user_id: UInt64Array,
+    amount: Int64Array,
+    timestamp: TimestampMillisecondArray,
+    status: StringArray,
+    description: StringArray,
+    currency: Option<StringArray>,
+}
+
+impl Columns {
+    /// Колонки ищутся по имени, типы приводятся к схеме YPBank: файлы других систем
+    /// могут хранить TX_TYPE без словаря, счета в Int64, а время в микросекундах
+    fn new(batch: &RecordBatch) -> std::result::Result<Self, ArrowError> {
+        let column = |name: &str, data_type: &DataType| -> std::result::Result<ArrayRef, _> {
+            let array = batch
+                .column_by_name(name)
+                .ok_or_else(|| ArrowError::SchemaError(format!("column {} is missing", name)))?;
+            arrow_cast::cast(array, data_type).map_err(|e| {
+                ArrowError::SchemaError(format!(
+                    "column {} of type {} cannot be read as {}: {}",
+                    name,
+                    array.data_type(),
+                    data_type,
+                    e
+                ))
+            })
+        };
+        let timestamp = DataType::Timestamp(TimeUnit::Millisecond, None);
+        Ok(Self {
+            tx_id: column("TX_ID", &DataType::UInt64)?
+                .as_primitive::<UInt64Type>()
+                .clone(),
+            tx_type: column("TX_TYPE", &DataType::Utf8)?.as_string().clone(),
+            from_user_id: column("FROM_USER_ID", &DataType::UInt64)?
+                .as_primitive::<UInt64Type>()
+                .clone(),
+            to_user_id: column("TO_USER_ID", &DataType::UInt64)?
+                .as_primitive::<UInt64Type>()
+                .clone(),
+            amount: column("AMOUNT", &DataType::Int64)?
+                .as_primitive::<Int64Type>()
+                .clone(),
+            timestamp: column("TIMESTAMP", &timestamp)?
+                .as_primitive::<TimestampMillisecondType>()
+                .clone(),
+            status: column("STATUS", &DataType::Utf8)?.as_string().clone(),
+            description: column("DESCRIPTION", &DataType::Utf8)?.as_string().clone(),
+            // CURRENCY необязательна, как и в остальных форматах
+            currency: match batch.column_by_name("CURRENCY") {
+                Some(_) => Some(column("CURRENCY", &DataType::Utf8)?.as_string().clone()),
+                None => None,
+            },
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.tx_id.len()
+    }
+
+    /// Запись строки `row`, при ошибке возвращаются имя колонки и сообщение
+    fn message(&self, row: usize) -> std::result::Result<Message, (&'static str, String)> {
+        // Значение null или не приведенное к типу схемы (отрицательный счет и т.п.)
+        fn value<A: Array>(
+            array: &A,
+            row: usize,
+            name: &'static str,
+        ) -> std::result::Result<(), (&'static str, String)> {
+            if array.is_null(row) {
+                return Err((name, "value is null or out of range".to_string()));
+            }
+            Ok(())
+        }
+
+        value(&self.tx_id, row, "TX_ID")?;
+        value(&self.tx_type, row, "TX_TYPE")?;
+        value(&self.from_user_id, row, "FROM_USER_ID")?;
+        value(&self.to_user_id, row, "TO_USER_ID")?;
+        value(&self.amount, row, "AMOUNT")?;
+        value(&self.timestamp, row, "TIMESTAMP")?;
+        value(&self.status, row, "STATUS")?;
+        value(&self.description, row, "DESCRIPTION")?;
+
+        let tx_type = match self.tx_type.value(row) {
+            "DEPOSIT" => TypeTransaction::Deposit,
+            "TRANSFER" => TypeTransaction::Transfer,
+            "WITHDRAWAL" => TypeTransaction::Withdrawal,
+            value => return Err(("TX_TYPE", format!("unknown TX_TYPE {:?}", value))),
+        };
+        let status = match self.status.value(row) {
+            "SUCCESS" => StatusTransaction::Success,
+            "FAILURE" => StatusTransaction::Failure,
+            "PENDING" => StatusTransaction::Pending,
+            value => return Err(("STATUS", format!("unknown STATUS {:?}", value))),
+        };
+        let timestamp = u64::try_from(self.timestamp.value(row)).map_err(|_| {
+            (
+                "TIMESTAMP",
+                format!("TIMESTAMP {} is before 1970", self.timestamp.value(row)),
+            )
+        })?;
+        let currency = match &self.currency {
+            Some(currency) if currency.is_valid(row) => Some(
+                Currency::from_code(currency.value(row))
+                    .map_err(|e| ("CURRENCY", e.to_string()))?,
+            ),
+            _ => None,
+        };
+
+        Ok(Message {
+            tx_id: self.tx_id.value(row),
+            tx_type,
+            from_user_id: self.from_user_id.value(row),
+            to_user_id: self.to_user_id.value(row),
+            amount: self.amount.value(row),
+            timestamp,
+            status,
+            description: self.description.value(row).to_string(),
+            currency,
+        })
+    }
+}
+
+/// Построчное чтение записей из очередного RecordBatch
+#[derive(Default)]
+pub(crate) struct Rows {
+    pub(crate) source: Option<String>,
+    columns: Option<Box<Columns>>,
+    // Номер строки в текущем RecordBatch
+    row: usize,
+    // Количество прочитанных строк
+    record: u64,
+}
+
+impl Rows {
+    /// Переход к следующему RecordBatch, ошибка схемы завершает чтение
+    pub(crate) fn set(&mut self, batch: &RecordBatch) -> Result<()> {
+        self.columns = Some(Box::new(Columns::new(batch)?));
+        self.row = 0;
+        Ok(())
+    }
+
+    /// Следующая запись текущего RecordBatch, `error` - ошибка строки формата
+    pub(crate) fn next(
+        &mut self,
+        error: fn(Box<ParseError>) -> AppError,
+    ) -> Option<Result<Message>> {
+        let columns = self.columns.as_ref().filter(|c| self.row < c.len())?;
+        let result = columns.message(self.row).map_err(|(field, message)| {
+            error(Box::new(ParseError {
+                location: Location {
+                    source: self.source.clone(),
+                    record: Some(self.record + 1),
+                    field: Some(field.to_string()),
+                    ..Location::default()
+                },
+                message,
+            }))
+        });
+        self.row += 1;
+        self.record += 1;
+        Some(result)
+    }
+}
+
+/// Колонки записей, накопленных для следующего RecordBatch
+#[derive(Default)]
+pub(crate) struct Batch {
+    tx_id: Vec<u64>,
+    tx_type: Vec<i8>,
+    from_user_id: Vec<u64>,
+    to_user_id: Vec<u64>,
+    amount: Vec<i64>,
+    timestamp: Vec<i64>,
+    status: Vec<i8>,
+    description: Vec<String>,
+    currency: Vec<Option<String>>,
+}
+
+impl Batch {
+    /// Время Arrow хранится в Int64, поэтому TIMESTAMP больше i64::MAX - ошибка записи
+    pub(crate) fn push(&mut self, message: &Message, format: DataFormat) -> Result<()> {
+        let timestamp = i64::try_from(message.timestamp).map_err(|_| AppError::MappingError {
+            format,
+            tx_id: message.tx_id,
+            reason: format!(
+                "TIMESTAMP {} does not fit a signed 64-bit timestamp",
+                message.timestamp
+            ),
+        })?;
+        self.tx_id.push(message.tx_id);
+        self.tx_type.push(message.tx_type.to_u8() as i8);
+        self.from_user_id.push(message.from_user_id);
+        self.to_user_id.push(message.to_user_id);
+        self.amount.push(message.amount);
+        self.timestamp.push(timestamp);
+        self.status.push(message.status.to_u8() as i8);
+        self.description.push(message.description.clone());
+        self.currency
+            .push(message.currency.map(|currency| currency.code().to_string()));
+        Ok(())
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.tx_id.len()
+    }
+
+    pub(crate) fn into_record_batch(self) -> std::result::Result<RecordBatch, ArrowError> {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(UInt64Array::from(self.tx_id)),
+            Arc::new(dictionary(self.tx_type, &TX_TYPES)?),
+            Arc::new(UInt64Array::from(self.from_user_id)),
+            Arc::new(UInt64Array::from(self.to_user_id)),
+            Arc::new(Int64Array::from(self.amount)),
+            Arc::new(TimestampMillisecondArray::from(self.timestamp).with_timezone("UTC")),
+            Arc::new(dictionary(self.status, &STATUSES)?),
+            Arc::new(StringArray::from(self.description)),
+            Arc::new(StringArray::from(self.currency)),
+        ];
+        RecordBatch::try_new(record_batch_schema(), columns)
+    }
+}
+
+/// Колонка со словарем из всех значений перечисления. Файл IPC допускает один словарь
+/// для колонки во всех RecordBatch, поэтому словарь не зависит от записей.
+fn dictionary(
+    keys: Vec<i8>,
+    values: &[&str],
+) -> std::result::Result<DictionaryArray<Int8Type>, ArrowError> {
+    DictionaryArray::try_new(
+        Int8Array::from(keys),
+        Arc::new(StringArray::from(values.to_vec())),
+    )
+}
+
+/// Вариант Arrow IPC
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IpcFormat {
+    /// Файл `.arrow`: схема, RecordBatch и оглавление в конце для произвольного доступа
+    File,
+    /// Поток `.arrows`: схема и RecordBatch без оглавления, читается последовательно
+    Stream,
+}
+
+impl IpcFormat {
+    fn data_format(self) -> DataFormat {
+        match self {
+            IpcFormat::File => DataFormat::ARROW,
+            IpcFormat::Stream => DataFormat::ARROWSTREAM,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ArrowYPBankRecord {
+    data: Vec<Message>,
+    ipc: IpcFormat,
+}
+
+impl ArrowYPBankRecord {
+    pub fn from_read<R: std::io::Read>(r: R, ipc: IpcFormat) -> Result<Self> {
+        let data = ArrowRecordReader::new(r, ipc).collect::<Result<Vec<_>>>()?;
+        Ok(Self { data, ipc })
+    }
+
+    pub fn new(ipc: IpcFormat) -> Self {
+        Self {
+            data: Vec::new(),
+            ipc,
+        }
+    }
+}
+
+enum IpcReader<R: std::io::Read> {
+    File(FileReader<Cursor<Vec<u8>>>),
+    Stream(StreamReader<BufReader<R>>),
+}
+
+impl<R: std::io::Read> Iterator for IpcReader<R> {
+    type Item = std::result::Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            IpcReader::File(reader) => reader.next(),
+            IpcReader::Stream(reader) => reader.next(),
+        }
+    }
+}
+
+/// Читатель Arrow IPC. Поток читается по одному RecordBatch, файл - целиком в память,
+/// так как его оглавление находится в конце.
+pub struct ArrowRecordReader<R: std::io::Read> {
+    input: Option<R>,
+    ipc: IpcFormat,
+    finished: bool,
+    batches: Option<Box<IpcReader<R>>>,
+    rows: Rows,
+}
+
+impl<R: std::io::Read> ArrowRecordReader<R> {
+    pub fn new(r: R, ipc: IpcFormat) -> Self {
+        Self {
+            input: Some(r),
+            ipc,
+            finished: false,
+            batches: None,
+            rows: Rows::default(),
+        }
+    }
+
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.rows.source = Some(source.into());
+        self
+    }
+
+    fn open(&self, mut input: R) -> Result<IpcReader<R>> {
+        Ok(match self.ipc {
+            IpcFormat::File => {
+                let mut data = Vec::new();
+                input.read_to_end(&mut data)?;
+                IpcReader::File(FileReader::try_new(Cursor::new(data), None)?)
+            }
+            IpcFormat::Stream => IpcReader::Stream(StreamReader::try_new_buffered(input, None)?),
+        })
+    }
+
+    /// Переход к следующему RecordBatch, `false` в конце входа
+    fn next_batch(&mut self) -> Result<bool> {
+        if let Some(input) = self.input.take() {
+            self.batches = Some(Box::new(self.open(input)?));
+        }
+        match self.batches.as_mut().and_then(|batches| batches.next()) {
+            Some(batch) => {
+                self.rows.set(&batch?)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+impl<R: std::io::Read> Iterator for ArrowRecordReader<R> {
+    type Item = Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            if let Some(result) = self.rows.next(AppError::ArrowRecordError) {
+                return Some(result);
+            }
+            // Ошибка файла или схемы завершает чтение
+            match self.next_batch() {
+                Ok(true) => {}
+                Ok(false) => self.finished = true,
+                Err(e) => {
+                    self.finished = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+enum IpcWriter<W: std::io::Write> {
+    File(FileWriter<W>),
+    Stream(StreamWriter<W>),
+}
+
+/// Потоковый писатель Arrow IPC. Записи накапливаются и пишутся по одному RecordBatch.
+pub struct ArrowRecordWriter<W: std::io::Write> {
+    // Выходной поток до создания писателя IPC, создание пишет схему и может завершиться ошибкой
+    output: Option<W>,
+    ipc: IpcFormat,
+    writer: Option<Box<IpcWriter<W>>>,
+    batch: Box<Batch>,
+}
+
+impl<W: std::io::Write> ArrowRecordWriter<W> {
+    pub fn new(output: W, ipc: IpcFormat) -> Self {
+        Self {
+            output: Some(output),
+            ipc,
+            writer: None,
+            batch: Box::default(),
+        }
+    }
+
+    pub fn write_message(&mut self, message: &Message) -> Result<()> {
+        self.batch.push(message, self.ipc.data_format())?;
+        if self.batch.len() >= BATCH_SIZE {
+            self.write_batch()?;
+        }
+        Ok(())
+    }
+
+    fn ipc_writer(&mut self) -> Result<&mut IpcWriter<W>> {
+        if let Some(output) = self.output.take() {
+            let schema = record_batch_schema();
+            self.writer = Some(Box::new(match self.ipc {
+                IpcFormat::File => IpcWriter::File(FileWriter::try_new(output, &schema)?),
+                IpcFormat::Stream => IpcWriter::Stream(StreamWriter::try_new(output, &schema)?),
+            }));
+        }
+        Ok(self.writer.as_mut().expect("writer is created from output"))
+    }
+
+    fn write_batch(&mut self) -> Result<()> {
+        let batch = std::mem::take(&mut self.batch).into_record_batch()?;
+        match self.ipc_writer()? {
+            IpcWriter::File(writer) => writer.write(&batch)?,
+            IpcWriter::Stream(writer) => writer.write(&batch)?,
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W> {
+        if self.batch.len() > 0 {
+            self.write_batch()?;
+        }
+        // Схема пишется и для пустого входа
+        self.ipc_writer()?;
+        let writer = self.writer.take().expect("writer is created above");
+        let mut output = match *writer {
+            IpcWriter::File(mut writer) => {
+                writer.finish()?;
+                writer.into_inner()?
+            }
+            IpcWriter::Stream(mut writer) => {
+                writer.finish()?;
+                writer.into_inner()?
+            }
+        };
+        output.flush()?;
+        Ok(output)
+    }
+}
+
+impl BankRecord for ArrowYPBankRecord {
+    fn write_to<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        let mut writer = ArrowRecordWriter::new(writer, self.ipc);
+        for message in &self.data {
+            writer.write_message(message)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+
+    fn push(&mut self, value: Message) {
+        self.data.push(value);
+    }
+
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn pop(&mut self) -> Option<Message> {
+        self.data.pop()
+    }
+
+    fn iter(&self) -> std::slice::Iter<'_, Message> {
+        self.data.iter()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convertor::test_records::{pending_transfer, pending_transfers};
+
+    #[test]
+    fn test_record_batch() {
+        let messages = pending_transfers();
+        let batch = to_record_batch(&messages).unwrap();
+        assert_eq!(batch.schema(), record_batch_schema());
+        assert_eq!(batch.num_rows(), 2500);
+        assert_eq!(
+            batch
+                .column_by_name("TX_TYPE")
+                .unwrap()
+                .as_dictionary::<Int8Type>()
+                .values()
+                .len(),
+            3
+        );
+        assert_eq!(from_record_batch(&batch).unwrap(), messages);
+
+        let mut message = pending_transfer(7);
+        message.timestamp = u64::MAX;
+        assert_eq!(
+            to_record_batch(&[message]).unwrap_err().to_string(),
+            "ARROW mapping error: TX_ID 7: TIMESTAMP 18446744073709551615 does not fit a signed 64-bit timestamp"
+        );
+    }
+
+    #[test]
+    fn test_read_other_schema() {
+        // RecordBatch другой системы: строки без словаря, Int64 вместо UInt64,
+        // время в микросекундах, без колонки CURRENCY
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("TX_ID", DataType::Int64, true),
+            Field::new("TX_TYPE", DataType::Utf8, true),
+            Field::new("FROM_USER_ID", DataType::Int64, true),
+            Field::new("TO_USER_ID", DataType::Int64, true),
+            Field::new("AMOUNT", DataType::Int64, true),
+            Field::new(
+                "TIMESTAMP",
+                DataType::Timestamp(TimeUnit::Microsecond, None),
+                true,
+            ),
+            Field::new("STATUS", DataType::Utf8, true),
+            Field::new("DESCRIPTION", DataType::Utf8, true),
+        ]));
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(vec![
+                Some(1),
+                Some(2),
+                Some(3),
+                Some(4),
+                None,
+            ])),
+            Arc::new(StringArray::from(vec![
+                "DEPOSIT", "LOAN", "DEPOSIT", "DEPOSIT", "DEPOSIT",
+            ])),
+            Arc::new(Int64Array::from(vec![0, 0, -5, 0, 0])),
+            Arc::new(Int64Array::from(vec![7, 7, 7, 7, 7])),
+            Arc::new(Int64Array::from(vec![100, 100, 100, 100, 100])),
+            Arc::new(arrow_array::TimestampMicrosecondArray::from(vec![
+                1633036800123456,
+                0,
+                0,
+                -1000,
+                0,
+            ])),
+            Arc::new(StringArray::from(vec!["SUCCESS"; 5])),
+            Arc::new(StringArray::from(vec!["a"; 5])),
+        ];
+        let batch = RecordBatch::try_new(schema.clone(), columns).unwrap();
+        let mut writer = StreamWriter::try_new(Vec::new(), &schema).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+        let data = writer.into_inner().unwrap();
+
+        let results = ArrowRecordReader::new(data.as_slice(), IpcFormat::Stream)
+            .with_source("lake.arrows")
+            .collect::<Vec<_>>();
+        assert_eq!(
+            results[0].as_ref().unwrap(),
+            &Message {
+                tx_id: 1,
+                tx_type: TypeTransaction::Deposit,
+                from_user_id: 0,
+                to_user_id: 7,
+                amount: 100,
+                timestamp: 1633036800123,
+                status: StatusTransaction::Success,
+                description: "a".to_string(),
+                currency: None,
+            }
+        );
+        let errors = results[1..]
+            .iter()
+            .map(|result| result.as_ref().unwrap_err())
+            .collect::<Vec<_>>();
+        assert!(errors.iter().all(|e| e.is_record_error()));
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [
+                "lake.arrows: record 2: TX_TYPE: unknown TX_TYPE \"LOAN\"",
+                "lake.arrows: record 3: FROM_USER_ID: value is null or out of range",
+                "lake.arrows: record 4: TIMESTAMP: TIMESTAMP -1 is before 1970",
+                "lake.arrows: record 5: TX_ID: value is null or out of range",
+            ]
+        );
+
+        let err = from_record_batch(&batch).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "<input>: record 2: TX_TYPE: unknown TX_TYPE \"LOAN\""
+        );
+    }
+
+    #[test]
+    fn test_ipc_round_trip() {
+        let messages = pending_transfers();
+        for ipc in [IpcFormat::File, IpcFormat::Stream] {
+            let mut writer = ArrowRecordWriter::new(Vec::new(), ipc);
+            for message in &messages {
+                writer.write_message(message).unwrap();
+            }
+            let data = writer.finish().unwrap();
+            let record = ArrowYPBankRecord::from_read(data.as_slice(), ipc).unwrap();
+            assert_eq!(record.iter().cloned().collect::<Vec<_>>(), messages);
+
+            let mut empty = Vec::new();
+            ArrowYPBankRecord::new(ipc).write_to(&mut empty).unwrap();
+            assert!(
+                ArrowYPBankRecord::from_read(empty.as_slice(), ipc)
+                    .unwrap()
+                    .is_empty()
+            );
+        }
+
+        // Файл начинается с метки ARROW1, поток - с маркера продолжения сообщения
+        let mut file = Vec::new();
+        ArrowYPBankRecord::new(IpcFormat::File)
+            .write_to(&mut file)
+            .unwrap();
+        assert!(file.starts_with(b"ARROW1"));
+        assert!(file.ends_with(b"ARROW1"));
+    }
+
+    #[test]
+    fn test_read_errors() {
+        let err = ArrowYPBankRecord::from_read("TX_ID,TX_TYPE\n".as_bytes(), IpcFormat::File)
+            .unwrap_err();
+        assert!(matches!(err, AppError::ArrowError(_)));
+        assert!(!err.is_record_error());
+
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "TX_ID",
+            DataType::UInt64,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(UInt64Array::from(vec![1])) as ArrayRef],
+        )
+        .unwrap();
+        let mut writer = FileWriter::try_new(Vec::new(), &schema).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+        let data = writer.into_inner().unwrap();
+        let err = ArrowYPBankRecord::from_read(data.as_slice(), IpcFormat::File).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Arrow error: Schema error: column TX_TYPE is missing"
+        );
+    }
+}
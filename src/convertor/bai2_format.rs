@@ -0,0 +1,1100 @@
+use std::{collections::VecDeque, io::BufRead};
+
+use crate::{
+    DataFormat,
+    convertor::{
+        AmountPolicy, BankRecord, Currency, Message, StatusTransaction, TypeTransaction,
+        datetime::{self, DateTime},
+        statement::{self, Statement, StatementEntry, Statements, Transfers},
+    },
+    error::{AppError, Location, ParseError, Result},
+    validator::{AccountConsistency, Rule, TransferAccounts},
+};
+
+// Идентификатор отправителя и получателя в заголовках файла и группы
+const BANK_ID: &str = "YPBANK";
+
+// Коды типов BAI2: перевод внутри банка и прочие зачисления и списания
+const BOOK_TRANSFER_CREDIT: u16 = 206;
+const MISC_CREDIT: u16 = 399;
+const BOOK_TRANSFER_DEBIT: u16 = 506;
+const MISC_DEBIT: u16 = 699;
+
+// Валюта по умолчанию, если она не указана ни в счете, ни в группе
+const DEFAULT_CURRENCY: &str = "USD";
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Bai2YPBankRecord {
+    data: Vec<Message>,
+}
+
+impl Bai2YPBankRecord {
+    pub fn from_read<R: std::io::Read>(r: R) -> Result<Self> {
+        let data = Bai2RecordReader::new(r).collect::<Result<Vec<_>>>()?;
+        Ok(Self { data })
+    }
+
+    pub fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+}
+
+/// Логическая запись: физическая строка вместе с продолжениями `88`
+#[derive(Debug)]
+struct Record {
+    line: u64,
+    lines: u64,
+    text: String,
+}
+
+/// Накопленные итоги файла, группы или счета для сверки с записью окончания
+#[derive(Debug, Default)]
+struct Totals {
+    amount: i128,
+    records: u64,
+    // Количество групп в файле или счетов в группе
+    items: u64,
+}
+
+/// Группа `02`: дата и время состояния и валюта по умолчанию
+#[derive(Debug)]
+struct Group {
+    totals: Totals,
+    currency: Option<String>,
+    date: String,
+    time: String,
+}
+
+/// Счет `03` и его транзакции, которые возвращаются после сверки итогов `49`
+#[derive(Debug)]
+struct Account {
+    id: String,
+    currency: Option<String>,
+    totals: Totals,
+    // Сумма записи 16 не разобрана, итог счета сверить нельзя
+    unverifiable: bool,
+    messages: Vec<Result<Message>>,
+}
+
+/// Потоковый читатель BAI2. Транзакции счета возвращаются после проверки его итогов
+/// в записи `49`, итоги группы и файла проверяются записями `98` и `99`.
+pub struct Bai2RecordReader<R: std::io::Read> {
+    lines: std::io::Lines<std::io::BufReader<R>>,
+    finished: bool,
+    source: Option<String>,
+    // Номер последней прочитанной строки
+    line: u64,
+    // Количество прочитанных записей 16
+    record: u64,
+    // Запись, которую могут продолжить следующие строки 88
+    pending: Option<Record>,
+    file: Option<Totals>,
+    group: Option<Group>,
+    account: Option<Account>,
+    // Транзакции счетов с проверенными итогами
+    ready: VecDeque<Result<Message>>,
+    transfers: Transfers,
+}
+
+impl<R: std::io::Read> Bai2RecordReader<R> {
+    pub fn new(r: R) -> Self {
+        Self {
+            lines: std::io::BufReader::new(r).lines(),
+            finished: false,
+            source: None,
+            line: 0,
+            record: 0,
+            pending: None,
+            file: None,
+            group: None,
+            account: None,
+            ready: VecDeque::new(),
+            transfers: Transfers::default(),
+        }
+    }
+
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Чтение одной строки, завершенная логическая запись обрабатывается
+    fn read_line(&mut self) -> Result<()> {
+        let Some(line) = self.lines.next() else {
+            self.finished = true;
+            if let Some(record) = self.pending.take() {
+                self.process(record)?;
+            }
+            if self.file.is_some() {
+                return Err(structure_error(
+                    &self.source,
+                    self.line,
+                    "unexpected end of file, 99 file trailer is missing".to_string(),
+                ));
+            }
+            return Ok(());
+        };
+        let line = line?;
+        self.line += 1;
+        // Записи фиксированной длины дополняются пробелами
+        let line = line.trim_end();
+        if line.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(continuation) = line.strip_prefix("88,") {
+            let Some(record) = &mut self.pending else {
+                return Err(structure_error(
+                    &self.source,
+                    self.line,
+                    "88 continuation without a preceding record".to_string(),
+                ));
+            };
+            record.lines += 1;
+            // Поля продолжаются после '/', текст 16 продолжается без разделителя
+            match record.text.strip_suffix('/') {
+                Some(text) => record.text = format!("{},{}", text, continuation),
+                None => record.text.push_str(continuation),
+            }
+            return Ok(());
+        }
+
+        let record = Record {
+            line: self.line,
+            lines: 1,
+            text: line.to_string(),
+        };
+        match self.pending.replace(record) {
+            Some(record) => self.process(record),
+            None => Ok(()),
+        }
+    }
+
+    fn process(&mut self, record: Record) -> Result<()> {
+        let (code, body) = record
+            .text
+            .split_once(',')
+            .unwrap_or((record.text.as_str(), ""));
+        let source = self.source.clone();
+        let error = |message: &str| structure_error(&source, record.line, message.to_string());
+        let fields = body
+            .strip_suffix('/')
+            .unwrap_or(body)
+            .split(',')
+            .collect::<Vec<_>>();
+        let field = |i: usize| fields.get(i).map_or("", |field| field.trim());
+
+        match code {
+            "01" => {
+                if self.file.is_some() {
+                    return Err(error(
+                        "unexpected 01 file header, 99 file trailer is missing",
+                    ));
+                }
+                if field(7) != "2" {
+                    return Err(error(&format!(
+                        "BAI version {:?} is not supported, expected 2",
+                        field(7)
+                    )));
+                }
+                self.file = Some(Totals {
+                    records: record.lines,
+                    ..Totals::default()
+                });
+            }
+            "02" => {
+                if self.file.is_none() {
+                    return Err(error("02 group header outside of a file"));
+                }
+                if self.group.is_some() {
+                    return Err(error(
+                        "unexpected 02 group header, 98 group trailer is missing",
+                    ));
+                }
+                self.group = Some(Group {
+                    totals: Totals {
+                        records: record.lines,
+                        ..Totals::default()
+                    },
+                    currency: Some(field(5).to_string()).filter(|c| !c.is_empty()),
+                    date: field(3).to_string(),
+                    time: field(4).to_string(),
+                });
+            }
+            "03" => {
+                if self.group.is_none() {
+                    return Err(error("03 account identifier outside of a group"));
+                }
+                if self.account.is_some() {
+                    return Err(error(
+                        "unexpected 03 account identifier, 49 account trailer is missing",
+                    ));
+                }
+                // Сводные суммы: код типа, сумма, количество, тип доступности средств
+                let mut amount = 0;
+                let mut i = 2;
+                while i < fields.len() {
+                    if !field(i + 1).is_empty() {
+                        amount += parse_total(field(i + 1))
+                            .ok_or_else(|| error(&format!("invalid amount {:?}", field(i + 1))))?;
+                    }
+                    let extra = funds_fields(field(i + 3), field(i + 4))
+                        .ok_or_else(|| error(&format!("invalid funds type {:?}", field(i + 3))))?;
+                    i += 4 + extra;
+                }
+                self.account = Some(Account {
+                    id: field(0).to_string(),
+                    currency: Some(field(1).to_string()).filter(|c| !c.is_empty()),
+                    totals: Totals {
+                        amount,
+                        records: record.lines,
+                        items: 0,
+                    },
+                    unverifiable: false,
+                    messages: Vec::new(),
+                });
+            }
+            "16" => {
+                let (Some(account), Some(group)) = (&mut self.account, &self.group) else {
+                    return Err(error("16 transaction detail outside of an account"));
+                };
+                self.record += 1;
+                account.totals.records += record.lines;
+                match parse_total(field(1)) {
+                    Some(amount) => account.totals.amount += amount,
+                    None => account.unverifiable = true,
+                }
+                let location = Location {
+                    source: self.source.clone(),
+                    line: Some(record.line),
+                    record: Some(self.record),
+                    ..Location::default()
+                };
+                let message = parse_detail(body, account, group, &location)
+                    .map_err(|e| AppError::Bai2ParseError(Box::new(e)));
+                account.messages.push(message);
+            }
+            "49" => {
+                let Some(mut account) = self.account.take() else {
+                    return Err(error("49 account trailer outside of an account"));
+                };
+                account.totals.records += record.lines;
+                let total = parse_total(field(0))
+                    .ok_or_else(|| error(&format!("invalid control total {:?}", field(0))))?;
+                if !account.unverifiable && total != account.totals.amount {
+                    return Err(error(&format!(
+                        "account {} control total {} does not match the sum of amounts {}",
+                        account.id, total, account.totals.amount
+                    )));
+                }
+                check_count(field(1), account.totals.records, "records").map_err(|e| error(&e))?;
+                if let Some(group) = &mut self.group {
+                    group.totals.amount += total;
+                    group.totals.items += 1;
+                    group.totals.records += account.totals.records;
+                }
+                self.ready.extend(account.messages);
+            }
+            "98" => {
+                if self.account.is_some() {
+                    return Err(error(
+                        "unexpected 98 group trailer, 49 account trailer is missing",
+                    ));
+                }
+                let Some(mut group) = self.group.take() else {
+                    return Err(error("98 group trailer outside of a group"));
+                };
+                group.totals.records += record.lines;
+                let total = parse_total(field(0))
+                    .ok_or_else(|| error(&format!("invalid control total {:?}", field(0))))?;
+                if total != group.totals.amount {
+                    return Err(error(&format!(
+                        "group control total {} does not match the sum of account totals {}",
+                        total, group.totals.amount
+                    )));
+                }
+                check_count(field(1), group.totals.items, "accounts").map_err(|e| error(&e))?;
+                check_count(field(2), group.totals.records, "records").map_err(|e| error(&e))?;
+                if let Some(file) = &mut self.file {
+                    file.amount += total;
+                    file.items += 1;
+                    file.records += group.totals.records;
+                }
+            }
+            "99" => {
+                if self.group.is_some() {
+                    return Err(error(
+                        "unexpected 99 file trailer, 98 group trailer is missing",
+                    ));
+                }
+                let Some(mut file) = self.file.take() else {
+                    return Err(error("99 file trailer outside of a file"));
+                };
+                file.records += record.lines;
+                let total = parse_total(field(0))
+                    .ok_or_else(|| error(&format!("invalid control total {:?}", field(0))))?;
+                if total != file.amount {
+                    return Err(error(&format!(
+                        "file control total {} does not match the sum of group totals {}",
+                        total, file.amount
+                    )));
+                }
+                check_count(field(1), file.items, "groups").map_err(|e| error(&e))?;
+                check_count(field(2), file.records, "records").map_err(|e| error(&e))?;
+            }
+            _ => return Err(error(&format!("unknown record type {:?}", code))),
+        }
+        Ok(())
+    }
+}
+
+impl<R: std::io::Read> Iterator for Bai2RecordReader<R> {
+    type Item = Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.ready.pop_front() {
+                Some(Ok(message)) if self.transfers.is_repeated(&message) => continue,
+                Some(result) => return Some(result),
+                None => {}
+            }
+            if self.finished {
+                return None;
+            }
+            // После ошибки структуры или итогов содержимое файла недостоверно
+            if let Err(e) = self.read_line() {
+                self.finished = true;
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+fn structure_error(source: &Option<String>, line: u64, message: String) -> AppError {
+    AppError::Bai2StructureError(Box::new(ParseError {
+        location: Location {
+            source: source.clone(),
+            line: Some(line),
+            ..Location::default()
+        },
+        message,
+    }))
+}
+
+/// Разбор записи 16 в запись YPBank
+fn parse_detail(
+    body: &str,
+    account: &Account,
+    group: &Group,
+    location: &Location,
+) -> std::result::Result<Message, ParseError> {
+    let error = |field: &str, message: String| ParseError {
+        location: Location {
+            field: Some(field.to_string()),
+            ..location.clone()
+        },
+        message,
+    };
+
+    // Текст занимает остаток записи и может содержать запятые
+    let mut rest = Some(body);
+    let code = next_field(&mut rest);
+    let amount = next_field(&mut rest);
+    let funds = next_field(&mut rest);
+    let mut value_date = None;
+    match funds {
+        "V" => value_date = Some((next_field(&mut rest), next_field(&mut rest))),
+        "S" | "D" => {
+            let extra = funds_fields(
+                funds,
+                rest.map_or("", |rest| rest.split(',').next().unwrap_or("")),
+            )
+            .ok_or_else(|| error("funds type", format!("invalid funds type {:?}", funds)))?;
+            for _ in 0..extra {
+                next_field(&mut rest);
+            }
+        }
+        _ => {}
+    }
+    let bank_reference = next_field(&mut rest);
+    let customer_reference = next_field(&mut rest);
+    // Текст, начинающийся с '/', означает его отсутствие
+    let text = rest
+        .filter(|text| !text.starts_with('/'))
+        .unwrap_or_default();
+
+    let code = code
+        .parse::<u16>()
+        .ok()
+        .filter(|code| (100..700).contains(code))
+        .ok_or_else(|| {
+            error(
+                "type code",
+                format!("type code {:?} is not a credit or debit detail", code),
+            )
+        })?;
+    let credit = code < 400;
+
+    if amount.is_empty() || !amount.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(error("amount", format!("invalid amount {:?}", amount)));
+    }
+    let amount = amount
+        .parse::<i64>()
+        .map_err(|_| error("amount", format!("invalid amount {:?}", amount)))?;
+
+    let (date, time) = value_date.unwrap_or((&group.date, &group.time));
+    let timestamp = datetime::parse_yymmdd(date)
+        .zip(parse_time(time))
+        .map(|(date, time)| date + time)
+        .ok_or_else(|| {
+            error(
+                "date",
+                format!("invalid date and time {:?} {:?}", date, time),
+            )
+        })?;
+
+    let tx_id = bank_reference.parse::<u64>().map_err(|_| {
+        error(
+            "bank reference",
+            format!("invalid integer {:?}", bank_reference),
+        )
+    })?;
+
+    let account_id = account.id.parse::<u64>().map_err(|_| {
+        error(
+            "account",
+            format!("account {:?} is not a YPBank user id", account.id),
+        )
+    })?;
+
+    let code_currency = account
+        .currency
+        .as_deref()
+        .or(group.currency.as_deref())
+        .unwrap_or(DEFAULT_CURRENCY);
+    let currency = Currency::from_code(code_currency)
+        .map_err(|_| error("currency", format!("unknown currency {:?}", code_currency)))?;
+
+    // Счет второй стороны указывается только у переводов внутри банка
+    let transfer = code == BOOK_TRANSFER_CREDIT || code == BOOK_TRANSFER_DEBIT;
+    let counterparty = if transfer {
+        customer_reference.parse::<u64>().unwrap_or(0)
+    } else {
+        0
+    };
+    let tx_type = if transfer {
+        TypeTransaction::Transfer
+    } else if credit {
+        TypeTransaction::Deposit
+    } else {
+        TypeTransaction::Withdrawal
+    };
+
+    let (from_user_id, to_user_id) = statement::accounts(account_id, counterparty, credit);
+    Ok(Message {
+        tx_id,
+        tx_type,
+        from_user_id,
+        to_user_id,
+        amount,
+        timestamp,
+        status: StatusTransaction::Success,
+        description: text.to_string(),
+        currency: Some(currency),
+    })
+}
+
+/// Следующее поле записи, последнее поле записи заканчивается '/'
+fn next_field<'a>(rest: &mut Option<&'a str>) -> &'a str {
+    let Some(text) = rest.take() else {
+        return "";
+    };
+    match text.split_once(',') {
+        Some((field, tail)) => {
+            *rest = Some(tail);
+            field.trim()
+        }
+        None => text.strip_suffix('/').unwrap_or(text).trim(),
+    }
+}
+
+/// Количество дополнительных полей после типа доступности средств:
+/// `V` - дата и время, `S` - три суммы, `D` - число распределений и пары день-сумма
+fn funds_fields(funds: &str, next: &str) -> Option<usize> {
+    match funds {
+        "V" => Some(2),
+        "S" => Some(3),
+        "D" => next
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|count| count.checked_mul(2)?.checked_add(1)),
+        "" | "0" | "1" | "2" | "Z" => Some(0),
+        _ => None,
+    }
+}
+
+/// Итоговая сумма со знаком, `+` допускается
+fn parse_total(value: &str) -> Option<i128> {
+    let value = value.trim();
+    let digits = value.strip_prefix(['+', '-']).unwrap_or(value);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    value.trim_start_matches('+').parse().ok()
+}
+
+fn check_count(value: &str, expected: u64, what: &str) -> std::result::Result<(), String> {
+    match value.parse::<u64>() {
+        Ok(count) if count == expected => Ok(()),
+        Ok(count) => Err(format!(
+            "number of {} {} does not match {} read",
+            what, count, expected
+        )),
+        Err(_) => Err(format!("invalid number of {} {:?}", what, value)),
+    }
+}
+
+/// Время `HHMM` в миллисекундах от начала суток, пустое время - начало суток,
+/// `2400` и `9999` - конец суток
+fn parse_time(value: &str) -> Option<u64> {
+    match value {
+        "" => Some(0),
+        "2400" | "9999" => Some(86_400_000),
+        _ if value.len() == 4 && value.bytes().all(|b| b.is_ascii_digit()) => {
+            let hour = value[..2].parse::<u64>().ok()?;
+            let minute = value[2..].parse::<u64>().ok()?;
+            (hour < 24 && minute < 60).then_some((hour * 60 + minute) * 60_000)
+        }
+        _ => None,
+    }
+}
+
+/// Время UTC в виде `HHMM`
+fn format_hhmm(timestamp: u64) -> String {
+    let date = DateTime::from_timestamp(timestamp);
+    format!("{:02}{:02}", date.hour, date.minute)
+}
+
+/// Писатель BAI2, по записи `03` на каждую выписку [`Statements`] в одной группе с итогами
+pub struct Bai2RecordWriter<W: std::io::Write> {
+    writer: W,
+    amount_policy: AmountPolicy,
+    statements: Statements,
+    created: Option<u64>,
+}
+
+impl<W: std::io::Write> Bai2RecordWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            amount_policy: AmountPolicy::default(),
+            statements: Statements::default(),
+            created: None,
+        }
+    }
+
+    pub fn with_amount_policy(mut self, amount_policy: AmountPolicy) -> Self {
+        self.amount_policy = amount_policy;
+        self
+    }
+
+    /// Время создания файла для записей `01` и `02`, по умолчанию время последней записи
+    pub fn with_created(mut self, created: u64) -> Self {
+        self.created = Some(created);
+        self
+    }
+
+    pub fn write_message(&mut self, message: &Message) -> Result<()> {
+        let message = self.amount_policy.apply(message, &DataFormat::BAI2)?;
+        let currency = message
+            .currency
+            .ok_or_else(|| mapping_error(&message, "CURRENCY is required for the account"))?;
+        if message.status != StatusTransaction::Success {
+            return Err(mapping_error(
+                &message,
+                &format!(
+                    "STATUS {}, BAI2 contains posted transactions only",
+                    message.status
+                ),
+            ));
+        }
+        if message.from_user_id == 0 && message.to_user_id == 0 {
+            return Err(mapping_error(
+                &message,
+                "FROM_USER_ID and TO_USER_ID are 0, there is no account",
+            ));
+        }
+        // Тип транзакции восстанавливается по коду типа и счетам
+        if let Some(reason) = AccountConsistency
+            .check(&message)
+            .or_else(|| TransferAccounts.check(&message))
+        {
+            return Err(mapping_error(
+                &message,
+                &format!("{}, TX_TYPE cannot be restored from BAI2", reason),
+            ));
+        }
+        if message.description.starts_with('/') {
+            return Err(mapping_error(
+                &message,
+                "DESCRIPTION starts with '/', which means no text in BAI2",
+            ));
+        }
+        // Записи фиксированной длины дополняются пробелами, поэтому читатель их отбрасывает
+        if message.description.ends_with(char::is_whitespace) {
+            return Err(mapping_error(
+                &message,
+                "DESCRIPTION ends with whitespace, which is record padding in BAI2",
+            ));
+        }
+        if message.description.contains(['\r', '\n']) {
+            return Err(mapping_error(
+                &message,
+                "DESCRIPTION contains a line break, BAI2 text is a single line",
+            ));
+        }
+        if message.timestamp % 60_000 != 0 {
+            return Err(mapping_error(
+                &message,
+                &format!(
+                    "TIMESTAMP {} is not a whole minute, BAI2 stores HHMM",
+                    message.timestamp
+                ),
+            ));
+        }
+        self.statements.push(&message, currency);
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W> {
+        let created = self
+            .created
+            .unwrap_or_else(|| self.statements.latest_timestamp());
+        let (date, time) = (datetime::format_yymmdd(created), format_hhmm(created));
+        writeln!(
+            self.writer,
+            "01,{},{},{},{},1,,,2/",
+            BANK_ID, BANK_ID, date, time
+        )?;
+        writeln!(
+            self.writer,
+            "02,{},{},1,{},{},,/",
+            BANK_ID, BANK_ID, date, time
+        )?;
+
+        // Итоги группы: сумма итогов счетов, число счетов и записей вместе с 02 и 98
+        let mut total = 0i128;
+        let mut accounts = 0u64;
+        let mut records = 2u64;
+        let statements = std::mem::take(&mut self.statements);
+        for statement in statements.into_statements() {
+            let (account_total, account_records) = self.write_account(&statement)?;
+            total += account_total;
+            accounts += 1;
+            records += account_records;
+        }
+        writeln!(self.writer, "98,{},{},{}/", total, accounts, records)?;
+        writeln!(self.writer, "99,{},1,{}/", total, records + 2)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Запись счета, возвращает его итоговую сумму и число записей вместе с 03 и 49
+    fn write_account(&mut self, statement: &Statement) -> Result<(i128, u64)> {
+        let w = &mut self.writer;
+        writeln!(w, "03,{},{}/", statement.account, statement.currency)?;
+        let mut total = 0i128;
+        for entry in &statement.entries {
+            total += entry.message.amount as i128;
+            write_detail(w, entry)?;
+        }
+        let records = statement.entries.len() as u64 + 2;
+        writeln!(w, "49,{},{}/", total, records)?;
+        Ok((total, records))
+    }
+}
+
+/// Запись 16 строки выписки с датой валютирования
+fn write_detail<W: std::io::Write>(w: &mut W, entry: &StatementEntry) -> Result<()> {
+    let message = &entry.message;
+    let code = match (message.tx_type, entry.credit) {
+        (TypeTransaction::Transfer, true) => BOOK_TRANSFER_CREDIT,
+        (TypeTransaction::Transfer, false) => BOOK_TRANSFER_DEBIT,
+        (_, true) => MISC_CREDIT,
+        (_, false) => MISC_DEBIT,
+    };
+    let counterparty = match entry.counterparty() {
+        0 => String::new(),
+        id => id.to_string(),
+    };
+    let text = match message.description.as_str() {
+        "" => "/",
+        description => description,
+    };
+    writeln!(
+        w,
+        "16,{},{},V,{},{},{},{},{}",
+        code,
+        message.amount,
+        datetime::format_yymmdd(message.timestamp),
+        format_hhmm(message.timestamp),
+        message.tx_id,
+        counterparty,
+        text
+    )?;
+    Ok(())
+}
+
+fn mapping_error(message: &Message, reason: &str) -> AppError {
+    AppError::MappingError {
+        format: DataFormat::BAI2,
+        tx_id: message.tx_id,
+        reason: reason.to_string(),
+    }
+}
+
+impl Default for Bai2YPBankRecord {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BankRecord for Bai2YPBankRecord {
+    fn write_to<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        let mut writer = Bai2RecordWriter::new(writer);
+        for message in &self.data {
+            writer.write_message(message)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+
+    fn push(&mut self, value: Message) {
+        self.data.push(value);
+    }
+
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn pop(&mut self) -> Option<Message> {
+        self.data.pop()
+    }
+
+    fn iter(&self) -> std::slice::Iter<'_, Message> {
+        self.data.iter()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convertor::test_records::{self, transaction};
+
+    fn message(tx_id: u64, tx_type: TypeTransaction, from: u64, to: u64) -> Message {
+        Message {
+            // BAI2 хранит HHMM, время с секундами не записывается
+            timestamp: 1633036800000 + tx_id * 60_000,
+            description: format!("Record {}, part {}", tx_id, tx_id),
+            ..transaction(tx_id, tx_type, from, to)
+        }
+    }
+
+    fn write(messages: &[Message]) -> String {
+        test_records::write_text(messages, &DataFormat::BAI2)
+    }
+
+    fn read(bai: &str) -> Vec<Result<Message>> {
+        test_records::read(bai, &DataFormat::BAI2, "statement.bai")
+    }
+
+    fn error(results: &[Result<Message>], i: usize) -> String {
+        results[i].as_ref().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_write() {
+        let bai = write(&[
+            message(1, TypeTransaction::Transfer, 11, 22),
+            message(2, TypeTransaction::Deposit, 0, 11),
+        ]);
+        assert_eq!(
+            bai.lines().collect::<Vec<_>>(),
+            [
+                "01,YPBANK,YPBANK,210930,2122,1,,,2/",
+                "02,YPBANK,YPBANK,1,210930,2122,,/",
+                "03,11,EUR/",
+                "16,506,12345,V,210930,2121,1,22,Record 1, part 1",
+                "16,399,12345,V,210930,2122,2,,Record 2, part 2",
+                "49,24690,4/",
+                "03,22,EUR/",
+                "16,206,12345,V,210930,2121,1,11,Record 1, part 1",
+                "49,12345,3/",
+                "98,37035,2,9/",
+                "99,37035,1,11/",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_created() {
+        let mut writer = Bai2RecordWriter::new(Vec::new()).with_created(1700000000000);
+        writer
+            .write_message(&message(1, TypeTransaction::Deposit, 0, 11))
+            .unwrap();
+        let bai = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert!(bai.starts_with(concat!(
+            "01,YPBANK,YPBANK,231114,2213,1,,,2/\n",
+            "02,YPBANK,YPBANK,1,231114,2213,,/\n",
+        )));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut messages = vec![
+            message(1, TypeTransaction::Transfer, 11, 22),
+            message(2, TypeTransaction::Deposit, 0, 11),
+            message(3, TypeTransaction::Withdrawal, 22, 0),
+            message(4, TypeTransaction::Withdrawal, 11, 0),
+        ];
+        messages[1].description = "Invoice 12/2021/".to_string();
+        messages[2].description = String::new();
+        messages[2].currency = Some(Currency::from_code("JPY").unwrap());
+        messages[3].amount = 0;
+
+        let bai = write(&messages);
+        assert!(bai.contains("16,699,12345,V,210930,2123,3,,/\n"));
+        let mut data = Bai2YPBankRecord::from_read(bai.as_bytes())
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        data.sort_by_key(|message| message.tx_id);
+        assert_eq!(data, messages);
+
+        let empty = write(&[]);
+        assert!(
+            Bai2YPBankRecord::from_read(empty.as_bytes())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_write_errors() {
+        let mut message = message(7, TypeTransaction::Withdrawal, 11, 0);
+        let mut writer = Bai2RecordWriter::new(Vec::new());
+
+        message.to_user_id = 22;
+        let err = writer.write_message(&message).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "BAI2 mapping error: TX_ID 7: WITHDRAWAL must have TO_USER_ID 0, found 22, TX_TYPE cannot be restored from BAI2"
+        );
+
+        message.to_user_id = 0;
+        message.description = "/no text".to_string();
+        assert!(matches!(
+            writer.write_message(&message),
+            Err(AppError::MappingError { tx_id: 7, .. })
+        ));
+
+        message.description = String::new();
+        message.status = StatusTransaction::Pending;
+        assert!(matches!(
+            writer.write_message(&message),
+            Err(AppError::MappingError { .. })
+        ));
+
+        message.status = StatusTransaction::Success;
+        message.currency = None;
+        assert!(matches!(
+            writer.write_message(&message),
+            Err(AppError::MappingError { .. })
+        ));
+
+        message.currency = Some(Currency::from_code("EUR").unwrap());
+        message.timestamp = 1633036800123;
+        let err = writer.write_message(&message).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "BAI2 mapping error: TX_ID 7: TIMESTAMP 1633036800123 is not a whole minute, BAI2 stores HHMM"
+        );
+
+        message.timestamp = 1633036800000;
+        for description in ["Record 7 ", "Record\n7"] {
+            message.description = description.to_string();
+            assert!(matches!(
+                writer.write_message(&message),
+                Err(AppError::MappingError { tx_id: 7, .. })
+            ));
+        }
+
+        message.description = String::new();
+        message.amount = -100;
+        assert!(matches!(
+            writer.write_message(&message),
+            Err(AppError::NegativeAmountError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_read_other_bank() {
+        // Файл другого банка: сводные суммы 03, валюта группы, типы доступности S и D,
+        // продолжение 88 и записи фиксированной длины с пробелами
+        let bai = concat!(
+            "01,122099999,123456789,211001,0200,1,80,,2/\n",
+            "02,123456789,122099999,1,210930,2400,CAD,2/      \n",
+            "03,4100,,010,500000,,,040,+480000,,/\n",
+            "88,045,-1000,,/\n",
+            "16,142,10050,S,10000,50,0,77,INV-1,ACH credit,\n",
+            "88, payroll\n",
+            "16,475,20000,D,2,0,15000,1,5000,78,1234,/\n",
+            "16,506,300,0,79,4200/\n",
+            "49,1009350,7/\n",
+            "98,1009350,1,9/\n",
+            "99,1009350,1,11/\n",
+        );
+        let results = read(bai);
+        let messages = results.into_iter().collect::<Result<Vec<_>>>().unwrap();
+        let cad = Some(Currency::from_code("CAD").unwrap());
+        // Время 2400 - конец суток 30.09.2021
+        let timestamp = 1633046400000;
+        assert_eq!(
+            messages,
+            vec![
+                Message {
+                    tx_id: 77,
+                    tx_type: TypeTransaction::Deposit,
+                    from_user_id: 0,
+                    to_user_id: 4100,
+                    amount: 10050,
+                    timestamp,
+                    status: StatusTransaction::Success,
+                    description: "ACH credit, payroll".to_string(),
+                    currency: cad,
+                },
+                Message {
+                    tx_id: 78,
+                    tx_type: TypeTransaction::Withdrawal,
+                    from_user_id: 4100,
+                    to_user_id: 0,
+                    amount: 20000,
+                    timestamp,
+                    status: StatusTransaction::Success,
+                    description: String::new(),
+                    currency: cad,
+                },
+                Message {
+                    tx_id: 79,
+                    tx_type: TypeTransaction::Transfer,
+                    from_user_id: 4100,
+                    to_user_id: 4200,
+                    amount: 300,
+                    timestamp,
+                    status: StatusTransaction::Success,
+                    description: String::new(),
+                    currency: cad,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let bai = concat!(
+            "01,A,B,211001,0200,1,,,2/\n",
+            "02,B,A,1,211001,0200,EUR,/\n",
+            "03,11,,/\n",
+            "16,901,100,Z,1,,/\n",
+            "16,399,100,Z,REF-2,,/\n",
+            "16,399,100,V,211301,1200,3,,/\n",
+            "16,399,100,Z,4,,Record 4\n",
+            "49,400,6/\n",
+            "98,400,1,8/\n",
+            "99,400,1,10/\n",
+        );
+        let results = read(bai);
+        assert_eq!(results.len(), 4);
+        assert_eq!(
+            error(&results, 0),
+            "statement.bai:4: type code: type code \"901\" is not a credit or debit detail"
+        );
+        assert_eq!(
+            error(&results, 1),
+            "statement.bai:5: bank reference: invalid integer \"REF-2\""
+        );
+        assert_eq!(
+            error(&results, 2),
+            "statement.bai:6: date: invalid date and time \"211301\" \"1200\""
+        );
+        assert!(
+            results[..3]
+                .iter()
+                .all(|r| r.as_ref().unwrap_err().is_record_error())
+        );
+        assert_eq!(results[3].as_ref().unwrap().description, "Record 4");
+    }
+
+    #[test]
+    fn test_control_totals() {
+        let file = |account: &str, group: &str, trailer: &str| {
+            format!(
+                "01,A,B,211001,0200,1,,,2/\n02,B,A,1,211001,0200,EUR,/\n03,11,EUR/\n16,399,100,Z,1,,/\n{}\n{}\n{}\n",
+                account, group, trailer
+            )
+        };
+        // Транзакции счета возвращаются только после проверки его итогов в 49
+        let cases = [
+            (
+                file("49,101,3/", "98,101,1,5/", "99,101,1,7/"),
+                "statement.bai:5: account 11 control total 101 does not match the sum of amounts 100",
+                0,
+            ),
+            (
+                file("49,100,4/", "98,100,1,5/", "99,100,1,7/"),
+                "statement.bai:5: number of records 4 does not match 3 read",
+                0,
+            ),
+            (
+                file("49,100,3/", "98,100,2,5/", "99,100,1,7/"),
+                "statement.bai:6: number of accounts 2 does not match 1 read",
+                1,
+            ),
+            (
+                file("49,100,3/", "98,100,1,5/", "99,-100,1,7/"),
+                "statement.bai:7: file control total -100 does not match the sum of group totals 100",
+                1,
+            ),
+            (
+                file("49,100,3/", "98,100,1,5/", ""),
+                "statement.bai:7: unexpected end of file, 99 file trailer is missing",
+                1,
+            ),
+            (
+                file("03,12,EUR/", "", ""),
+                "statement.bai:5: unexpected 03 account identifier, 49 account trailer is missing",
+                0,
+            ),
+        ];
+        for (bai, expected, read_records) in cases {
+            let results = read(&bai);
+            assert_eq!(results.len(), read_records + 1, "{}", expected);
+            assert!(results[..read_records].iter().all(|r| r.is_ok()));
+            let err = results.last().unwrap().as_ref().unwrap_err();
+            assert!(matches!(err, AppError::Bai2StructureError(_)));
+            assert_eq!(err.to_string(), expected);
+        }
+
+        let results = read("01,A,B,211001,0200,1,,,3/\n");
+        assert_eq!(
+            error(&results, 0),
+            "statement.bai:1: BAI version \"3\" is not supported, expected 2"
+        );
+    }
+}
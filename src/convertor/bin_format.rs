@@ -1,7 +1,9 @@
+use std::{collections::VecDeque, io::Read};
+
 use crate::{
     CsvYPBankRecord, TxtYPBankRecord,
-    convertor::{BankRecord, Message, StatusTransaction, TypeTransaction},
-    error::{AppError, BinParseError, Result},
+    convertor::{BankRecord, Currency, Message, StatusTransaction, TypeTransaction},
+    error::{AppError, BinParseError, Location, Result},
 };
 
 const MAGIC_NUMBER: [u8; 4] = [0x59, 0x50, 0x42, 0x4E]; // "YPBN"
@@ -9,74 +11,275 @@ const MAGIC_NUMBER: [u8; 4] = [0x59, 0x50, 0x42, 0x4E]; // "YPBN"
 const MIN_SAIZE_MESSAGE: usize = 46;
 const MAX_SAIZE_MESSAGE: usize = 1024; // TODO: уточнить максимальный размер записи
 
+/// Наибольшая длина описания в байтах, при которой запись с кавычками и расширением
+/// помещается в MAX_SAIZE_MESSAGE
+pub(crate) const MAX_DESCRIPTION_LEN: usize = MAX_SAIZE_MESSAGE - MIN_SAIZE_MESSAGE - 2 - 4;
+
+const EXTENSION_VERSION: u8 = 1; // версия расширения записи с полем CURRENCY
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct BinYPBankRecord {
     data: Vec<Message>,
 }
 
 impl BinYPBankRecord {
-    pub fn from_read<R: std::io::Read>(mut r: R) -> Result<Self> {
-        let mut data = Self::new();
+    pub fn from_read<R: std::io::Read>(r: R) -> Result<Self> {
+        let data = BinRecordReader::new(r).collect::<Result<Vec<_>>>()?;
+        Ok(Self { data })
+    }
+
+    pub fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+}
+
+/// Потоковый читатель бинарного формата, возвращает записи по одной
+pub struct BinRecordReader<R: std::io::Read> {
+    reader: std::io::BufReader<R>,
+    finished: bool,
+    source: Option<String>,
+    // Количество прочитанных байт, смещение следующего заголовка
+    offset: u64,
+    // Количество прочитанных записей
+    record: u64,
+    // Режим восстановления: после ошибки ищем следующий MAGIC
+    recovery: bool,
+    // Байты текущей записи, нужны для повторного просмотра при поиске MAGIC
+    record_bytes: Vec<u8>,
+    // Возвращенные в поток байты, читаются раньше reader
+    pending: VecDeque<u8>,
+}
+
+impl<R: std::io::Read> BinRecordReader<R> {
+    pub fn new(r: R) -> Self {
+        Self {
+            reader: std::io::BufReader::new(r),
+            finished: false,
+            source: None,
+            offset: 0,
+            record: 0,
+            recovery: false,
+            record_bytes: Vec::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    pub fn with_recovery(mut self, recovery: bool) -> Self {
+        self.recovery = recovery;
+        self
+    }
+
+    /// Чтение до заполнения буфера или конца файла, возвращает количество прочитанных байт
+    fn read_full(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let mut read = 0;
+        while read < buf.len() {
+            if let Some(byte) = self.pending.pop_front() {
+                buf[read] = byte;
+                read += 1;
+                continue;
+            }
+            match self.reader.read(&mut buf[read..]) {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        self.offset += read as u64;
+        self.record_bytes.extend_from_slice(&buf[..read]);
+        Ok(read)
+    }
+
+    /// Чтение одного байта при поиске MAGIC. Байт не попадает в record_bytes,
+    /// поэтому длинный участок мусора не накапливается в памяти
+    fn read_byte(&mut self) -> Result<Option<u8>> {
+        if let Some(byte) = self.pending.pop_front() {
+            self.offset += 1;
+            return Ok(Some(byte));
+        }
+        let mut byte = [0u8; 1];
         loop {
-            // Считываем магическое число
-            let mut magic_buf = [0u8; 4];
-            match r.read_exact(&mut magic_buf) {
-                Ok(()) => {}
-                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
-                    break;
-                }
-                Err(e) => {
-                    return Err(AppError::BinParseError(BinParseError::MagicNumberError(
-                        format!("Error parse magic number: {}", e),
-                    )));
+            match self.reader.read(&mut byte) {
+                Ok(0) => return Ok(None),
+                Ok(_) => {
+                    self.offset += 1;
+                    return Ok(Some(byte[0]));
                 }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Возврат байт в начало потока
+    fn unread(&mut self, bytes: &[u8]) {
+        for byte in bytes.iter().rev() {
+            self.pending.push_front(*byte);
+        }
+        self.offset -= bytes.len() as u64;
+    }
+
+    /// Поиск следующего MAGIC начиная со второго байта поврежденной записи.
+    /// Возвращает смещение найденного заголовка или конца файла.
+    fn resync(&mut self) -> Result<u64> {
+        let record_bytes = std::mem::take(&mut self.record_bytes);
+        if let Some((_, rest)) = record_bytes.split_first() {
+            self.unread(rest);
+        }
+
+        let mut window = [0u8; 4];
+        let mut filled = 0;
+        while let Some(byte) = self.read_byte()? {
+            window.rotate_left(1);
+            window[3] = byte;
+            filled += 1;
+            if filled >= window.len() && window == MAGIC_NUMBER {
+                self.unread(&MAGIC_NUMBER);
+                break;
+            }
+        }
+        Ok(self.offset)
+    }
+
+    fn read_message(&mut self) -> Result<Option<Message>> {
+        self.record_bytes.clear();
+
+        let location = Location {
+            source: self.source.clone(),
+            record: Some(self.record + 1),
+            byte: Some(self.offset),
+            ..Location::default()
+        };
+        let at = |field: &str| Location {
+            field: Some(field.to_string()),
+            ..location.clone()
+        };
+
+        // Считываем магическое число
+        let mut magic_buf = [0u8; 4];
+        let read = self.read_full(&mut magic_buf)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        self.record += 1;
+        if read < magic_buf.len() {
+            return Err(BinParseError::TruncatedRecordError {
+                location: at("MAGIC"),
+                expected: magic_buf.len(),
+                bytes: magic_buf[..read].to_vec(),
             }
+            .into());
+        }
 
-            if magic_buf != MAGIC_NUMBER {
-                return Err(AppError::BinParseError(BinParseError::MagicNumberError(
-                    format!(
-                        "Invalid magic number: {:?}, expected: {:?}",
-                        magic_buf, MAGIC_NUMBER
-                    ),
-                )));
+        if magic_buf != MAGIC_NUMBER {
+            return Err(BinParseError::MagicNumberError {
+                location: at("MAGIC"),
+                bytes: magic_buf.to_vec(),
             }
+            .into());
+        }
 
-            // Считываем размер записи
-            let mut record_size_buf = [0u8; 4];
-            r.read_exact(&mut record_size_buf)?;
-            // dbg!(record_size_buf);
-            let record_size = u32::from_be_bytes(record_size_buf) as usize;
-            // dbg!(record_size);
-            // Проверям размер записи
-            if record_size < MIN_SAIZE_MESSAGE {
-                return Err(AppError::BinParseError(BinParseError::MessageSizeError(
-                    format!(
-                        "Message size is less than {} bytes: {}",
-                        MIN_SAIZE_MESSAGE, record_size
-                    ),
-                )));
+        // Считываем размер записи
+        let mut record_size_buf = [0u8; 4];
+        let read = self.read_full(&mut record_size_buf)?;
+        if read < record_size_buf.len() {
+            return Err(BinParseError::TruncatedRecordError {
+                location: at("RECORD_SIZE"),
+                expected: record_size_buf.len(),
+                bytes: record_size_buf[..read].to_vec(),
+            }
+            .into());
+        }
+        let record_size = u32::from_be_bytes(record_size_buf) as usize;
+        // Проверям размер записи
+        if !(MIN_SAIZE_MESSAGE..=MAX_SAIZE_MESSAGE).contains(&record_size) {
+            return Err(BinParseError::MessageSizeError {
+                location: at("RECORD_SIZE"),
+                size: record_size,
+                min: MIN_SAIZE_MESSAGE,
+                max: MAX_SAIZE_MESSAGE,
+                bytes: record_size_buf.to_vec(),
             }
-            if record_size > MAX_SAIZE_MESSAGE {
-                return Err(AppError::BinParseError(BinParseError::MessageSizeError(
-                    format!(
-                        "Message size is more than {} bytes: {}",
-                        MAX_SAIZE_MESSAGE, record_size
-                    ),
-                )));
+            .into());
+        }
+        // Считываем саму запись
+        let mut record_buf = vec![0u8; record_size];
+        let read = self.read_full(&mut record_buf)?;
+        if read < record_size {
+            record_buf.truncate(read);
+            return Err(BinParseError::TruncatedRecordError {
+                location,
+                expected: record_size,
+                bytes: record_buf,
             }
-            // Считываем саму запись
-            let mut record_buf = vec![0u8; record_size];
-            r.read_exact(&mut record_buf)?;
-            // dbg!(&record_buf.len());
-            let message = Message::parse_from_bin(&record_buf)?;
-            data.push(message);
+            .into());
         }
+        Message::parse_from_bin(&record_buf, &location).map(Some)
+    }
+}
 
-        Ok(data)
+/// Потоковый писатель бинарного формата, принимает записи по одной
+pub struct BinRecordWriter<W: std::io::Write> {
+    writer: W,
+}
+
+impl<W: std::io::Write> BinRecordWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
     }
 
-    pub fn new() -> Self {
-        Self { data: Vec::new() }
+    pub fn write_message(&mut self, message: &Message) -> Result<()> {
+        //Серелизируем тело сообщения
+        let body_data = message.to_bin()?;
+        let record_syze = body_data.len() as u32;
+
+        // Записываем заголовок
+        self.writer.write_all(&MAGIC_NUMBER)?;
+        self.writer.write_all(&record_syze.to_be_bytes())?;
+
+        // Записываем тело сообщения
+        self.writer.write_all(&body_data)?;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl<R: std::io::Read> Iterator for BinRecordReader<R> {
+    type Item = Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        match self.read_message() {
+            Ok(Some(message)) => Some(Ok(message)),
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            // В режиме восстановления пропускаем байты до следующего MAGIC
+            Err(AppError::BinParseError(cause)) if self.recovery => match self.resync() {
+                Ok(end) => Some(Err(BinParseError::SkippedBytes { cause, end }.into())),
+                Err(e) => {
+                    self.finished = true;
+                    Some(Err(e))
+                }
+            },
+            // Без восстановления граница следующей записи неизвестна, поэтому чтение прекращается
+            Err(e) => {
+                self.finished = true;
+                Some(Err(e))
+            }
+        }
     }
 }
 
@@ -88,19 +291,11 @@ impl Default for BinYPBankRecord {
 
 impl BankRecord for BinYPBankRecord {
     fn write_to<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        let mut writer = BinRecordWriter::new(writer);
         for message in &self.data {
-            //Серелизируем тело сообщения
-            let body_data = message.to_bin()?;
-            let record_syze = body_data.len() as u32;
-
-            // Записываем заголовок
-            writer.write_all(&MAGIC_NUMBER)?;
-            writer.write_all(&record_syze.to_be_bytes())?;
-
-            // Записываем тело сообщения
-            writer.write_all(&body_data)?;
+            writer.write_message(message)?;
         }
-
+        writer.finish()?;
         Ok(())
     }
 
@@ -127,54 +322,49 @@ impl BankRecord for BinYPBankRecord {
 
 impl From<CsvYPBankRecord> for BinYPBankRecord {
     fn from(value: CsvYPBankRecord) -> Self {
-        let mut record = Self::new();
-        for mes in value.iter() {
-            record.push(Message {
-                tx_id: mes.tx_id,
-                tx_type: mes.tx_type,
-                from_user_id: mes.from_user_id,
-                to_user_id: mes.to_user_id,
-                amount: mes.amount,
-                timestamp: mes.timestamp,
-                status: mes.status,
-                description: format!("\"{}\"", mes.description.clone()),
-            });
+        Self {
+            data: value.iter().cloned().collect(),
         }
-        record
     }
 }
 
 impl From<TxtYPBankRecord> for BinYPBankRecord {
     fn from(value: TxtYPBankRecord) -> Self {
-        let mut record = Self::new();
-        for mes in value.iter() {
-            record.push(Message {
-                tx_id: mes.tx_id,
-                tx_type: mes.tx_type,
-                from_user_id: mes.from_user_id,
-                to_user_id: mes.to_user_id,
-                amount: mes.amount,
-                timestamp: mes.timestamp,
-                status: mes.status,
-                description: mes.description.clone(),
-            });
+        Self {
+            data: value.iter().cloned().collect(),
         }
-        record
     }
 }
 
+/// Описание в двойных кавычках, как его хранит BIN
+fn quote_description(description: &str) -> String {
+    format!("\"{}\"", description)
+}
+
+/// Снятие одной пары внешних кавычек, описание без кавычек возвращается как есть.
+/// Кавычки внутри текста сохраняются, поэтому `quote_description` и `unquote_description`
+/// взаимно обратны.
+fn unquote_description(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
 impl Message {
-    fn parse_from_bin(buf: &[u8]) -> Result<Self> {
+    fn parse_from_bin(buf: &[u8], location: &Location) -> Result<Self> {
+        let at = |field: &str| Location {
+            field: Some(field.to_string()),
+            ..location.clone()
+        };
         if buf.len() < MIN_SAIZE_MESSAGE {
-            return Err(AppError::BinParseError(BinParseError::MessageSizeError(
-                format!(
-                    "Message size is less than {} bytes: {}",
-                    MIN_SAIZE_MESSAGE,
-                    buf.len()
-                ),
-            )));
+            return Err(BinParseError::TruncatedRecordError {
+                location: location.clone(),
+                expected: MIN_SAIZE_MESSAGE,
+                bytes: buf.to_vec(),
+            }
+            .into());
         }
-        // dbg!(buf.len());
         let mut cursor = 0;
 
         //TX_ID 8 bytes
@@ -182,7 +372,12 @@ impl Message {
         cursor += 8;
 
         // TX_TYPE 1 byte
-        let tx_type = TypeTransaction::from_u8(buf[cursor])?;
+        let tx_type = TypeTransaction::from_u8(buf[cursor]).ok_or_else(|| {
+            BinParseError::TransactionError {
+                location: at("TX_TYPE"),
+                bytes: vec![buf[cursor]],
+            }
+        })?;
         cursor += 1;
 
         // FROM_USER_ID 8 bytes
@@ -193,8 +388,8 @@ impl Message {
         let to_user_id = u64::from_be_bytes(buf[cursor..cursor + 8].try_into()?);
         cursor += 8;
 
-        // AMOUNT 8 bytes
-        let amount = u64::from_be_bytes(buf[cursor..cursor + 8].try_into()?);
+        // AMOUNT 8 bytes, signed
+        let amount = i64::from_be_bytes(buf[cursor..cursor + 8].try_into()?);
         cursor += 8;
 
         // TIMESTAMP 8 bytes
@@ -202,25 +397,49 @@ impl Message {
         cursor += 8;
 
         // STATUS 1 byte
-        let status = StatusTransaction::from_u8(buf[cursor])?;
+        let status = StatusTransaction::from_u8(buf[cursor]).ok_or_else(|| {
+            BinParseError::TransactionError {
+                location: at("STATUS"),
+                bytes: vec![buf[cursor]],
+            }
+        })?;
         cursor += 1;
 
         // DESCRIPTION_LEN 4 bytes
-        let desc_len = u32::from_be_bytes(buf[cursor..cursor + 4].try_into()?);
+        let desc_len_buf = &buf[cursor..cursor + 4];
+        let desc_len = u32::from_be_bytes(desc_len_buf.try_into()?) as usize;
         cursor += 4;
 
         // DESCRIPTION DESCRIPTION_LEN bytes, UTF-8
-        let description = if desc_len > 0 {
-            if cursor + desc_len as usize > buf.len() {
-                return Err(AppError::BinParseError(BinParseError::DescriptionError(
-                    "Description length is greater than buffer length".to_string(),
-                )));
+        if cursor + desc_len > buf.len() {
+            return Err(BinParseError::DescriptionError {
+                location: at("DESC_LEN"),
+                reason: format!(
+                    "description length {} exceeds record body by {} bytes",
+                    desc_len,
+                    cursor + desc_len - buf.len()
+                ),
+                bytes: desc_len_buf.to_vec(),
             }
-
-            String::from_utf8(buf[cursor..cursor + desc_len as usize].to_vec())?
+            .into());
+        }
+        let desc_buf = &buf[cursor..cursor + desc_len];
+        let description =
+            std::str::from_utf8(desc_buf).map_err(|_| BinParseError::DescriptionError {
+                location: at("DESCRIPTION"),
+                reason: "invalid UTF-8".to_string(),
+                bytes: desc_buf.to_vec(),
+            })?;
+        let description = unquote_description(description).to_string();
+        cursor += desc_len;
+
+        // Расширение записи: EXT_VERSION 1 byte, далее поля версии
+        let currency = if cursor < buf.len() {
+            Some(Self::parse_extension(&buf[cursor..], &at("EXT_VERSION"))?)
         } else {
-            String::new()
+            None
         };
+
         let message = Message {
             tx_id,
             tx_type,
@@ -230,11 +449,35 @@ impl Message {
             timestamp,
             status,
             description,
+            currency,
         };
-        // dbg!(&message);
         Ok(message)
     }
 
+    /// Разбор расширения записи. Версии расширяются только дописыванием полей в конец,
+    /// поэтому поля старших версий, неизвестные читателю, пропускаются.
+    fn parse_extension(buf: &[u8], location: &Location) -> Result<Currency> {
+        let error = |reason: String| BinParseError::ExtensionError {
+            location: location.clone(),
+            reason,
+            bytes: buf.to_vec(),
+        };
+
+        let version = buf[0];
+        if version < EXTENSION_VERSION {
+            return Err(error(format!("invalid extension version {}", version)).into());
+        }
+
+        // CURRENCY 3 bytes, ASCII код ISO 4217
+        let code = buf
+            .get(1..4)
+            .ok_or_else(|| error(format!("extension is too short, {} bytes", buf.len())))?;
+        std::str::from_utf8(code)
+            .ok()
+            .and_then(|code| Currency::from_code(code).ok())
+            .ok_or_else(|| error("unknown currency code".to_string()).into())
+    }
+
     fn to_bin(&self) -> Result<Vec<u8>> {
         let mut buf = Vec::new();
 
@@ -250,7 +493,7 @@ impl Message {
         // TO_USER_ID 8 bytes
         buf.extend_from_slice(&self.to_user_id.to_be_bytes());
 
-        // AMOUNT 8 bytes
+        // AMOUNT 8 bytes, signed
         buf.extend_from_slice(&self.amount.to_be_bytes());
 
         // TIMESTAMP 8 bytes
@@ -259,12 +502,25 @@ impl Message {
         // STATUS 1 byte
         buf.push(self.status.to_u8());
 
+        // Описание хранится в двойных кавычках, пустое описание не пишется
+        let description = if self.description.is_empty() {
+            String::new()
+        } else {
+            quote_description(&self.description)
+        };
+
         // DESCRIPTION_LEN 4 bytes
-        let desc_len = self.description.len() as u32;
+        let desc_len = description.len() as u32;
         buf.extend_from_slice(&desc_len.to_be_bytes());
 
         // DESCRIPTION DESCRIPTION_LEN bytes, UTF-8
-        buf.extend_from_slice(self.description.as_bytes());
+        buf.extend_from_slice(description.as_bytes());
+
+        // Расширение пишется только при наличии валюты, иначе запись соответствует спецификации
+        if let Some(currency) = &self.currency {
+            buf.push(EXTENSION_VERSION);
+            buf.extend_from_slice(currency.as_bytes());
+        }
         // dbg!(&buf.len());
         Ok(buf)
     }
@@ -275,6 +531,7 @@ mod tests {
     use std::fs::File;
 
     use super::*;
+    use crate::error::AppError;
 
     static TEST_BIN: &str = "tests/data/examples_file/records_example.bin";
     static TEST_WRITE_BIN: &str = "records_example_write.bin";
@@ -298,4 +555,201 @@ mod tests {
         let wr_data = BinYPBankRecord::from_read(&mut file).unwrap();
         assert_eq!(data, wr_data);
     }
+
+    #[test]
+    fn test_negative_amount() {
+        let message = Message {
+            tx_id: 1,
+            tx_type: TypeTransaction::Withdrawal,
+            from_user_id: 2,
+            to_user_id: 0,
+            amount: -100,
+            timestamp: 3,
+            status: StatusTransaction::Success,
+            description: String::new(),
+            currency: None,
+        };
+        let mut writer = BinRecordWriter::new(Vec::new());
+        writer.write_message(&message).unwrap();
+        let buf = writer.finish().unwrap();
+
+        let data = BinRecordReader::new(buf.as_slice())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(data, vec![message]);
+    }
+
+    #[test]
+    fn test_currency_extension() {
+        let message = Message {
+            tx_id: 1,
+            tx_type: TypeTransaction::Deposit,
+            from_user_id: 0,
+            to_user_id: 2,
+            amount: 100,
+            timestamp: 3,
+            status: StatusTransaction::Success,
+            description: "a".to_string(),
+            currency: Some(Currency::from_code("EUR").unwrap()),
+        };
+        let mut writer = BinRecordWriter::new(Vec::new());
+        writer.write_message(&message).unwrap();
+        let buf = writer.finish().unwrap();
+        assert_eq!(
+            &buf[buf.len() - 4..],
+            &[EXTENSION_VERSION, b'E', b'U', b'R']
+        );
+
+        let data = BinRecordReader::new(buf.as_slice())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(data, vec![message]);
+    }
+
+    fn write_messages(count: u64) -> Vec<u8> {
+        let mut writer = BinRecordWriter::new(Vec::new());
+        for tx_id in 0..count {
+            writer
+                .write_message(&Message {
+                    tx_id,
+                    tx_type: TypeTransaction::Deposit,
+                    from_user_id: 0,
+                    to_user_id: 2,
+                    amount: 100,
+                    timestamp: 3,
+                    status: StatusTransaction::Success,
+                    description: "a".to_string(),
+                    currency: None,
+                })
+                .unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn test_error_location() {
+        let mut buf = write_messages(3);
+        let record_len = buf.len() / 3;
+
+        // STATUS второй записи: заголовок 8 байт + 41 байт полей до STATUS (TX_ID, TX_TYPE, счета, AMOUNT, TIMESTAMP)
+        buf[record_len + 8 + 41] = 7;
+        let err = BinRecordReader::new(buf.as_slice())
+            .with_source("records.bin")
+            .nth(1)
+            .unwrap()
+            .unwrap_err();
+        let AppError::BinParseError(err) = err else {
+            panic!("unexpected error: {err:?}");
+        };
+        assert!(matches!(*err, BinParseError::TransactionError { .. }));
+        assert_eq!(err.location().record, Some(2));
+        assert_eq!(err.location().byte, Some(record_len as u64));
+        assert_eq!(err.location().field.as_deref(), Some("STATUS"));
+        assert_eq!(err.bytes(), &[7]);
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "records.bin: record 2: byte {}: STATUS: invalid value [07]",
+                record_len
+            )
+        );
+
+        // MAGIC третьей записи
+        let mut buf = write_messages(3);
+        buf[2 * record_len] = b'X';
+        let err = BinRecordReader::new(buf.as_slice())
+            .nth(2)
+            .unwrap()
+            .unwrap_err();
+        let AppError::BinParseError(err) = err else {
+            panic!("unexpected error: {err:?}");
+        };
+        assert!(matches!(*err, BinParseError::MagicNumberError { .. }));
+        assert_eq!(err.location().record, Some(3));
+        assert_eq!(err.location().byte, Some(2 * record_len as u64));
+        assert_eq!(err.bytes(), b"XPBN");
+    }
+
+    #[test]
+    fn test_truncated_record() {
+        let mut buf = write_messages(2);
+        buf.truncate(buf.len() - 5);
+        let err = BinRecordReader::new(buf.as_slice())
+            .nth(1)
+            .unwrap()
+            .unwrap_err();
+        let AppError::BinParseError(err) = err else {
+            panic!("unexpected error: {err:?}");
+        };
+        assert!(matches!(
+            *err,
+            BinParseError::TruncatedRecordError { expected, ref bytes, .. } if bytes.len() == expected - 5
+        ));
+    }
+
+    #[test]
+    fn test_recovery() {
+        let buf = write_messages(4);
+        let record_len = buf.len() / 4;
+
+        // Мусор между первой и второй записью и испорченный STATUS третьей записи
+        let mut corrupted = buf[..record_len].to_vec();
+        corrupted.extend_from_slice(b"garbage");
+        corrupted.extend_from_slice(&buf[record_len..]);
+        corrupted[7 + 2 * record_len + 8 + 41] = 7;
+
+        let results = BinRecordReader::new(corrupted.as_slice())
+            .with_recovery(true)
+            .collect::<Vec<_>>();
+        let messages = results
+            .iter()
+            .filter_map(|r| r.as_ref().ok())
+            .map(|m| m.tx_id)
+            .collect::<Vec<_>>();
+        assert_eq!(messages, vec![0, 1, 3]);
+
+        let skipped = results
+            .iter()
+            .filter_map(|r| match r {
+                Err(AppError::BinParseError(e)) => match e.as_ref() {
+                    BinParseError::SkippedBytes { cause, end } => {
+                        Some((cause.location().byte.unwrap(), *end))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let record_len = record_len as u64;
+        assert_eq!(
+            skipped,
+            vec![
+                (record_len, record_len + 7),
+                (7 + 2 * record_len, 7 + 3 * record_len)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recovery_large_junk() {
+        // Пропущенные байты не накапливаются в буфере записи
+        let buf = write_messages(2);
+        let record_len = buf.len() / 2;
+        let junk = 1 << 20;
+        let mut corrupted = buf[..record_len].to_vec();
+        corrupted.resize(record_len + junk, 0xAA);
+        corrupted.extend_from_slice(&buf[record_len..]);
+
+        let mut reader = BinRecordReader::new(corrupted.as_slice()).with_recovery(true);
+        let results = reader.by_ref().collect::<Vec<_>>();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().tx_id, 0);
+        assert!(matches!(
+            &results[1],
+            Err(AppError::BinParseError(e))
+                if matches!(e.as_ref(), BinParseError::SkippedBytes { end, .. } if *end == (record_len + junk) as u64)
+        ));
+        assert_eq!(results[2].as_ref().unwrap().tx_id, 1);
+        assert!(reader.record_bytes.capacity() < junk);
+    }
 }
@@ -0,0 +1,981 @@
+use quick_xml::events::Event;
+
+use crate::{
+    DataFormat,
+    convertor::{
+        AmountPolicy, BankRecord, Currency, Message, Money, StatusTransaction, TypeTransaction,
+        datetime::{self, DateTime},
+        statement::{Statement, StatementEntry, Statements, Transfers},
+        xml::{self, XmlReader, xml_reader},
+    },
+    error::{AppError, Location, ParseError, Result},
+};
+
+const NAMESPACE: &str = "urn:iso:std:iso:20022:tech:xsd:camt.053.001.08";
+
+// Идентификатор банка в полях MsgId, Acct и Issr
+const ISSUER: &str = "YPBANK";
+
+// Максимальная длина элемента Ustrd по схеме ISO 20022
+const MAX_USTRD_LEN: usize = 140;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Camt053YPBankRecord {
+    data: Vec<Message>,
+}
+
+impl Camt053YPBankRecord {
+    pub fn from_read<R: std::io::Read>(r: R) -> Result<Self> {
+        let data = Camt053RecordReader::new(r).collect::<Result<Vec<_>>>()?;
+        Ok(Self { data })
+    }
+
+    pub fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+}
+
+/// Поля элемента `Ntry` в виде текста, разбираются после закрывающего тега
+#[derive(Debug, Default)]
+struct Entry {
+    byte: u64,
+    tx_id: Option<String>,
+    amount: Option<String>,
+    currency: Option<String>,
+    direction: Option<String>,
+    status: Option<String>,
+    booking_date: Option<String>,
+    value_date: Option<String>,
+    tx_type: Option<String>,
+    from_user_id: Option<String>,
+    to_user_id: Option<String>,
+    description: Option<String>,
+    details: usize,
+}
+
+impl Entry {
+    /// Текст элемента по пути относительно `Ntry`
+    fn push_text(&mut self, path: &[String], text: &str) {
+        let path = path.iter().map(String::as_str).collect::<Vec<_>>();
+        let field = match path.as_slice() {
+            ["NtryRef"] => &mut self.tx_id,
+            ["Amt"] => &mut self.amount,
+            ["CdtDbtInd"] => &mut self.direction,
+            // До версии camt.053.001.08 статус записывался текстом элемента Sts
+            ["Sts"] | ["Sts", "Cd"] => &mut self.status,
+            ["BookgDt", "DtTm" | "Dt"] => &mut self.booking_date,
+            ["ValDt", "DtTm" | "Dt"] => &mut self.value_date,
+            ["BkTxCd", "Prtry", "Cd"] => &mut self.tx_type,
+            [
+                "NtryDtls",
+                "TxDtls",
+                "RltdPties",
+                "DbtrAcct",
+                "Id",
+                "Othr",
+                "Id",
+            ] => &mut self.from_user_id,
+            [
+                "NtryDtls",
+                "TxDtls",
+                "RltdPties",
+                "CdtrAcct",
+                "Id",
+                "Othr",
+                "Id",
+            ] => &mut self.to_user_id,
+            ["NtryDtls", "TxDtls", "RmtInf", "Ustrd"] => &mut self.description,
+            _ => return,
+        };
+        field.get_or_insert_with(String::new).push_str(text);
+    }
+
+    fn into_message(self, location: &Location) -> std::result::Result<Message, ParseError> {
+        let error = |field: &str, message: String| ParseError {
+            location: Location {
+                field: Some(field.to_string()),
+                ..location.clone()
+            },
+            message,
+        };
+        let required = |value: Option<String>, field: &str| {
+            value
+                .map(|value| value.trim().to_string())
+                .ok_or_else(|| error(field, "missing element".to_string()))
+        };
+        let user_id = |value: Option<String>, field: &str| match value {
+            Some(value) => value.trim().parse::<u64>().map_err(|_| {
+                error(
+                    field,
+                    format!("account id {:?} is not a YPBank user id", value.trim()),
+                )
+            }),
+            None => Ok(0),
+        };
+
+        if self.details > 1 {
+            return Err(error(
+                "TxDtls",
+                format!(
+                    "entry has {} transaction details, batch entries are not supported",
+                    self.details
+                ),
+            ));
+        }
+
+        let tx_id = required(self.tx_id, "NtryRef")?;
+        let tx_id = tx_id
+            .parse::<u64>()
+            .map_err(|_| error("NtryRef", format!("invalid integer {:?}", tx_id)))?;
+
+        let amount = required(self.amount, "Amt")?;
+        let code = self
+            .currency
+            .ok_or_else(|| error("Amt", "missing Ccy attribute".to_string()))?;
+        let currency = Currency::from_code(code.trim())
+            .map_err(|_| error("Amt", format!("unknown currency {:?}", code)))?;
+        let money = Money::from_decimal(&amount, currency).map_err(|_| {
+            error(
+                "Amt",
+                format!(
+                    "invalid amount {:?}, {} allows {} decimals",
+                    amount,
+                    currency,
+                    currency.exponent()
+                ),
+            )
+        })?;
+        if money.minor_units < 0 {
+            return Err(error(
+                "Amt",
+                format!(
+                    "negative amount {:?}, the sign is given by CdtDbtInd",
+                    amount
+                ),
+            ));
+        }
+
+        let direction = required(self.direction, "CdtDbtInd")?;
+        let credit = match direction.as_str() {
+            "CRDT" => true,
+            "DBIT" => false,
+            _ => {
+                return Err(error(
+                    "CdtDbtInd",
+                    format!("invalid value {:?}, expected CRDT or DBIT", direction),
+                ));
+            }
+        };
+
+        let status = required(self.status, "Sts")?;
+        let status = match status.as_str() {
+            "BOOK" => StatusTransaction::Success,
+            "PDNG" => StatusTransaction::Pending,
+            "INFO" => StatusTransaction::Failure,
+            _ => {
+                return Err(error(
+                    "Sts",
+                    format!(
+                        "status {:?} has no YPBank equivalent, expected BOOK, PDNG or INFO",
+                        status
+                    ),
+                ));
+            }
+        };
+
+        // Дата бухгалтерской проводки, при ее отсутствии дата валютирования
+        let (date, field) = match (self.booking_date, self.value_date) {
+            (Some(date), _) => (date, "BookgDt"),
+            (None, Some(date)) => (date, "ValDt"),
+            (None, None) => {
+                return Err(error(
+                    "BookgDt",
+                    "missing BookgDt and ValDt, TIMESTAMP cannot be restored".to_string(),
+                ));
+            }
+        };
+        let timestamp = datetime::parse_iso8601(date.trim())
+            .ok_or_else(|| error(field, format!("invalid date {:?}", date.trim())))?;
+
+        // Собственный код типа YPBank, для других банков тип определяется направлением
+        let tx_type = match self.tx_type.as_deref().map(str::trim) {
+            Some("DEPOSIT") => TypeTransaction::Deposit,
+            Some("TRANSFER") => TypeTransaction::Transfer,
+            Some("WITHDRAWAL") => TypeTransaction::Withdrawal,
+            _ if credit => TypeTransaction::Deposit,
+            _ => TypeTransaction::Withdrawal,
+        };
+        // Перевод есть в выписках обоих счетов: списанием и зачислением
+        let contradicts = match tx_type {
+            TypeTransaction::Deposit => !credit,
+            TypeTransaction::Withdrawal => credit,
+            TypeTransaction::Transfer => false,
+        };
+        if contradicts {
+            return Err(error(
+                "CdtDbtInd",
+                format!("{} contradicts TX_TYPE {}", direction, tx_type),
+            ));
+        }
+
+        Ok(Message {
+            tx_id,
+            tx_type,
+            from_user_id: user_id(self.from_user_id, "DbtrAcct")?,
+            to_user_id: user_id(self.to_user_id, "CdtrAcct")?,
+            amount: money.minor_units,
+            timestamp,
+            status,
+            description: self.description.unwrap_or_default(),
+            currency: Some(currency),
+        })
+    }
+}
+
+/// Потоковый читатель выписки camt.053, возвращает по записи на каждый элемент `Ntry`.
+/// Перевод из выписки второго счета пропускается.
+pub struct Camt053RecordReader<R: std::io::Read> {
+    reader: XmlReader<R>,
+    // Локальные имена открытых элементов без префикса пространства имен
+    path: Vec<String>,
+    // Поля текущего элемента Ntry
+    entry: Option<Box<Entry>>,
+    finished: bool,
+    source: Option<String>,
+    // Количество прочитанных записей
+    record: u64,
+    transfers: Transfers,
+}
+
+impl<R: std::io::Read> Camt053RecordReader<R> {
+    pub fn new(r: R) -> Self {
+        Self {
+            reader: xml_reader(r),
+            path: Vec::new(),
+            entry: None,
+            finished: false,
+            source: None,
+            record: 0,
+            transfers: Transfers::default(),
+        }
+    }
+
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    fn location(&self, byte: u64) -> Location {
+        Location {
+            source: self.source.clone(),
+            record: self.entry.as_ref().map(|_| self.record),
+            byte: Some(byte),
+            ..Location::default()
+        }
+    }
+
+    fn xml_error(&self, message: impl ToString) -> AppError {
+        AppError::XmlError(Box::new(ParseError {
+            location: self.location(self.reader.error_position()),
+            message: message.to_string(),
+        }))
+    }
+
+    fn read_message(&mut self) -> Result<Option<Message>> {
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            let position = self.reader.buffer_position();
+            let event = self
+                .reader
+                .read_event_into(&mut buf)
+                .map_err(|e| self.xml_error(e))?;
+            match event {
+                Event::Start(element) => {
+                    let name = xml::local_name(&element);
+                    if self.path.is_empty() && name != "Document" {
+                        return Err(
+                            self.xml_error(format!("expected camt.053 Document, found <{}>", name))
+                        );
+                    }
+                    match (&mut self.entry, name.as_str()) {
+                        (None, "Ntry") => {
+                            self.record += 1;
+                            self.entry = Some(Box::new(Entry {
+                                byte: position,
+                                ..Entry::default()
+                            }));
+                        }
+                        (Some(entry), "Amt") if self.path.last().is_some_and(|p| p == "Ntry") => {
+                            let currency = element
+                                .try_get_attribute("Ccy")
+                                .map_err(quick_xml::Error::from)
+                                .and_then(|attribute| {
+                                    attribute.map(|a| a.unescape_value()).transpose()
+                                });
+                            entry.currency = match currency {
+                                Ok(currency) => currency.map(|c| c.into_owned()),
+                                Err(e) => return Err(self.xml_error(e)),
+                            };
+                        }
+                        (Some(entry), "TxDtls") => entry.details += 1,
+                        _ => {}
+                    }
+                    self.path.push(name);
+                }
+                Event::End(_) => {
+                    if self.path.pop().as_deref() != Some("Ntry") {
+                        continue;
+                    }
+                    let location = self.location(self.entry.as_ref().map_or(0, |e| e.byte));
+                    if let Some(entry) = self.entry.take() {
+                        let message = entry
+                            .into_message(&location)
+                            .map_err(|e| AppError::CamtParseError(Box::new(e)))?;
+                        if self.transfers.is_repeated(&message) {
+                            continue;
+                        }
+                        return Ok(Some(message));
+                    }
+                }
+                Event::Text(text) if self.entry.is_some() => {
+                    let text = text.unescape().map_err(|e| self.xml_error(e))?;
+                    self.push_text(&text);
+                }
+                Event::CData(text) if self.entry.is_some() => {
+                    let text = String::from_utf8(text.into_inner().into_owned())
+                        .map_err(|e| self.xml_error(e))?;
+                    self.push_text(&text);
+                }
+                Event::Eof => {
+                    if let Some(name) = self.path.last() {
+                        return Err(self.xml_error(format!(
+                            "unexpected end of file, <{}> is not closed",
+                            name
+                        )));
+                    }
+                    return Ok(None);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn push_text(&mut self, text: &str) {
+        let Some(entry) = &mut self.entry else {
+            return;
+        };
+        if let Some(start) = self.path.iter().rposition(|name| name == "Ntry") {
+            entry.push_text(&self.path[start + 1..], text);
+        }
+    }
+}
+
+impl<R: std::io::Read> Iterator for Camt053RecordReader<R> {
+    type Item = Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        match self.read_message() {
+            Ok(Some(message)) => Some(Ok(message)),
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(e) => {
+                // После ошибки XML структура документа дальше неизвестна
+                if !e.is_record_error() {
+                    self.finished = true;
+                }
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Писатель выписки camt.053, по элементу `Stmt` на каждую выписку [`Statements`]
+pub struct Camt053RecordWriter<W: std::io::Write> {
+    writer: W,
+    amount_policy: AmountPolicy,
+    statements: Statements,
+    created: Option<u64>,
+}
+
+impl<W: std::io::Write> Camt053RecordWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            amount_policy: AmountPolicy::default(),
+            statements: Statements::default(),
+            created: None,
+        }
+    }
+
+    pub fn with_amount_policy(mut self, amount_policy: AmountPolicy) -> Self {
+        self.amount_policy = amount_policy;
+        self
+    }
+
+    /// Время создания документа для `MsgId` и `CreDtTm`, по умолчанию время последней записи
+    pub fn with_created(mut self, created: u64) -> Self {
+        self.created = Some(created);
+        self
+    }
+
+    pub fn write_message(&mut self, message: &Message) -> Result<()> {
+        let message = self.amount_policy.apply(message, &DataFormat::CAMT053)?;
+        let currency = message
+            .currency
+            .ok_or_else(|| mapping_error(&message, "CURRENCY is required for Amt Ccy"))?;
+        description(&message)?;
+        if message.from_user_id == 0 && message.to_user_id == 0 {
+            return Err(mapping_error(
+                &message,
+                "FROM_USER_ID and TO_USER_ID are 0, there is no account statement",
+            ));
+        }
+        self.statements.push(&message, currency);
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W> {
+        let created = self
+            .created
+            .unwrap_or_else(|| self.statements.latest_timestamp());
+        let id = format!("{}-{}", ISSUER, created);
+        let created = DateTime::from_timestamp(created).to_iso8601();
+        write!(
+            self.writer,
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                "<Document xmlns=\"{namespace}\">\n",
+                "  <BkToCstmrStmt>\n",
+                "    <GrpHdr>\n",
+                "      <MsgId>{id}</MsgId>\n",
+                "      <CreDtTm>{created}</CreDtTm>\n",
+                "    </GrpHdr>\n",
+            ),
+            namespace = NAMESPACE,
+            id = id,
+            created = created,
+        )?;
+        let statements = std::mem::take(&mut self.statements);
+        for (number, statement) in statements.into_statements().enumerate() {
+            let id = format!("{}-{}", id, number + 1);
+            self.write_statement(&id, &created, &statement)?;
+        }
+        self.writer
+            .write_all(b"  </BkToCstmrStmt>\n</Document>\n")?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_statement(&mut self, id: &str, created: &str, statement: &Statement) -> Result<()> {
+        let entries = &statement.entries;
+        let (Some(first), Some(last)) = (entries.first(), entries.last()) else {
+            return Ok(());
+        };
+        let currency = statement.currency;
+
+        // Начальный остаток неизвестен, конечный равен обороту за выписку
+        let mut balance = 0i64;
+        for entry in entries {
+            balance = balance
+                .checked_add(entry.signed_amount())
+                .filter(|balance| balance.checked_abs().is_some())
+                .ok_or_else(|| mapping_error(&entry.message, "closing balance overflow"))?;
+        }
+
+        let w = &mut self.writer;
+        writeln!(w, "    <Stmt>")?;
+        writeln!(w, "      <Id>{}</Id>", id)?;
+        writeln!(w, "      <CreDtTm>{}</CreDtTm>", created)?;
+        writeln!(w, "      <Acct>")?;
+        writeln!(
+            w,
+            "        <Id><Othr><Id>{}</Id></Othr></Id>",
+            statement.account
+        )?;
+        writeln!(w, "        <Ccy>{}</Ccy>", currency)?;
+        writeln!(w, "      </Acct>")?;
+        write_balance(w, "OPBD", 0, currency, first.message.timestamp)?;
+        write_balance(w, "CLBD", balance, currency, last.message.timestamp)?;
+        for entry in entries {
+            write_entry(w, entry, currency)?;
+        }
+        writeln!(w, "    </Stmt>")?;
+        Ok(())
+    }
+}
+
+/// Элемент `Bal` с кодом типа остатка, знак остатка задает `CdtDbtInd`
+fn write_balance<W: std::io::Write>(
+    w: &mut W,
+    code: &str,
+    balance: i64,
+    currency: Currency,
+    timestamp: u64,
+) -> Result<()> {
+    writeln!(w, "      <Bal>")?;
+    writeln!(
+        w,
+        "        <Tp><CdOrPrtry><Cd>{}</Cd></CdOrPrtry></Tp>",
+        code
+    )?;
+    writeln!(
+        w,
+        "        <Amt Ccy=\"{}\">{}</Amt>",
+        currency,
+        Money::new(balance.abs(), currency).to_decimal()
+    )?;
+    writeln!(
+        w,
+        "        <CdtDbtInd>{}</CdtDbtInd>",
+        if balance < 0 { "DBIT" } else { "CRDT" }
+    )?;
+    writeln!(
+        w,
+        "        <Dt><DtTm>{}</DtTm></Dt>",
+        DateTime::from_timestamp(timestamp).to_iso8601()
+    )?;
+    writeln!(w, "      </Bal>")?;
+    Ok(())
+}
+
+/// Элемент `Ntry` строки выписки. Направление задается счетом выписки:
+/// перевод списывается у отправителя и зачисляется получателю.
+fn write_entry<W: std::io::Write>(
+    w: &mut W,
+    entry: &StatementEntry,
+    currency: Currency,
+) -> Result<()> {
+    let message = &entry.message;
+    let description = description(message)?;
+    writeln!(w, "      <Ntry>")?;
+    writeln!(w, "        <NtryRef>{}</NtryRef>", message.tx_id)?;
+    writeln!(
+        w,
+        "        <Amt Ccy=\"{}\">{}</Amt>",
+        currency,
+        Money::new(message.amount, currency).to_decimal()
+    )?;
+    writeln!(
+        w,
+        "        <CdtDbtInd>{}</CdtDbtInd>",
+        if entry.credit { "CRDT" } else { "DBIT" }
+    )?;
+    writeln!(w, "        <Sts>")?;
+    writeln!(w, "          <Cd>{}</Cd>", status_code(message.status))?;
+    writeln!(w, "        </Sts>")?;
+    writeln!(w, "        <BookgDt>")?;
+    writeln!(
+        w,
+        "          <DtTm>{}</DtTm>",
+        DateTime::from_timestamp(message.timestamp).to_iso8601()
+    )?;
+    writeln!(w, "        </BookgDt>")?;
+    writeln!(w, "        <BkTxCd>")?;
+    writeln!(w, "          <Prtry>")?;
+    writeln!(w, "            <Cd>{}</Cd>", message.tx_type)?;
+    writeln!(w, "            <Issr>{}</Issr>", ISSUER)?;
+    writeln!(w, "          </Prtry>")?;
+    writeln!(w, "        </BkTxCd>")?;
+
+    let parties = [
+        ("DbtrAcct", message.from_user_id),
+        ("CdtrAcct", message.to_user_id),
+    ];
+    let has_parties = parties.iter().any(|(_, id)| *id != 0);
+    if has_parties || !description.is_empty() {
+        writeln!(w, "        <NtryDtls>")?;
+        writeln!(w, "          <TxDtls>")?;
+        if has_parties {
+            writeln!(w, "            <RltdPties>")?;
+            // Нулевой счет означает отсутствие стороны и не записывается
+            for (name, id) in parties.iter().filter(|(_, id)| *id != 0) {
+                writeln!(
+                    w,
+                    "              <{name}><Id><Othr><Id>{id}</Id></Othr></Id></{name}>"
+                )?;
+            }
+            writeln!(w, "            </RltdPties>")?;
+        }
+        if !description.is_empty() {
+            writeln!(w, "            <RmtInf>")?;
+            for chunk in &description {
+                writeln!(w, "              <Ustrd>{}</Ustrd>", chunk)?;
+            }
+            writeln!(w, "            </RmtInf>")?;
+        }
+        writeln!(w, "          </TxDtls>")?;
+        writeln!(w, "        </NtryDtls>")?;
+    }
+    writeln!(w, "      </Ntry>")?;
+    Ok(())
+}
+
+/// Код `Sts` записи. Отклоненная транзакция не проводится и получает
+/// информационный статус `INFO`.
+fn status_code(status: StatusTransaction) -> &'static str {
+    match status {
+        StatusTransaction::Success => "BOOK",
+        StatusTransaction::Pending => "PDNG",
+        StatusTransaction::Failure => "INFO",
+    }
+}
+
+/// Части `Ustrd` описания записи
+fn description(message: &Message) -> Result<Vec<String>> {
+    split_description(&message.description).map_err(|c| {
+        mapping_error(
+            message,
+            &format!("DESCRIPTION contains {:?}, not allowed in XML", c),
+        )
+    })
+}
+
+fn mapping_error(message: &Message, reason: &str) -> AppError {
+    AppError::MappingError {
+        format: DataFormat::CAMT053,
+        tx_id: message.tx_id,
+        reason: reason.to_string(),
+    }
+}
+
+/// Описание, разбитое на части по [`MAX_USTRD_LEN`] символов с экранированием XML
+fn split_description(text: &str) -> std::result::Result<Vec<String>, char> {
+    let chars = text.chars().collect::<Vec<_>>();
+    chars
+        .chunks(MAX_USTRD_LEN)
+        .map(|chunk| xml::escape_text(&chunk.iter().collect::<String>()))
+        .collect()
+}
+
+impl Default for Camt053YPBankRecord {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BankRecord for Camt053YPBankRecord {
+    fn write_to<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        let mut writer = Camt053RecordWriter::new(writer);
+        for message in &self.data {
+            writer.write_message(message)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+
+    fn push(&mut self, value: Message) {
+        self.data.push(value);
+    }
+
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn pop(&mut self) -> Option<Message> {
+        self.data.pop()
+    }
+
+    fn iter(&self) -> std::slice::Iter<'_, Message> {
+        self.data.iter()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convertor::test_records::{self, transaction};
+
+    /// Транзакция со счетами, которые допускает ее тип
+    fn message(tx_id: u64, tx_type: TypeTransaction, status: StatusTransaction) -> Message {
+        let (from, to) = match tx_type {
+            TypeTransaction::Deposit => (0, 22),
+            TypeTransaction::Transfer => (11, 22),
+            TypeTransaction::Withdrawal => (11, 0),
+        };
+        Message {
+            status,
+            ..transaction(tx_id, tx_type, from, to)
+        }
+    }
+
+    fn write(messages: &[Message]) -> String {
+        test_records::write_text(messages, &DataFormat::CAMT053)
+    }
+
+    fn statement(entries: &str) -> String {
+        format!(
+            "<?xml version=\"1.0\"?>\n<Document xmlns=\"{}\"><BkToCstmrStmt><Stmt>{}</Stmt></BkToCstmrStmt></Document>",
+            NAMESPACE, entries
+        )
+    }
+
+    fn read(xml: &str) -> Vec<Result<Message>> {
+        test_records::read(xml, &DataFormat::CAMT053, "statement.xml")
+    }
+
+    #[test]
+    fn test_write() {
+        let mut withdrawal = message(2, TypeTransaction::Withdrawal, StatusTransaction::Success);
+        withdrawal.timestamp += 86_400_000;
+        withdrawal.amount = 100;
+        let xml = write(&[
+            withdrawal,
+            message(1, TypeTransaction::Transfer, StatusTransaction::Success),
+        ]);
+        let statements = xml
+            .split("    <Stmt>\n")
+            .skip(1)
+            .map(|statement| statement.split_once("      <Acct>\n").unwrap().1)
+            .collect::<Vec<_>>();
+        assert!(xml.contains("<MsgId>YPBANK-1633123200123</MsgId>"));
+        assert!(xml.contains("<Id>YPBANK-1633123200123-2</Id>"));
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0].starts_with(concat!(
+            "        <Id><Othr><Id>11</Id></Othr></Id>\n",
+            "        <Ccy>EUR</Ccy>\n",
+            "      </Acct>\n",
+            "      <Bal>\n",
+            "        <Tp><CdOrPrtry><Cd>OPBD</Cd></CdOrPrtry></Tp>\n",
+            "        <Amt Ccy=\"EUR\">0.00</Amt>\n",
+            "        <CdtDbtInd>CRDT</CdtDbtInd>\n",
+            "        <Dt><DtTm>2021-09-30T21:20:00.123Z</DtTm></Dt>\n",
+            "      </Bal>\n",
+            "      <Bal>\n",
+            "        <Tp><CdOrPrtry><Cd>CLBD</Cd></CdOrPrtry></Tp>\n",
+            "        <Amt Ccy=\"EUR\">124.45</Amt>\n",
+            "        <CdtDbtInd>DBIT</CdtDbtInd>\n",
+            "        <Dt><DtTm>2021-10-01T21:20:00.123Z</DtTm></Dt>\n",
+            "      </Bal>\n",
+            "      <Ntry>\n",
+            "        <NtryRef>1</NtryRef>\n",
+            "        <Amt Ccy=\"EUR\">123.45</Amt>\n",
+            "        <CdtDbtInd>DBIT</CdtDbtInd>\n",
+        )));
+        assert_eq!(statements[0].matches("<Ntry>").count(), 2);
+        assert!(statements[1].starts_with("        <Id><Othr><Id>22</Id></Othr></Id>\n"));
+        assert!(statements[1].contains(concat!(
+            "        <Amt Ccy=\"EUR\">123.45</Amt>\n",
+            "        <CdtDbtInd>CRDT</CdtDbtInd>\n",
+            "        <Sts>\n",
+        )));
+        assert_eq!(statements[1].matches("<Ntry>").count(), 1);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut messages = vec![
+            message(1, TypeTransaction::Deposit, StatusTransaction::Success),
+            message(2, TypeTransaction::Transfer, StatusTransaction::Failure),
+            message(3, TypeTransaction::Withdrawal, StatusTransaction::Pending),
+            transaction(4, TypeTransaction::Transfer, 33, 33),
+        ];
+        // Описание длиннее одного Ustrd со спецсимволами XML и переводами строк
+        messages[1].description = format!("<a & b> \"q\"\r\n\t{}", "x".repeat(300));
+        messages[2].description = String::new();
+        messages[2].currency = Some(Currency::from_code("JPY").unwrap());
+
+        let xml = write(&messages);
+        assert!(xml.contains("<Amt Ccy=\"EUR\">123.45</Amt>"));
+        assert!(xml.contains("<Amt Ccy=\"JPY\">12345</Amt>"));
+        assert!(xml.contains("<DtTm>2021-09-30T21:20:00.123Z</DtTm>"));
+        // Переводы записываются в выписки обоих счетов
+        assert_eq!(xml.matches("<Ustrd>").count(), 1 + 2 * 3 + 2);
+
+        let mut actual = Camt053YPBankRecord::from_read(xml.as_bytes())
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        actual.sort_by_key(|message| message.tx_id);
+        assert_eq!(actual, messages);
+    }
+
+    #[test]
+    fn test_write_empty() {
+        let xml = write(&[]);
+        assert!(xml.contains("<CreDtTm>1970-01-01T00:00:00.000Z</CreDtTm>"));
+        let data = Camt053YPBankRecord::from_read(xml.as_bytes()).unwrap();
+        assert!(data.is_empty());
+    }
+
+    #[test]
+    fn test_write_created() {
+        let messages = [message(
+            1,
+            TypeTransaction::Deposit,
+            StatusTransaction::Success,
+        )];
+        assert_eq!(write(&messages), write(&messages));
+
+        let mut writer = Camt053RecordWriter::new(Vec::new()).with_created(1700000000000);
+        writer.write_message(&messages[0]).unwrap();
+        let xml = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert!(xml.contains(concat!(
+            "      <MsgId>YPBANK-1700000000000</MsgId>\n",
+            "      <CreDtTm>2023-11-14T22:13:20.000Z</CreDtTm>\n",
+        )));
+    }
+
+    #[test]
+    fn test_write_errors() {
+        let mut message = message(7, TypeTransaction::Deposit, StatusTransaction::Success);
+        message.currency = None;
+        let mut writer = Camt053RecordWriter::new(Vec::new());
+        let err = writer.write_message(&message).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "CAMT053 mapping error: TX_ID 7: CURRENCY is required for Amt Ccy"
+        );
+
+        message.currency = Some(Currency::from_code("EUR").unwrap());
+        message.description = "bell \u{7}".to_string();
+        assert!(matches!(
+            writer.write_message(&message),
+            Err(AppError::MappingError { tx_id: 7, .. })
+        ));
+
+        message.description = String::new();
+        message.to_user_id = 0;
+        assert_eq!(
+            writer.write_message(&message).unwrap_err().to_string(),
+            "CAMT053 mapping error: TX_ID 7: FROM_USER_ID and TO_USER_ID are 0, there is no account statement"
+        );
+
+        message.to_user_id = 22;
+        message.amount = -100;
+        assert!(matches!(
+            writer.write_message(&message),
+            Err(AppError::NegativeAmountError { .. })
+        ));
+        let mut writer = writer.with_amount_policy(AmountPolicy::Absolute);
+        writer.write_message(&message).unwrap();
+        let xml = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert!(xml.contains("<Amt Ccy=\"EUR\">1.00</Amt>"));
+    }
+
+    #[test]
+    fn test_read_other_bank() {
+        // Выписка другого банка: префикс пространства имен, статус текстом Sts (до версии 08),
+        // дата без времени, код типа без собственного кода YPBank
+        let xml = r#"<?xml version="1.0"?>
+<c:Document xmlns:c="urn:iso:std:iso:20022:tech:xsd:camt.053.001.02">
+  <c:BkToCstmrStmt><c:Stmt>
+    <c:Ntry>
+      <c:NtryRef>42</c:NtryRef>
+      <c:Amt Ccy="CHF">10.5</c:Amt>
+      <c:CdtDbtInd>DBIT</c:CdtDbtInd>
+      <c:Sts>BOOK</c:Sts>
+      <c:BookgDt><c:Dt>2021-10-01</c:Dt></c:BookgDt>
+      <c:BkTxCd><c:Domn><c:Cd>PMNT</c:Cd></c:Domn></c:BkTxCd>
+      <c:NtryDtls><c:TxDtls>
+        <c:RmtInf><c:Ustrd><![CDATA[Rent & <fees>]]></c:Ustrd></c:RmtInf>
+      </c:TxDtls></c:NtryDtls>
+    </c:Ntry>
+  </c:Stmt></c:BkToCstmrStmt>
+</c:Document>"#;
+        let data = Camt053YPBankRecord::from_read(xml.as_bytes()).unwrap();
+        assert_eq!(
+            data.iter().next(),
+            Some(&Message {
+                tx_id: 42,
+                tx_type: TypeTransaction::Withdrawal,
+                from_user_id: 0,
+                to_user_id: 0,
+                amount: 1050,
+                timestamp: 1633046400000,
+                status: StatusTransaction::Success,
+                description: "Rent & <fees>".to_string(),
+                currency: Some(Currency::from_code("CHF").unwrap()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let entry = |body: &str| {
+            format!(
+                "<Ntry><NtryRef>1</NtryRef><CdtDbtInd>CRDT</CdtDbtInd><BookgDt><Dt>2021-10-01</Dt></BookgDt>{}</Ntry>",
+                body
+            )
+        };
+        let xml = statement(&[
+            entry("<Amt Ccy=\"EUR\">1.234</Amt><Sts><Cd>BOOK</Cd></Sts>"),
+            entry("<Amt Ccy=\"EUR\">1</Amt><Sts><Cd>FUTR</Cd></Sts>"),
+            entry("<Amt Ccy=\"EUR\">1</Amt><Sts><Cd>BOOK</Cd></Sts><BkTxCd><Prtry><Cd>WITHDRAWAL</Cd></Prtry></BkTxCd>"),
+            entry("<Amt Ccy=\"EUR\">1</Amt><Sts><Cd>BOOK</Cd></Sts><NtryDtls><TxDtls/><TxDtls/></NtryDtls>"),
+            entry("<Amt Ccy=\"EUR\">1</Amt><Sts><Cd>BOOK</Cd></Sts><NtryDtls><TxDtls><RltdPties><DbtrAcct><Id><Othr><Id>CH93-0076</Id></Othr></Id></DbtrAcct></RltdPties></TxDtls></NtryDtls>"),
+            entry("<Amt Ccy=\"EUR\">1</Amt><Sts><Cd>BOOK</Cd></Sts>"),
+        ]
+        .concat());
+        let results = read(&xml);
+        assert_eq!(results.len(), 6);
+        let messages = results[..5]
+            .iter()
+            .map(|r| r.as_ref().unwrap_err().to_string())
+            .collect::<Vec<_>>();
+        assert!(messages[0].starts_with("statement.xml: record 1: byte "));
+        assert!(messages[0].ends_with(": Amt: invalid amount \"1.234\", EUR allows 2 decimals"));
+        assert!(messages[1].ends_with(
+            ": Sts: status \"FUTR\" has no YPBank equivalent, expected BOOK, PDNG or INFO"
+        ));
+        assert!(messages[2].ends_with(": CdtDbtInd: CRDT contradicts TX_TYPE WITHDRAWAL"));
+        assert!(messages[3].ends_with(
+            ": TxDtls: entry has 2 transaction details, batch entries are not supported"
+        ));
+        assert!(
+            messages[4].ends_with(": DbtrAcct: account id \"CH93-0076\" is not a YPBank user id")
+        );
+        assert!(
+            results[..5]
+                .iter()
+                .all(|r| r.as_ref().unwrap_err().is_record_error())
+        );
+        assert_eq!(results[5].as_ref().unwrap().tx_id, 1);
+
+        let missing = read(&statement("<Ntry><Amt Ccy=\"EUR\">1</Amt></Ntry>"));
+        assert!(
+            missing[0]
+                .as_ref()
+                .unwrap_err()
+                .to_string()
+                .ends_with(": NtryRef: missing element")
+        );
+    }
+
+    #[test]
+    fn test_xml_errors() {
+        let results = read(&statement("<Ntry><NtryRef>1</Ntry>"));
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0], Err(AppError::XmlError(_))));
+
+        let results = read("<Document><Ntry><NtryRef>1</NtryRef>");
+        assert_eq!(results.len(), 1);
+        assert!(
+            results[0]
+                .as_ref()
+                .unwrap_err()
+                .to_string()
+                .ends_with("unexpected end of file, <Ntry> is not closed")
+        );
+
+        let results = read("<Statement/>");
+        assert!(
+            results[0]
+                .as_ref()
+                .unwrap_err()
+                .to_string()
+                .ends_with("expected camt.053 Document, found <Statement>")
+        );
+    }
+}
@@ -1,9 +1,23 @@
 use crate::{
-    BinYPBankRecord, TxtYPBankRecord,
-    convertor::{BankRecord, Message},
-    error::Result,
+    BinYPBankRecord, DataFormat, TxtYPBankRecord,
+    convertor::{AmountPolicy, BankRecord, Currency, Message},
+    error::{AppError, Location, ParseError, Result},
 };
 
+const HEADER: [&str; 8] = [
+    "TX_ID",
+    "TX_TYPE",
+    "FROM_USER_ID",
+    "TO_USER_ID",
+    "AMOUNT",
+    "TIMESTAMP",
+    "STATUS",
+    "DESCRIPTION",
+];
+
+// Необязательная колонка после DESCRIPTION
+const CURRENCY_COLUMN: &str = "CURRENCY";
+
 #[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize, Clone)]
 pub struct CsvYPBankRecord {
     data: Vec<Message>,
@@ -11,14 +25,8 @@ pub struct CsvYPBankRecord {
 
 impl CsvYPBankRecord {
     pub fn from_read<R: std::io::Read>(r: R) -> Result<Self> {
-        let mut reader = csv::Reader::from_reader(r);
-        let mut res = Self::new();
-        for result in reader.deserialize() {
-            let record: Message = result?;
-            res.push(record);
-        }
-
-        Ok(res)
+        let data = CsvRecordReader::new(r).collect::<Result<Vec<_>>>()?;
+        Ok(Self { data })
     }
 
     pub fn new() -> Self {
@@ -26,6 +34,200 @@ impl CsvYPBankRecord {
     }
 }
 
+/// Потоковый читатель CSV формата, возвращает записи по одной
+pub struct CsvRecordReader<R: std::io::Read> {
+    // csv::Reader содержит внутренний буфер, поэтому храним его в куче
+    reader: Box<csv::Reader<R>>,
+    headers: Option<csv::StringRecord>,
+    record: csv::StringRecord,
+    finished: bool,
+    source: Option<String>,
+}
+
+impl<R: std::io::Read> CsvRecordReader<R> {
+    pub fn new(r: R) -> Self {
+        Self {
+            reader: Box::new(csv::Reader::from_reader(r)),
+            headers: None,
+            record: csv::StringRecord::new(),
+            finished: false,
+            source: None,
+        }
+    }
+
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    fn read_message(&mut self) -> Result<Option<Message>> {
+        if self.headers.is_none() {
+            let headers = self.reader.headers().cloned();
+            self.headers = Some(headers.map_err(|e| self.csv_error(e))?);
+        }
+
+        if !self
+            .reader
+            .read_record(&mut self.record)
+            .map_err(|e| self.csv_error(e))?
+        {
+            return Ok(None);
+        }
+
+        let message = self
+            .record
+            .deserialize::<Message>(self.headers.as_ref())
+            .map_err(|e| self.csv_error(e))?;
+        if message.amount < 0 {
+            return Err(self.parse_error(
+                self.record.position(),
+                Some("AMOUNT".to_string()),
+                format!(
+                    "negative amount {}, CSV format allows only non-negative amounts",
+                    message.amount
+                ),
+            ));
+        }
+        Ok(Some(message))
+    }
+
+    /// Привязка ошибки csv к строке, записи и полю файла
+    fn csv_error(&self, e: csv::Error) -> AppError {
+        let (field, message) = match e.kind() {
+            csv::ErrorKind::Deserialize { err, .. } => {
+                let field = err.field().map(|i| i as usize);
+                let message = match field.and_then(|i| self.record.get(i)) {
+                    Some(value) => format!("invalid value {:?}: {}", value, err.kind()),
+                    None => err.kind().to_string(),
+                };
+                (field, message)
+            }
+            csv::ErrorKind::Utf8 { err, .. } => (Some(err.field()), "invalid UTF-8".to_string()),
+            csv::ErrorKind::UnequalLengths {
+                expected_len, len, ..
+            } => (
+                None,
+                format!("expected {} fields, found {}", expected_len, len),
+            ),
+            _ => return AppError::CsvError(e),
+        };
+
+        let field = field.map(|i| {
+            self.headers
+                .as_ref()
+                .and_then(|headers| headers.get(i))
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("field {}", i + 1))
+        });
+        self.parse_error(e.position(), field, message)
+    }
+
+    fn parse_error(
+        &self,
+        position: Option<&csv::Position>,
+        field: Option<String>,
+        message: String,
+    ) -> AppError {
+        AppError::CsvParseError(Box::new(ParseError {
+            location: Location {
+                source: self.source.clone(),
+                line: position.map(|p| p.line()),
+                // Заголовок имеет номер 0, поэтому номер записи совпадает с порядковым номером
+                record: position.map(|p| p.record()),
+                field,
+                byte: position.map(|p| p.byte()),
+            },
+            message,
+        }))
+    }
+}
+
+/// Потоковый писатель CSV формата, принимает записи по одной
+pub struct CsvRecordWriter<W: std::io::Write> {
+    // csv::Writer содержит внутренний буфер, поэтому храним его в куче
+    writer: Box<csv::Writer<W>>,
+    amount_policy: AmountPolicy,
+    header_written: bool,
+}
+
+impl<W: std::io::Write> CsvRecordWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Box::new(
+                csv::WriterBuilder::new()
+                    .has_headers(false)
+                    .from_writer(writer),
+            ),
+            amount_policy: AmountPolicy::default(),
+            header_written: false,
+        }
+    }
+
+    pub fn with_amount_policy(mut self, amount_policy: AmountPolicy) -> Self {
+        self.amount_policy = amount_policy;
+        self
+    }
+
+    pub fn write_message(&mut self, message: &Message) -> Result<()> {
+        let message = self.amount_policy.apply(message, &DataFormat::CSV)?;
+
+        // Колонка CURRENCY пишется всегда, для записи без валюты она пустая
+        if !self.header_written {
+            for field in HEADER {
+                self.writer.write_field(field)?;
+            }
+            self.writer.write_field(CURRENCY_COLUMN)?;
+            self.writer.write_record(None::<&[u8]>)?;
+            self.header_written = true;
+        }
+
+        let row = (
+            message.tx_id,
+            message.tx_type,
+            message.from_user_id,
+            message.to_user_id,
+            message.amount,
+            message.timestamp,
+            message.status,
+            &message.description,
+        );
+        self.writer
+            .serialize((row, message.currency.as_ref().map(Currency::code)))?;
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<W> {
+        self.writer
+            .into_inner()
+            .map_err(|e| AppError::IOError(e.into_error()))
+    }
+}
+
+impl<R: std::io::Read> Iterator for CsvRecordReader<R> {
+    type Item = Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        match self.read_message() {
+            Ok(Some(message)) => Some(Ok(message)),
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(e) => {
+                // После ошибки ввода-вывода продолжать чтение бессмысленно
+                if matches!(e, AppError::CsvError(_)) {
+                    self.finished = true;
+                }
+                Some(Err(e))
+            }
+        }
+    }
+}
+
 impl Default for CsvYPBankRecord {
     fn default() -> Self {
         Self::new()
@@ -34,13 +236,11 @@ impl Default for CsvYPBankRecord {
 
 impl BankRecord for CsvYPBankRecord {
     fn write_to<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
-        let mut writer = csv::Writer::from_writer(writer);
-
+        let mut writer = CsvRecordWriter::new(writer);
         for record in &self.data {
-            if let Err(e) = writer.serialize(record) {
-                return Err(e.into());
-            }
+            writer.write_message(record)?;
         }
+        writer.finish()?;
         Ok(())
     }
 
@@ -67,39 +267,17 @@ impl BankRecord for CsvYPBankRecord {
 
 impl From<BinYPBankRecord> for CsvYPBankRecord {
     fn from(value: BinYPBankRecord) -> Self {
-        let mut record = Self::new();
-        for mes in value.iter() {
-            record.push(Message {
-                tx_id: mes.tx_id,
-                tx_type: mes.tx_type,
-                from_user_id: mes.from_user_id,
-                to_user_id: mes.to_user_id,
-                amount: mes.amount,
-                timestamp: mes.timestamp,
-                status: mes.status,
-                description: mes.description.clone().replace("\"", ""),
-            });
+        Self {
+            data: value.iter().cloned().collect(),
         }
-        record
     }
 }
 
 impl From<TxtYPBankRecord> for CsvYPBankRecord {
     fn from(value: TxtYPBankRecord) -> Self {
-        let mut record = Self::new();
-        for mes in value.iter() {
-            record.push(Message {
-                tx_id: mes.tx_id,
-                tx_type: mes.tx_type,
-                from_user_id: mes.from_user_id,
-                to_user_id: mes.to_user_id,
-                amount: mes.amount,
-                timestamp: mes.timestamp,
-                status: mes.status,
-                description: mes.description.clone().replace("\"", ""),
-            });
+        Self {
+            data: value.iter().cloned().collect(),
         }
-        record
     }
 }
 
@@ -131,4 +309,62 @@ mod tests {
         let wr_data = CsvYPBankRecord::from_read(&mut file).unwrap();
         assert_eq!(data, wr_data);
     }
+
+    #[test]
+    fn test_read_negative_amount() {
+        let input = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n1,WITHDRAWAL,2,0,-100,3,SUCCESS,\"a\"\n";
+        let mut reader = CsvRecordReader::new(input.as_bytes());
+        assert!(matches!(
+            reader.next(),
+            Some(Err(AppError::CsvParseError(err)))
+                if err.location.field.as_deref() == Some("AMOUNT")
+        ));
+    }
+
+    #[test]
+    fn test_currency_column() {
+        let input = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION,CURRENCY\n1,DEPOSIT,0,2,100,3,SUCCESS,\"a, b\",EUR\n2,DEPOSIT,0,2,100,3,SUCCESS,\"c\",\n";
+        let data = CsvRecordReader::new(input.as_bytes())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(data[0].currency, Some(Currency::from_code("EUR").unwrap()));
+        assert_eq!(data[1].currency, None);
+
+        // Первая запись без валюты не убирает колонку CURRENCY для следующих
+        let mut writer = CsvRecordWriter::new(Vec::new());
+        for message in data.iter().rev() {
+            writer.write_message(message).unwrap();
+        }
+        let buf = writer.finish().unwrap();
+        assert!(
+            String::from_utf8(buf.clone())
+                .unwrap()
+                .starts_with("TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION,CURRENCY\n2,DEPOSIT,0,2,100,3,SUCCESS,c,\n")
+        );
+        let mut wr_data = CsvRecordReader::new(buf.as_slice())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        wr_data.reverse();
+        assert_eq!(data, wr_data);
+    }
+
+    #[test]
+    fn test_parse_error_location() {
+        let input = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n1,DEPOSIT,0,2,100,3,SUCCESS,\"a\"\n12a,DEPOSIT,0,2,100,3,SUCCESS,\"b\"\n";
+        let err = CsvRecordReader::new(input.as_bytes())
+            .with_source("records.csv")
+            .nth(1)
+            .unwrap()
+            .unwrap_err();
+        let AppError::CsvParseError(err) = err else {
+            panic!("unexpected error: {err:?}");
+        };
+        assert_eq!(err.location.line, Some(3));
+        assert_eq!(err.location.record, Some(2));
+        assert_eq!(err.location.field.as_deref(), Some("TX_ID"));
+        assert!(
+            err.to_string()
+                .starts_with("records.csv:3: TX_ID: invalid value \"12a\"")
+        );
+    }
 }
@@ -0,0 +1,261 @@
+//! Преобразование TIMESTAMP (Unix time в миллисекундах) в календарную дату UTC и обратно.
+//! Внешние форматы выписок хранят даты в виде строк, поэтому преобразование нужно
+//! при записи и разборе, а зависимость от библиотеки дат ради него избыточна.
+
+pub(crate) const MILLIS_PER_DAY: u64 = 86_400_000;
+
+/// Дата и время UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct DateTime {
+    pub(crate) year: i64,
+    pub(crate) month: u32,
+    pub(crate) day: u32,
+    pub(crate) hour: u32,
+    pub(crate) minute: u32,
+    pub(crate) second: u32,
+    pub(crate) millis: u32,
+}
+
+impl DateTime {
+    /// Дата и время по Unix time в миллисекундах
+    pub(crate) fn from_timestamp(timestamp: u64) -> Self {
+        let (year, month, day) = civil_from_days((timestamp / MILLIS_PER_DAY) as i64);
+        let rest = timestamp % MILLIS_PER_DAY;
+        Self {
+            year,
+            month,
+            day,
+            hour: (rest / 3_600_000) as u32,
+            minute: (rest / 60_000 % 60) as u32,
+            second: (rest / 1000 % 60) as u32,
+            millis: (rest % 1000) as u32,
+        }
+    }
+
+    /// Полночь заданной даты, `None` для несуществующей даты
+    pub(crate) fn from_date(year: i64, month: u32, day: u32) -> Option<Self> {
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+        Some(Self {
+            year,
+            month,
+            day,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            millis: 0,
+        })
+    }
+
+    /// Unix time в миллисекундах, `None` для времени вне диапазона или до 1970 года
+    pub(crate) fn timestamp(&self) -> Option<u64> {
+        if self.hour > 23 || self.minute > 59 || self.second > 59 || self.millis > 999 {
+            return None;
+        }
+        Self::from_date(self.year, self.month, self.day)?;
+        let days = u64::try_from(days_from_civil(self.year, self.month, self.day)).ok()?;
+        let millis = ((self.hour as u64 * 60 + self.minute as u64) * 60 + self.second as u64)
+            * 1000
+            + self.millis as u64;
+        days.checked_mul(MILLIS_PER_DAY)?.checked_add(millis)
+    }
+
+    /// Дата в виде `YYYY-MM-DD`
+    pub(crate) fn to_iso_date(self) -> String {
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+
+    /// Дата и время в виде `YYYY-MM-DDTHH:MM:SS.mmmZ`
+    pub(crate) fn to_iso8601(self) -> String {
+        format!(
+            "{}T{:02}:{:02}:{:02}.{:03}Z",
+            self.to_iso_date(),
+            self.hour,
+            self.minute,
+            self.second,
+            self.millis
+        )
+    }
+}
+
+/// Разбор даты ISO 8601 в Unix time в миллисекундах.
+/// Допускаются `YYYY-MM-DD` (полночь UTC) и `YYYY-MM-DDTHH:MM:SS` с дробной частью секунд
+/// и зоной `Z` или `±HH:MM`; время без зоны считается UTC. Дробная часть точнее
+/// миллисекунд отбрасывается.
+pub(crate) fn parse_iso8601(s: &str) -> Option<u64> {
+    let (date, time) = match s.split_once('T') {
+        Some((date, time)) => (date, Some(time)),
+        None => (s, None),
+    };
+    let date = parse_date(date)?;
+    let Some(time) = time else {
+        return date.timestamp();
+    };
+
+    // Зона: Z, +HH:MM или -HH:MM после времени
+    let (time, offset) = if let Some(time) = time.strip_suffix('Z') {
+        (time, 0)
+    } else if let Some(position) = time.rfind(['+', '-']) {
+        let (time, zone) = time.split_at(position);
+        let sign = if zone.starts_with('-') { -1 } else { 1 };
+        let (hours, minutes) = zone[1..].split_once(':')?;
+        let minutes = number(hours, 2)? as i64 * 60 + number(minutes, 2)? as i64;
+        (time, sign * minutes * 60_000)
+    } else {
+        (time, 0)
+    };
+
+    let (time, millis) = match time.split_once('.') {
+        Some((time, fraction)) => {
+            if fraction.is_empty() || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            let millis = format!("{:0<3}", &fraction[..fraction.len().min(3)]);
+            (time, millis.parse().ok()?)
+        }
+        None => (time, 0),
+    };
+    let mut parts = time.split(':');
+    let date_time = DateTime {
+        hour: number(parts.next()?, 2)?,
+        minute: number(parts.next()?, 2)?,
+        second: number(parts.next()?, 2)?,
+        millis,
+        ..date
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    let timestamp = date_time.timestamp()? as i64 - offset;
+    u64::try_from(timestamp).ok()
+}
+
+/// Разбор даты `YYYY-MM-DD`
+pub(crate) fn parse_date(s: &str) -> Option<DateTime> {
+    let mut parts = s.split('-');
+    let year = number(parts.next()?, 4)?;
+    let month = number(parts.next()?, 2)?;
+    let day = number(parts.next()?, 2)?;
+    if parts.next().is_some() {
+        return None;
+    }
+    DateTime::from_date(year as i64, month, day)
+}
+
+/// Дата UTC в виде `YYMMDD`
+pub(crate) fn format_yymmdd(timestamp: u64) -> String {
+    let date = DateTime::from_timestamp(timestamp);
+    format!("{:02}{:02}{:02}", date.year % 100, date.month, date.day)
+}
+
+/// Дата `YYMMDD`, годы 70-99 относятся к XX веку
+pub(crate) fn parse_yymmdd(value: &str) -> Option<u64> {
+    if value.len() != 6 || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let year = value[..2].parse::<i64>().ok()?;
+    let year = if year < 70 { 2000 + year } else { 1900 + year };
+    DateTime::from_date(year, value[2..4].parse().ok()?, value[4..].parse().ok()?)?.timestamp()
+}
+
+// Число из заданного количества цифр
+fn number(s: &str, digits: usize) -> Option<u32> {
+    if s.len() != digits || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Количество дней от 1970-01-01, алгоритм Howard Hinnant "days_from_civil"
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month as i64 + 9) % 12) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+// Обратное преобразование, алгоритм Howard Hinnant "civil_from_days"
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamp_round_trip() {
+        for timestamp in [0, 1633036800000, 951782400123, 4102444799999] {
+            let date_time = DateTime::from_timestamp(timestamp);
+            assert_eq!(date_time.timestamp(), Some(timestamp));
+            assert_eq!(parse_iso8601(&date_time.to_iso8601()), Some(timestamp));
+        }
+        assert_eq!(
+            DateTime::from_timestamp(1633036800000).to_iso8601(),
+            "2021-09-30T21:20:00.000Z"
+        );
+        // 29 февраля високосного года
+        assert_eq!(
+            DateTime::from_timestamp(951782400123).to_iso8601(),
+            "2000-02-29T00:00:00.123Z"
+        );
+    }
+
+    #[test]
+    fn test_parse_iso8601() {
+        assert_eq!(parse_iso8601("2021-10-01"), Some(1633046400000));
+        assert_eq!(parse_iso8601("2021-10-01T00:00:00"), Some(1633046400000));
+        assert_eq!(
+            parse_iso8601("2021-10-01T03:00:00+03:00"),
+            Some(1633046400000)
+        );
+        assert_eq!(
+            parse_iso8601("2021-09-30T23:00:00.5-01:00"),
+            Some(1633046400500)
+        );
+        assert_eq!(
+            parse_iso8601("2021-10-01T00:00:00.123456Z"),
+            Some(1633046400123)
+        );
+
+        for invalid in [
+            "",
+            "2021-13-01",
+            "2021-02-29",
+            "2021-10-1",
+            "2021-10-01T24:00:00Z",
+            "2021-10-01T00:00Z",
+            "2021-10-01T00:00:00.Z",
+            "2021-10-01T00:00:00+0300",
+            "1969-12-31T23:59:59Z",
+        ] {
+            assert_eq!(parse_iso8601(invalid), None, "{}", invalid);
+        }
+    }
+}
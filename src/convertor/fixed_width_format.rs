@@ -0,0 +1,817 @@
+use std::{fmt::Display, io::BufRead, str::FromStr};
+
+use crate::{
+    DataFormat,
+    convertor::{BankRecord, Currency, Message, StatusTransaction, TypeTransaction},
+    error::{AppError, Location, ParseError, Result},
+};
+
+/// Поле записи, которому соответствует колонка фиксированной ширины
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordField {
+    /// TX_ID
+    TxId,
+    /// TX_TYPE
+    TxType,
+    /// FROM_USER_ID
+    FromUserId,
+    /// TO_USER_ID
+    ToUserId,
+    /// AMOUNT
+    Amount,
+    /// TIMESTAMP
+    Timestamp,
+    /// STATUS
+    Status,
+    /// DESCRIPTION
+    Description,
+    /// CURRENCY, необязательное поле
+    Currency,
+}
+
+impl RecordField {
+    const ALL: [RecordField; 9] = [
+        RecordField::TxId,
+        RecordField::TxType,
+        RecordField::FromUserId,
+        RecordField::ToUserId,
+        RecordField::Amount,
+        RecordField::Timestamp,
+        RecordField::Status,
+        RecordField::Description,
+        RecordField::Currency,
+    ];
+
+    /// Имя поля, как в остальных форматах
+    pub fn name(self) -> &'static str {
+        match self {
+            RecordField::TxId => "TX_ID",
+            RecordField::TxType => "TX_TYPE",
+            RecordField::FromUserId => "FROM_USER_ID",
+            RecordField::ToUserId => "TO_USER_ID",
+            RecordField::Amount => "AMOUNT",
+            RecordField::Timestamp => "TIMESTAMP",
+            RecordField::Status => "STATUS",
+            RecordField::Description => "DESCRIPTION",
+            RecordField::Currency => "CURRENCY",
+        }
+    }
+
+    fn is_integer(self) -> bool {
+        matches!(
+            self,
+            RecordField::TxId
+                | RecordField::FromUserId
+                | RecordField::ToUserId
+                | RecordField::Amount
+                | RecordField::Timestamp
+        )
+    }
+}
+
+impl Display for RecordField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl RecordField {
+    fn from_name(name: &str) -> Option<Self> {
+        RecordField::ALL
+            .into_iter()
+            .find(|field| field.name() == name)
+    }
+}
+
+impl FromStr for RecordField {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        RecordField::from_name(s)
+            .ok_or_else(|| AppError::LayoutError(format!("unknown field {:?}", s)))
+    }
+}
+
+/// Выравнивание значения внутри колонки
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    /// Значение в начале колонки, заполнитель справа
+    Left,
+    /// Значение в конце колонки, заполнитель слева
+    Right,
+}
+
+/// Колонка записи фиксированной ширины
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedWidthField {
+    /// Поле записи
+    pub field: RecordField,
+    /// Смещение от начала строки в символах, начиная с 0
+    pub offset: usize,
+    /// Ширина в символах
+    pub width: usize,
+    /// Символ, которым значение дополняется до ширины колонки
+    pub padding: char,
+    /// Выравнивание значения
+    pub alignment: Alignment,
+}
+
+impl FixedWidthField {
+    /// Значение, дополненное до ширины колонки. Отрицательное число с заполнителем `0`
+    /// пишется со знаком в начале колонки: `-0000123`.
+    fn pad(&self, value: &str) -> std::result::Result<String, String> {
+        let len = value.chars().count();
+        if len > self.width {
+            return Err(format!(
+                "value {:?} is longer than {} characters",
+                value, self.width
+            ));
+        }
+        if value.contains(['\n', '\r']) {
+            return Err("value contains a line break".to_string());
+        }
+        // Заполнитель на краю значения при чтении отбрасывается вместе с заполнением
+        let zero = self.field.is_integer() && self.padding == '0';
+        let edge = match self.alignment {
+            Alignment::Left => value.ends_with(self.padding),
+            Alignment::Right => value.starts_with(self.padding),
+        };
+        if edge && !(zero && value == "0") {
+            return Err(format!(
+                "value {:?} {} with the padding character {:?}",
+                value,
+                match self.alignment {
+                    Alignment::Left => "ends",
+                    Alignment::Right => "starts",
+                },
+                self.padding
+            ));
+        }
+
+        let fill = self.padding.to_string().repeat(self.width - len);
+        Ok(match self.alignment {
+            Alignment::Left => format!("{}{}", value, fill),
+            Alignment::Right => match value.strip_prefix('-') {
+                Some(digits) if zero => format!("-{}{}", fill, digits),
+                _ => format!("{}{}", fill, value),
+            },
+        })
+    }
+
+    /// Значение колонки без заполнителя
+    fn unpad<'a>(&self, raw: &'a str) -> std::borrow::Cow<'a, str> {
+        let zero = self.field.is_integer() && self.padding == '0';
+        let value = match self.alignment {
+            Alignment::Left => raw.trim_end_matches(self.padding),
+            Alignment::Right => match raw.strip_prefix('-') {
+                Some(digits) if zero => {
+                    return format!("-{}", digits.trim_start_matches('0')).into();
+                }
+                _ => raw.trim_start_matches(self.padding),
+            },
+        };
+        // Ноль целиком состоит из заполнителя
+        if value.is_empty() && zero {
+            return "0".into();
+        }
+        value.into()
+    }
+}
+
+/// Раскладка записи фиксированной ширины: колонки полей в строке.
+/// Промежутки между колонками заполняются пробелами и при чтении не проверяются.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedWidthLayout {
+    fields: Vec<FixedWidthField>,
+}
+
+impl FixedWidthLayout {
+    /// Раскладка из колонок. Все поля, кроме CURRENCY, обязательны,
+    /// колонки не должны пересекаться.
+    pub fn new(mut fields: Vec<FixedWidthField>) -> Result<Self> {
+        fields.sort_by_key(|field| field.offset);
+        for field in &fields {
+            if field.width == 0 {
+                return Err(AppError::LayoutError(format!(
+                    "{} has zero width",
+                    field.field
+                )));
+            }
+            if field.padding.is_control() {
+                return Err(AppError::LayoutError(format!(
+                    "{} has control padding character {:?}",
+                    field.field, field.padding
+                )));
+            }
+            // Заполнитель числа не должен читаться как его часть
+            if field.field.is_integer()
+                && field.padding != '0'
+                && (field.padding.is_ascii_digit() || field.padding == '-')
+            {
+                return Err(AppError::LayoutError(format!(
+                    "{} cannot be padded with {:?}",
+                    field.field, field.padding
+                )));
+            }
+            // Нули справа от числа неотличимы от его цифр
+            if field.field.is_integer()
+                && field.padding == '0'
+                && field.alignment == Alignment::Left
+            {
+                return Err(AppError::LayoutError(format!(
+                    "{} padded with '0' must be right-aligned",
+                    field.field
+                )));
+            }
+        }
+        for pair in fields.windows(2) {
+            if pair[0].offset + pair[0].width > pair[1].offset {
+                return Err(AppError::LayoutError(format!(
+                    "{} overlaps {}",
+                    pair[0].field, pair[1].field
+                )));
+            }
+        }
+        for field in RecordField::ALL {
+            match fields.iter().filter(|f| f.field == field).count() {
+                0 if field != RecordField::Currency => {
+                    return Err(AppError::LayoutError(format!("{} is missing", field)));
+                }
+                0 | 1 => {}
+                _ => {
+                    return Err(AppError::LayoutError(format!(
+                        "{} is defined more than once",
+                        field
+                    )));
+                }
+            }
+        }
+        Ok(Self { fields })
+    }
+
+    /// Колонки в порядке смещения
+    pub fn fields(&self) -> &[FixedWidthField] {
+        &self.fields
+    }
+
+    /// Длина строки записи в символах
+    pub fn record_length(&self) -> usize {
+        self.fields
+            .iter()
+            .map(|field| field.offset + field.width)
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn has(&self, field: RecordField) -> bool {
+        self.fields.iter().any(|f| f.field == field)
+    }
+}
+
+impl Default for FixedWidthLayout {
+    /// Восемь полей YPBank без промежутков, 217 символов: числа дополняются нулями слева,
+    /// текст - пробелами справа
+    fn default() -> Self {
+        let field = |field: RecordField, offset, width| {
+            let (padding, alignment) = match field.is_integer() {
+                true => ('0', Alignment::Right),
+                false => (' ', Alignment::Left),
+            };
+            FixedWidthField {
+                field,
+                offset,
+                width,
+                padding,
+                alignment,
+            }
+        };
+        Self::new(vec![
+            field(RecordField::TxId, 0, 20),
+            field(RecordField::TxType, 20, 10),
+            field(RecordField::FromUserId, 30, 20),
+            field(RecordField::ToUserId, 50, 20),
+            field(RecordField::Amount, 70, 20),
+            field(RecordField::Timestamp, 90, 20),
+            field(RecordField::Status, 110, 7),
+            field(RecordField::Description, 117, 100),
+        ])
+        .expect("default layout is valid")
+    }
+}
+
+/// Описание раскладки: по строке на колонку `NAME,OFFSET,WIDTH,PADDING,ALIGNMENT`,
+/// например `AMOUNT,70,20,0,right`. Пустые строки и строки с `#` пропускаются.
+impl FromStr for FixedWidthLayout {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut fields = Vec::new();
+        for (number, line) in (1..).zip(s.lines()) {
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+            let error =
+                |message: String| AppError::LayoutError(format!("line {}: {}", number, message));
+            let parts = line.split(',').collect::<Vec<_>>();
+            let [name, offset, width, padding, alignment] = parts[..] else {
+                return Err(error(format!(
+                    "expected NAME,OFFSET,WIDTH,PADDING,ALIGNMENT, found {:?}",
+                    line
+                )));
+            };
+            let integer = |value: &str, name: &str| {
+                value
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| error(format!("invalid {} {:?}", name, value)))
+            };
+            // Заполнитель не обрезается: им может быть пробел
+            let mut chars = padding.chars();
+            let (Some(padding), None) = (chars.next(), chars.next()) else {
+                return Err(error(format!(
+                    "padding must be one character, found {:?}",
+                    padding
+                )));
+            };
+            fields.push(FixedWidthField {
+                field: RecordField::from_name(name.trim())
+                    .ok_or_else(|| error(format!("unknown field {:?}", name.trim())))?,
+                offset: integer(offset, "offset")?,
+                width: integer(width, "width")?,
+                padding,
+                alignment: match alignment.trim().to_ascii_lowercase().as_str() {
+                    "left" => Alignment::Left,
+                    "right" => Alignment::Right,
+                    _ => return Err(error(format!("invalid alignment {:?}", alignment))),
+                },
+            });
+        }
+        Self::new(fields)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct FixedWidthYPBankRecord {
+    data: Vec<Message>,
+}
+
+impl FixedWidthYPBankRecord {
+    pub fn from_read<R: std::io::Read>(r: R) -> Result<Self> {
+        let data = FixedWidthRecordReader::new(r).collect::<Result<Vec<_>>>()?;
+        Ok(Self { data })
+    }
+
+    pub fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+}
+
+/// Потоковый читатель записей фиксированной ширины, по записи на строку.
+/// Пустые строки пропускаются, недостающие в конце строки символы считаются пробелами.
+pub struct FixedWidthRecordReader<R: std::io::Read> {
+    lines: std::io::Lines<std::io::BufReader<R>>,
+    finished: bool,
+    source: Option<String>,
+    layout: FixedWidthLayout,
+    // Номер последней прочитанной строки
+    line: u64,
+    // Количество прочитанных записей
+    record: u64,
+}
+
+impl<R: std::io::Read> FixedWidthRecordReader<R> {
+    pub fn new(r: R) -> Self {
+        Self {
+            lines: std::io::BufReader::new(r).lines(),
+            finished: false,
+            source: None,
+            layout: FixedWidthLayout::default(),
+            line: 0,
+            record: 0,
+        }
+    }
+
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    pub fn with_layout(mut self, layout: FixedWidthLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    fn parse_line(&self, line: &str) -> std::result::Result<Message, (RecordField, String)> {
+        let chars = line.chars().collect::<Vec<_>>();
+        let mut message = Message {
+            tx_id: 0,
+            tx_type: TypeTransaction::Deposit,
+            from_user_id: 0,
+            to_user_id: 0,
+            amount: 0,
+            timestamp: 0,
+            status: StatusTransaction::Success,
+            description: String::new(),
+            currency: None,
+        };
+        for field in self.layout.fields() {
+            let raw = (field.offset..field.offset + field.width)
+                .map(|i| chars.get(i).copied().unwrap_or(' '))
+                .collect::<String>();
+            let value = field.unpad(&raw);
+            let value = value.as_ref();
+            let error = |message: String| (field.field, message);
+            let integer = || format!("invalid integer {:?}", value);
+            match field.field {
+                RecordField::TxId => message.tx_id = value.parse().map_err(|_| error(integer()))?,
+                RecordField::TxType => {
+                    message.tx_type = match value {
+                        "DEPOSIT" => TypeTransaction::Deposit,
+                        "TRANSFER" => TypeTransaction::Transfer,
+                        "WITHDRAWAL" => TypeTransaction::Withdrawal,
+                        _ => return Err(error(format!("unknown TX_TYPE {:?}", value))),
+                    }
+                }
+                RecordField::FromUserId => {
+                    message.from_user_id = value.parse().map_err(|_| error(integer()))?
+                }
+                RecordField::ToUserId => {
+                    message.to_user_id = value.parse().map_err(|_| error(integer()))?
+                }
+                RecordField::Amount => {
+                    message.amount = value.parse().map_err(|_| error(integer()))?
+                }
+                RecordField::Timestamp => {
+                    message.timestamp = value.parse().map_err(|_| error(integer()))?
+                }
+                RecordField::Status => {
+                    message.status = match value {
+                        "SUCCESS" => StatusTransaction::Success,
+                        "FAILURE" => StatusTransaction::Failure,
+                        "PENDING" => StatusTransaction::Pending,
+                        _ => return Err(error(format!("unknown STATUS {:?}", value))),
+                    }
+                }
+                RecordField::Description => message.description = value.to_string(),
+                // Пустая колонка - запись без валюты
+                RecordField::Currency if value.trim().is_empty() => {}
+                RecordField::Currency => {
+                    message.currency =
+                        Some(Currency::from_code(value.trim()).map_err(|e| error(e.to_string()))?)
+                }
+            }
+        }
+        Ok(message)
+    }
+}
+
+impl<R: std::io::Read> Iterator for FixedWidthRecordReader<R> {
+    type Item = Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            let line = match self.lines.next() {
+                Some(Ok(line)) => line,
+                Some(Err(e)) => {
+                    self.finished = true;
+                    return Some(Err(AppError::IOError(e)));
+                }
+                None => {
+                    self.finished = true;
+                    return None;
+                }
+            };
+            self.line += 1;
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() {
+                continue;
+            }
+            self.record += 1;
+            return Some(self.parse_line(line).map_err(|(field, message)| {
+                AppError::FixedWidthParseError(Box::new(ParseError {
+                    location: Location {
+                        source: self.source.clone(),
+                        line: Some(self.line),
+                        record: Some(self.record),
+                        field: Some(field.to_string()),
+                        ..Location::default()
+                    },
+                    message,
+                }))
+            }));
+        }
+        None
+    }
+}
+
+/// Потоковый писатель записей фиксированной ширины, по строке на запись
+pub struct FixedWidthRecordWriter<W: std::io::Write> {
+    writer: W,
+    layout: FixedWidthLayout,
+}
+
+impl<W: std::io::Write> FixedWidthRecordWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            layout: FixedWidthLayout::default(),
+        }
+    }
+
+    pub fn with_layout(mut self, layout: FixedWidthLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub fn write_message(&mut self, message: &Message) -> Result<()> {
+        let mapping_error = |field: RecordField, reason: String| AppError::MappingError {
+            format: DataFormat::FIXEDWIDTH,
+            tx_id: message.tx_id,
+            reason: format!("{}: {}", field, reason),
+        };
+        if let Some(currency) = &message.currency
+            && !self.layout.has(RecordField::Currency)
+        {
+            return Err(mapping_error(
+                RecordField::Currency,
+                format!("{} has no column in the layout", currency),
+            ));
+        }
+
+        let mut line = vec![' '; self.layout.record_length()];
+        for field in self.layout.fields() {
+            let value = match field.field {
+                RecordField::TxId => message.tx_id.to_string(),
+                RecordField::TxType => message.tx_type.to_string(),
+                RecordField::FromUserId => message.from_user_id.to_string(),
+                RecordField::ToUserId => message.to_user_id.to_string(),
+                RecordField::Amount => message.amount.to_string(),
+                RecordField::Timestamp => message.timestamp.to_string(),
+                RecordField::Status => message.status.to_string(),
+                RecordField::Description => message.description.clone(),
+                RecordField::Currency => message
+                    .currency
+                    .as_ref()
+                    .map(|currency| currency.code().to_string())
+                    .unwrap_or_default(),
+            };
+            // Запись без валюты - колонка из пробелов
+            let padded = match field.field {
+                RecordField::Currency if value.is_empty() => " ".repeat(field.width),
+                _ => field
+                    .pad(&value)
+                    .map_err(|reason| mapping_error(field.field, reason))?,
+            };
+            for (i, c) in padded.chars().enumerate() {
+                line[field.offset + i] = c;
+            }
+        }
+        let mut line = line.into_iter().collect::<String>();
+        line.push('\n');
+        self.writer.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl Default for FixedWidthYPBankRecord {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BankRecord for FixedWidthYPBankRecord {
+    fn write_to<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        let mut writer = FixedWidthRecordWriter::new(writer);
+        for message in &self.data {
+            writer.write_message(message)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+
+    fn push(&mut self, value: Message) {
+        self.data.push(value);
+    }
+
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn pop(&mut self) -> Option<Message> {
+        self.data.pop()
+    }
+
+    fn iter(&self) -> std::slice::Iter<'_, Message> {
+        self.data.iter()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convertor::test_records::transaction;
+
+    fn message(tx_id: u64) -> Message {
+        Message {
+            amount: -12345,
+            status: StatusTransaction::Pending,
+            description: "Record, \"quoted\"".to_string(),
+            currency: None,
+            ..transaction(tx_id, TypeTransaction::Transfer, 9223372036854775807, 0)
+        }
+    }
+
+    fn write(messages: &[Message], layout: &FixedWidthLayout) -> Result<String> {
+        let mut writer = FixedWidthRecordWriter::new(Vec::new()).with_layout(layout.clone());
+        for message in messages {
+            writer.write_message(message)?;
+        }
+        Ok(String::from_utf8(writer.finish()?).unwrap())
+    }
+
+    #[test]
+    fn test_default_layout() {
+        let layout = FixedWidthLayout::default();
+        assert_eq!(layout.record_length(), 217);
+        let mut zero = message(2);
+        zero.amount = 0;
+        zero.description = String::new();
+        let text = write(&[message(1), zero.clone()], &layout).unwrap();
+
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0].len(), 217);
+        assert_eq!(
+            &lines[0][..117],
+            "00000000000000000001TRANSFER  0922337203685477580700000000000000000000-0000000000000012345\
+             00000001633036800123PENDING"
+        );
+        assert_eq!(lines[0][117..].trim_end(), "Record, \"quoted\"");
+        assert_eq!(&lines[1][70..90], "00000000000000000000");
+
+        let data = FixedWidthRecordReader::new(text.as_bytes())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(data, vec![message(1), zero]);
+    }
+
+    #[test]
+    fn test_custom_layout() {
+        // Колонки в произвольном порядке с промежутком и валютой
+        let layout: FixedWidthLayout = "
+            # поле,смещение,ширина,заполнитель,выравнивание
+            STATUS,0,7, ,left
+            TX_ID,8,6,0,right
+            TX_TYPE,14,10,*,right
+            FROM_USER_ID,24,19, ,right
+            TO_USER_ID,43,3,0,right
+            AMOUNT,46,8, ,right
+            TIMESTAMP,54,13,0,right
+            CURRENCY,67,3, ,left
+            DESCRIPTION,70,20,.,left
+        "
+        .parse()
+        .unwrap();
+        let mut message = message(42);
+        message.currency = Some(Currency::from_code("EUR").unwrap());
+        let text = write(&[message.clone()], &layout).unwrap();
+        assert_eq!(
+            text,
+            "PENDING 000042**TRANSFER9223372036854775807000  -123451633036800123EURRecord, \"quoted\"....\n"
+        );
+        let data = FixedWidthRecordReader::new(text.as_bytes())
+            .with_layout(layout)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(data, vec![message]);
+    }
+
+    #[test]
+    fn test_layout_errors() {
+        let errors = [
+            (
+                "TX_ID,0,20,0,right",
+                "Fixed-width layout error: TX_TYPE is missing",
+            ),
+            (
+                "TX_ID,0,0,0,right",
+                "Fixed-width layout error: TX_ID has zero width",
+            ),
+            (
+                "NAME,0,20,0,right",
+                "Fixed-width layout error: line 1: unknown field \"NAME\"",
+            ),
+            (
+                "TX_ID,0,20,00,right",
+                "Fixed-width layout error: line 1: padding must be one character, found \"00\"",
+            ),
+            (
+                "TX_ID,0,20,0,center",
+                "Fixed-width layout error: line 1: invalid alignment \"center\"",
+            ),
+            (
+                "TX_ID,0,20,1,right",
+                "Fixed-width layout error: TX_ID cannot be padded with '1'",
+            ),
+            (
+                "TX_ID,0,20,0,left",
+                "Fixed-width layout error: TX_ID padded with '0' must be right-aligned",
+            ),
+        ];
+        for (layout, expected) in errors {
+            assert_eq!(
+                layout.parse::<FixedWidthLayout>().unwrap_err().to_string(),
+                expected
+            );
+        }
+
+        let mut fields = FixedWidthLayout::default().fields().to_vec();
+        fields[1].offset = 19;
+        assert_eq!(
+            FixedWidthLayout::new(fields.clone())
+                .unwrap_err()
+                .to_string(),
+            "Fixed-width layout error: TX_ID overlaps TX_TYPE"
+        );
+        fields[1].offset = 20;
+        fields.push(fields[0]);
+        fields[8].offset = 300;
+        assert_eq!(
+            FixedWidthLayout::new(fields).unwrap_err().to_string(),
+            "Fixed-width layout error: TX_ID is defined more than once"
+        );
+    }
+
+    #[test]
+    fn test_write_errors() {
+        let layout = FixedWidthLayout::default();
+        let mut long = message(1);
+        long.description = "x".repeat(101);
+        let mut padded = message(2);
+        padded.description = "trailing space ".to_string();
+        let mut multiline = message(3);
+        multiline.description = "a\nb".to_string();
+        let mut currency = message(4);
+        currency.currency = Some(Currency::from_code("EUR").unwrap());
+
+        let errors = [long, padded, multiline, currency]
+            .iter()
+            .map(|message| {
+                write(std::slice::from_ref(message), &layout)
+                    .unwrap_err()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                format!(
+                    "FIXEDWIDTH mapping error: TX_ID 1: DESCRIPTION: value {:?} is longer than 100 characters",
+                    "x".repeat(101)
+                ),
+                "FIXEDWIDTH mapping error: TX_ID 2: DESCRIPTION: value \"trailing space \" ends with the padding character ' '".to_string(),
+                "FIXEDWIDTH mapping error: TX_ID 3: DESCRIPTION: value contains a line break".to_string(),
+                "FIXEDWIDTH mapping error: TX_ID 4: CURRENCY: EUR has no column in the layout".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let layout = FixedWidthLayout::default();
+        let line = write(&[message(1)], &layout).unwrap();
+        let bad_type = line.replacen("TRANSFER  ", "REFUND    ", 1);
+        let bad_amount = line.replacen("-0000000000000012345", "-00000000000000123x5", 1);
+        // Пробелы в конце строки обрезаны редактором
+        let trimmed = line.trim_end().to_string() + "\n";
+        let input = format!("{}\n{}{}{}", bad_type, bad_amount, trimmed, "short\n");
+
+        let results = FixedWidthRecordReader::new(input.as_bytes())
+            .with_source("records.dat")
+            .collect::<Vec<_>>();
+        assert_eq!(results.len(), 4);
+        assert_eq!(
+            results[0].as_ref().unwrap_err().to_string(),
+            "records.dat:1: TX_TYPE: unknown TX_TYPE \"REFUND\""
+        );
+        assert_eq!(
+            results[1].as_ref().unwrap_err().to_string(),
+            "records.dat:3: AMOUNT: invalid integer \"-123x5\""
+        );
+        assert_eq!(results[2].as_ref().unwrap(), &message(1));
+        let error = results[3].as_ref().unwrap_err();
+        assert!(error.is_record_error());
+        assert_eq!(
+            error.to_string(),
+            "records.dat:5: TX_ID: invalid integer \"short               \""
+        );
+    }
+}
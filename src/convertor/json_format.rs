@@ -0,0 +1,346 @@
+use std::io::{BufRead, Read};
+
+use serde::Deserialize;
+
+use crate::{
+    convertor::{BankRecord, Message},
+    error::{AppError, Location, ParseError, Result},
+};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct JsonYPBankRecord {
+    data: Vec<Message>,
+}
+
+impl JsonYPBankRecord {
+    pub fn from_read<R: std::io::Read>(r: R) -> Result<Self> {
+        let data = JsonRecordReader::new(r).collect::<Result<Vec<_>>>()?;
+        Ok(Self { data })
+    }
+
+    pub fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+}
+
+/// Буферизованный читатель с подсчетом прочитанных строк.
+/// serde_json читает значение побайтно и не забирает байты после закрывающей скобки объекта,
+/// поэтому разделители массива между записями читаются здесь.
+struct LineReader<R: std::io::Read> {
+    reader: std::io::BufReader<R>,
+    // Количество прочитанных переводов строки
+    lines: u64,
+}
+
+impl<R: std::io::Read> LineReader<R> {
+    /// Пропуск пробельных символов, возвращает следующий байт без чтения
+    fn peek(&mut self) -> std::io::Result<Option<u8>> {
+        loop {
+            let Some(&byte) = self.reader.fill_buf()?.first() else {
+                return Ok(None);
+            };
+            if !byte.is_ascii_whitespace() {
+                return Ok(Some(byte));
+            }
+            self.consume();
+        }
+    }
+
+    fn consume(&mut self) {
+        if self.reader.buffer().first() == Some(&b'\n') {
+            self.lines += 1;
+        }
+        self.reader.consume(1);
+    }
+}
+
+impl<R: std::io::Read> Read for LineReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        self.lines += buf[..n].iter().filter(|&&b| b == b'\n').count() as u64;
+        Ok(n)
+    }
+}
+
+/// Потоковый читатель JSON массива, возвращает записи по одной
+pub struct JsonRecordReader<R: std::io::Read> {
+    reader: LineReader<R>,
+    started: bool,
+    finished: bool,
+    source: Option<String>,
+    // Количество прочитанных записей
+    record: u64,
+}
+
+impl<R: std::io::Read> JsonRecordReader<R> {
+    pub fn new(r: R) -> Self {
+        Self {
+            reader: LineReader {
+                reader: std::io::BufReader::new(r),
+                lines: 0,
+            },
+            started: false,
+            finished: false,
+            source: None,
+            record: 0,
+        }
+    }
+
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    fn read_message(&mut self) -> Result<Option<Message>> {
+        if !self.started {
+            self.started = true;
+            match self.reader.peek()? {
+                // Пустой файл не содержит записей
+                None => return Ok(None),
+                Some(b'[') => self.reader.consume(),
+                Some(byte) => return Err(self.syntax_error(unexpected("'['", byte))),
+            }
+            if self.reader.peek()? == Some(b']') {
+                return self.end_of_array();
+            }
+        } else {
+            match self.reader.peek()? {
+                Some(b',') => self.reader.consume(),
+                Some(b']') => return self.end_of_array(),
+                Some(byte) => return Err(self.syntax_error(unexpected("',' or ']'", byte))),
+                None => return Err(self.syntax_error("missing closing ']'".to_string())),
+            }
+        }
+
+        // Строка записи - строка ее открывающей скобки
+        self.reader.peek()?;
+        self.record += 1;
+        let line = self.reader.lines + 1;
+        let value = serde_json::Value::deserialize(&mut serde_json::Deserializer::from_reader(
+            &mut self.reader,
+        ))
+        .map_err(|e| {
+            if e.is_io() {
+                return AppError::JsonError(e);
+            }
+            AppError::JsonSyntaxError(Box::new(ParseError {
+                location: self.location(line + e.line().saturating_sub(1) as u64),
+                message: json_error_message(&e),
+            }))
+        })?;
+        // После числа serde_json забирает следующий байт, разделитель массива теряется
+        if value.is_number() {
+            return Err(self.syntax_error("expected an object, found a number".to_string()));
+        }
+
+        serde_json::from_value(value)
+            .map(Some)
+            .map_err(|e| json_parse_error(self.location(line), &e))
+    }
+
+    fn end_of_array(&mut self) -> Result<Option<Message>> {
+        self.reader.consume();
+        match self.reader.peek()? {
+            None => Ok(None),
+            Some(byte) => Err(self.syntax_error(unexpected("end of file", byte))),
+        }
+    }
+
+    fn location(&self, line: u64) -> Location {
+        Location {
+            source: self.source.clone(),
+            line: Some(line),
+            record: Some(self.record),
+            ..Location::default()
+        }
+    }
+
+    fn syntax_error(&self, message: String) -> AppError {
+        AppError::JsonSyntaxError(Box::new(ParseError {
+            location: self.location(self.reader.lines + 1),
+            message,
+        }))
+    }
+}
+
+fn unexpected(expected: &str, byte: u8) -> String {
+    format!("expected {}, found {:?}", expected, byte as char)
+}
+
+/// Текст ошибки serde_json без позиции, позиция указывается в Location
+pub(crate) fn json_error_message(e: &serde_json::Error) -> String {
+    let message = e.to_string();
+    match message.rsplit_once(" at line ") {
+        Some((message, _)) if e.line() != 0 => message.to_string(),
+        _ => message,
+    }
+}
+
+/// Ошибка данных записи, чтение продолжается со следующей записи
+pub(crate) fn json_parse_error(location: Location, e: &serde_json::Error) -> AppError {
+    AppError::JsonParseError(Box::new(ParseError {
+        location,
+        message: json_error_message(e),
+    }))
+}
+
+impl<R: std::io::Read> Iterator for JsonRecordReader<R> {
+    type Item = Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        match self.read_message() {
+            Ok(Some(message)) => Some(Ok(message)),
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(e) => {
+                // После синтаксической ошибки граница следующей записи неизвестна
+                if !e.is_record_error() {
+                    self.finished = true;
+                }
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Потоковый писатель JSON массива, по одной записи в строке
+pub struct JsonRecordWriter<W: std::io::Write> {
+    writer: W,
+    // Количество записанных записей
+    records: u64,
+}
+
+impl<W: std::io::Write> JsonRecordWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer, records: 0 }
+    }
+
+    pub fn write_message(&mut self, message: &Message) -> Result<()> {
+        let separator: &[u8] = if self.records == 0 { b"[\n" } else { b",\n" };
+        self.writer.write_all(separator)?;
+        serde_json::to_writer(&mut self.writer, message)?;
+        self.records += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W> {
+        if self.records == 0 {
+            self.writer.write_all(b"[")?;
+        }
+        self.writer.write_all(b"\n]\n")?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl Default for JsonYPBankRecord {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BankRecord for JsonYPBankRecord {
+    fn write_to<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        let mut writer = JsonRecordWriter::new(writer);
+        for message in &self.data {
+            writer.write_message(message)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+
+    fn push(&mut self, value: Message) {
+        self.data.push(value);
+    }
+
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn pop(&mut self) -> Option<Message> {
+        self.data.pop()
+    }
+
+    fn iter(&self) -> std::slice::Iter<'_, Message> {
+        self.data.iter()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+    use crate::convertor::TxtYPBankRecord;
+
+    static TEST_TXT: &str = "tests/data/examples_file/records_example.txt";
+    static TEST_JSON: &str = "tests/data/examples_file/records_example.json";
+
+    #[test]
+    fn test_read() {
+        let data = JsonYPBankRecord::from_read(File::open(TEST_JSON).unwrap()).unwrap();
+        let txt = TxtYPBankRecord::from_read(File::open(TEST_TXT).unwrap()).unwrap();
+        assert!(data.iter().eq(txt.iter()));
+    }
+
+    #[test]
+    fn test_write() {
+        let data = JsonYPBankRecord::from_read(File::open(TEST_JSON).unwrap()).unwrap();
+
+        let mut buf = Vec::new();
+        data.write_to(&mut buf).unwrap();
+        assert_eq!(buf, std::fs::read(TEST_JSON).unwrap());
+
+        let mut buf = Vec::new();
+        JsonYPBankRecord::new().write_to(&mut buf).unwrap();
+        assert_eq!(buf, b"[\n]\n");
+        assert!(
+            JsonYPBankRecord::from_read(buf.as_slice())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let input = r#"[
+{"TX_ID":1,"TX_TYPE":"DEPOSIT","FROM_USER_ID":0,"TO_USER_ID":2,"AMOUNT":-3,"TIMESTAMP":4,"STATUS":"SUCCESS","DESCRIPTION":"a"},
+{"TX_ID":2,"TX_TYPE":"LOAN","FROM_USER_ID":0,"TO_USER_ID":2,"AMOUNT":3,"TIMESTAMP":4,"STATUS":"SUCCESS","DESCRIPTION":"b"},
+{"TX_ID":3,"TX_TYPE":"DEPOSIT","FROM_USER_ID":0,"TO_USER_ID":2,"AMOUNT":3,"TIMESTAMP":4,"STATUS":"SUCCESS","DESCRIPTION":"c","CURRENCY":"EUR"},
+{"TX_ID":4,
+"TX_TYPE" "DEPOSIT"},
+{"TX_ID":5}
+]"#;
+        let results = JsonRecordReader::new(input.as_bytes())
+            .with_source("records.json")
+            .collect::<Vec<_>>();
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].as_ref().unwrap().amount, -3);
+        assert_eq!(
+            results[1].as_ref().unwrap_err().to_string(),
+            "records.json:3: unknown variant `LOAN`, expected one of `DEPOSIT`, `TRANSFER`, `WITHDRAWAL`"
+        );
+        assert!(results[1].as_ref().unwrap_err().is_record_error());
+        assert!(results[2].as_ref().unwrap().currency.is_some());
+
+        let err = results[3].as_ref().unwrap_err();
+        assert!(!err.is_record_error());
+        assert_eq!(err.to_string(), "records.json:6: expected `:`");
+    }
+
+    #[test]
+    fn test_error_message_without_position() {
+        let e = serde_json::from_str::<Message>("{").unwrap_err();
+        assert_eq!(json_error_message(&e), "EOF while parsing an object");
+    }
+}
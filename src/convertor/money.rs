@@ -0,0 +1,429 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::error::{AppError, Result};
+
+/// Коды валют ISO 4217 и количество знаков дробной части (экспонента)
+const CURRENCIES: &[(&str, u8)] = &[
+    ("AED", 2),
+    ("AFN", 2),
+    ("ALL", 2),
+    ("AMD", 2),
+    ("ANG", 2),
+    ("AOA", 2),
+    ("ARS", 2),
+    ("AUD", 2),
+    ("AWG", 2),
+    ("AZN", 2),
+    ("BAM", 2),
+    ("BBD", 2),
+    ("BDT", 2),
+    ("BGN", 2),
+    ("BHD", 3),
+    ("BIF", 0),
+    ("BMD", 2),
+    ("BND", 2),
+    ("BOB", 2),
+    ("BOV", 2),
+    ("BRL", 2),
+    ("BSD", 2),
+    ("BTN", 2),
+    ("BWP", 2),
+    ("BYN", 2),
+    ("BZD", 2),
+    ("CAD", 2),
+    ("CDF", 2),
+    ("CHE", 2),
+    ("CHF", 2),
+    ("CHW", 2),
+    ("CLF", 4),
+    ("CLP", 0),
+    ("CNY", 2),
+    ("COP", 2),
+    ("COU", 2),
+    ("CRC", 2),
+    ("CUP", 2),
+    ("CVE", 2),
+    ("CZK", 2),
+    ("DJF", 0),
+    ("DKK", 2),
+    ("DOP", 2),
+    ("DZD", 2),
+    ("EGP", 2),
+    ("ERN", 2),
+    ("ETB", 2),
+    ("EUR", 2),
+    ("FJD", 2),
+    ("FKP", 2),
+    ("GBP", 2),
+    ("GEL", 2),
+    ("GHS", 2),
+    ("GIP", 2),
+    ("GMD", 2),
+    ("GNF", 0),
+    ("GTQ", 2),
+    ("GYD", 2),
+    ("HKD", 2),
+    ("HNL", 2),
+    ("HTG", 2),
+    ("HUF", 2),
+    ("IDR", 2),
+    ("ILS", 2),
+    ("INR", 2),
+    ("IQD", 3),
+    ("IRR", 2),
+    ("ISK", 0),
+    ("JMD", 2),
+    ("JOD", 3),
+    ("JPY", 0),
+    ("KES", 2),
+    ("KGS", 2),
+    ("KHR", 2),
+    ("KMF", 0),
+    ("KPW", 2),
+    ("KRW", 0),
+    ("KWD", 3),
+    ("KYD", 2),
+    ("KZT", 2),
+    ("LAK", 2),
+    ("LBP", 2),
+    ("LKR", 2),
+    ("LRD", 2),
+    ("LSL", 2),
+    ("LYD", 3),
+    ("MAD", 2),
+    ("MDL", 2),
+    ("MGA", 2),
+    ("MKD", 2),
+    ("MMK", 2),
+    ("MNT", 2),
+    ("MOP", 2),
+    ("MRU", 2),
+    ("MUR", 2),
+    ("MVR", 2),
+    ("MWK", 2),
+    ("MXN", 2),
+    ("MXV", 2),
+    ("MYR", 2),
+    ("MZN", 2),
+    ("NAD", 2),
+    ("NGN", 2),
+    ("NIO", 2),
+    ("NOK", 2),
+    ("NPR", 2),
+    ("NZD", 2),
+    ("OMR", 3),
+    ("PAB", 2),
+    ("PEN", 2),
+    ("PGK", 2),
+    ("PHP", 2),
+    ("PKR", 2),
+    ("PLN", 2),
+    ("PYG", 0),
+    ("QAR", 2),
+    ("RON", 2),
+    ("RSD", 2),
+    ("RUB", 2),
+    ("RWF", 0),
+    ("SAR", 2),
+    ("SBD", 2),
+    ("SCR", 2),
+    ("SDG", 2),
+    ("SEK", 2),
+    ("SGD", 2),
+    ("SHP", 2),
+    ("SLE", 2),
+    ("SOS", 2),
+    ("SRD", 2),
+    ("SSP", 2),
+    ("STN", 2),
+    ("SVC", 2),
+    ("SYP", 2),
+    ("SZL", 2),
+    ("THB", 2),
+    ("TJS", 2),
+    ("TMT", 2),
+    ("TND", 3),
+    ("TOP", 2),
+    ("TRY", 2),
+    ("TTD", 2),
+    ("TWD", 2),
+    ("TZS", 2),
+    ("UAH", 2),
+    ("UGX", 0),
+    ("USD", 2),
+    ("USN", 2),
+    ("UYI", 0),
+    ("UYU", 2),
+    ("UYW", 4),
+    ("UZS", 2),
+    ("VED", 2),
+    ("VES", 2),
+    ("VND", 0),
+    ("VUV", 0),
+    ("WST", 2),
+    ("XAF", 0),
+    ("XCD", 2),
+    ("XCG", 2),
+    ("XOF", 0),
+    ("XPF", 0),
+    ("YER", 2),
+    ("ZAR", 2),
+    ("ZMW", 2),
+    ("ZWG", 2),
+];
+
+/// Валюта по ISO 4217
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Currency {
+    code: [u8; 3],
+    exponent: u8,
+}
+
+impl Currency {
+    /// Поиск валюты по трехбуквенному коду
+    pub fn from_code(code: &str) -> Result<Self> {
+        CURRENCIES
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(c, exponent)| Currency {
+                code: c.as_bytes().try_into().expect("currency code is 3 bytes"),
+                exponent: *exponent,
+            })
+            .ok_or_else(|| AppError::MoneyError(format!("Unknown currency code: {:?}", code)))
+    }
+
+    /// Трехбуквенный код валюты
+    pub fn code(&self) -> &str {
+        // Коды берутся только из таблицы CURRENCIES и всегда состоят из ASCII
+        std::str::from_utf8(&self.code).expect("currency code is ASCII")
+    }
+
+    /// Количество знаков дробной части
+    pub fn exponent(&self) -> u8 {
+        self.exponent
+    }
+
+    pub(crate) fn as_bytes(&self) -> &[u8; 3] {
+        &self.code
+    }
+
+    fn minor_per_major(&self) -> i64 {
+        10i64.pow(self.exponent as u32)
+    }
+}
+
+impl Display for Currency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+impl FromStr for Currency {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::from_code(s)
+    }
+}
+
+impl serde::Serialize for Currency {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Currency {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        Currency::from_code(&code).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Денежная сумма в наименьших единицах валюты
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Money {
+    /// Сумма в наименьших единицах валюты (центах)
+    pub minor_units: i64,
+    /// Валюта
+    pub currency: Currency,
+}
+
+impl Money {
+    /// Создание суммы
+    pub fn new(minor_units: i64, currency: Currency) -> Self {
+        Self {
+            minor_units,
+            currency,
+        }
+    }
+
+    /// Сложение с проверкой валюты и переполнения
+    pub fn checked_add(self, other: Money) -> Result<Money> {
+        self.check_currency(&other)?;
+        self.minor_units
+            .checked_add(other.minor_units)
+            .map(|minor_units| Money::new(minor_units, self.currency))
+            .ok_or_else(|| AppError::MoneyError(format!("Overflow: {} + {}", self, other)))
+    }
+
+    /// Вычитание с проверкой валюты и переполнения
+    pub fn checked_sub(self, other: Money) -> Result<Money> {
+        self.check_currency(&other)?;
+        self.minor_units
+            .checked_sub(other.minor_units)
+            .map(|minor_units| Money::new(minor_units, self.currency))
+            .ok_or_else(|| AppError::MoneyError(format!("Overflow: {} - {}", self, other)))
+    }
+
+    fn check_currency(&self, other: &Money) -> Result<()> {
+        if self.currency != other.currency {
+            return Err(AppError::MoneyError(format!(
+                "Currency mismatch: {} and {}",
+                self.currency, other.currency
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Money {
+    /// Сумма в основных единицах без кода валюты, например `123.45`
+    pub(crate) fn to_decimal(self) -> String {
+        let sign = if self.minor_units < 0 { "-" } else { "" };
+        let abs = self.minor_units.unsigned_abs();
+        let exponent = self.currency.exponent as usize;
+        if exponent == 0 {
+            return format!("{}{}", sign, abs);
+        }
+        let divisor = self.currency.minor_per_major() as u64;
+        format!(
+            "{}{}.{:0width$}",
+            sign,
+            abs / divisor,
+            abs % divisor,
+            width = exponent
+        )
+    }
+
+    /// Разбор суммы в основных единицах без кода валюты, например `123.45`.
+    /// Знаков дробной части не больше экспоненты валюты.
+    pub(crate) fn from_decimal(amount: &str, currency: Currency) -> Result<Money> {
+        let error = || {
+            AppError::MoneyError(format!(
+                "Invalid amount {:?} for {} with {} decimals",
+                amount, currency, currency.exponent
+            ))
+        };
+
+        let (negative, digits) = match amount.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, amount),
+        };
+        let (major, minor) = digits.split_once('.').unwrap_or((digits, ""));
+
+        let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if major.is_empty()
+            || !is_digits(major)
+            || !is_digits(minor)
+            || (digits.contains('.') && minor.is_empty())
+            || minor.len() > currency.exponent as usize
+        {
+            return Err(error());
+        }
+
+        // Дополняем дробную часть нулями до экспоненты валюты
+        let scale = 10i64.pow((currency.exponent as usize - minor.len()) as u32);
+        let minor_units = major
+            .parse::<i64>()
+            .ok()
+            .and_then(|major| major.checked_mul(currency.minor_per_major()))
+            .and_then(|major| {
+                let minor = if minor.is_empty() {
+                    0
+                } else {
+                    minor.parse::<i64>().ok()?
+                };
+                major.checked_add(minor.checked_mul(scale)?)
+            })
+            .ok_or_else(error)?;
+
+        Ok(Money::new(
+            if negative { -minor_units } else { minor_units },
+            currency,
+        ))
+    }
+}
+
+impl Display for Money {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.to_decimal(), self.currency)
+    }
+}
+
+impl FromStr for Money {
+    type Err = AppError;
+
+    /// Разбор строки вида `123.45 EUR`
+    fn from_str(s: &str) -> Result<Self> {
+        let error = || AppError::MoneyError(format!("Invalid money value: {:?}", s));
+
+        let (amount, code) = s.trim().split_once(' ').ok_or_else(error)?;
+        let currency = Currency::from_code(code.trim())?;
+        Money::from_decimal(amount, currency).map_err(|_| error())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eur() -> Currency {
+        Currency::from_code("EUR").unwrap()
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Money::new(12345, eur()).to_string(), "123.45 EUR");
+        assert_eq!(Money::new(-5, eur()).to_string(), "-0.05 EUR");
+        assert_eq!(
+            Money::new(500, Currency::from_code("JPY").unwrap()).to_string(),
+            "500 JPY"
+        );
+        assert_eq!(
+            Money::new(1234, Currency::from_code("KWD").unwrap()).to_string(),
+            "1.234 KWD"
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            "123.45 EUR".parse::<Money>().unwrap(),
+            Money::new(12345, eur())
+        );
+        assert_eq!("-0.5 EUR".parse::<Money>().unwrap(), Money::new(-50, eur()));
+        assert_eq!("7 EUR".parse::<Money>().unwrap(), Money::new(700, eur()));
+        assert!("1.234 EUR".parse::<Money>().is_err());
+        assert!("1. EUR".parse::<Money>().is_err());
+        assert!("1.5 JPY".parse::<Money>().is_err());
+        assert!("12 XYZ".parse::<Money>().is_err());
+        assert!("99999999999999999999 EUR".parse::<Money>().is_err());
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let a = Money::new(100, eur());
+        assert_eq!(a.checked_add(a).unwrap(), Money::new(200, eur()));
+        assert_eq!(a.checked_sub(a).unwrap(), Money::new(0, eur()));
+        assert!(Money::new(i64::MAX, eur()).checked_add(a).is_err());
+        assert!(
+            a.checked_add(Money::new(1, Currency::from_code("USD").unwrap()))
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,951 @@
+use std::io::BufRead;
+
+use crate::{
+    DataFormat,
+    convertor::{
+        AmountPolicy, BankRecord, Currency, Message, Money, StatusTransaction, TypeTransaction,
+        datetime::{DateTime, format_yymmdd, parse_yymmdd},
+        statement::{self, Statement, StatementEntry, Statements, Transfers},
+    },
+    error::{AppError, Location, ParseError, Result},
+};
+
+// Длина строки поля и количество строк :86: по стандарту SWIFT
+const MAX_LINE_LEN: usize = 65;
+const MAX_NARRATIVE_LINES: usize = 6;
+// Длина подполя Customer Reference в :61: и суммы с запятой
+const MAX_REFERENCE_LEN: usize = 16;
+const MAX_AMOUNT_LEN: usize = 15;
+
+// Ссылка для TX_ID, который не помещается в Customer Reference
+const NONREF: &str = "NONREF";
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Mt940YPBankRecord {
+    data: Vec<Message>,
+}
+
+impl Mt940YPBankRecord {
+    pub fn from_read<R: std::io::Read>(r: R) -> Result<Self> {
+        let data = Mt940RecordReader::new(r).collect::<Result<Vec<_>>>()?;
+        Ok(Self { data })
+    }
+
+    pub fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+}
+
+/// Строка :61: вместе с описанием :86: и реквизитами выписки, в которой она находится
+#[derive(Debug)]
+struct Entry {
+    line: u64,
+    record: u64,
+    statement_line: String,
+    narrative: Option<String>,
+    account: Option<String>,
+    currency: Option<String>,
+}
+
+/// Поле, к которому относятся строки продолжения
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    StatementLine,
+    Narrative,
+    Other,
+}
+
+/// Потоковый читатель MT940, возвращает запись на каждую строку `:61:`.
+/// Перевод между двумя счетами YPBank есть в выписках обоих счетов,
+/// поэтому его повторная строка пропускается.
+pub struct Mt940RecordReader<R: std::io::Read> {
+    lines: std::io::Lines<std::io::BufReader<R>>,
+    finished: bool,
+    source: Option<String>,
+    // Номер последней прочитанной строки
+    line: u64,
+    // Количество прочитанных строк :61:
+    record: u64,
+    account: Option<String>,
+    currency: Option<String>,
+    entry: Option<Entry>,
+    field: Field,
+    transfers: Transfers,
+}
+
+impl<R: std::io::Read> Mt940RecordReader<R> {
+    pub fn new(r: R) -> Self {
+        Self {
+            lines: std::io::BufReader::new(r).lines(),
+            finished: false,
+            source: None,
+            line: 0,
+            record: 0,
+            account: None,
+            currency: None,
+            entry: None,
+            field: Field::Other,
+            transfers: Transfers::default(),
+        }
+    }
+
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Разбор строки, возвращает завершенную предыдущую запись
+    fn read_line(&mut self, line: &str) -> Option<Entry> {
+        // Блоки заголовка SWIFT {1:...}{2:...}{4: и конец сообщения -}
+        if line.starts_with('{') {
+            return None;
+        }
+        if line == "-" || line == "-}" {
+            self.account = None;
+            self.currency = None;
+            self.field = Field::Other;
+            return self.entry.take();
+        }
+
+        let Some((tag, value)) = split_tag(line) else {
+            // Дополнительные сведения :61: и продолжения других полей не используются
+            if let (Field::Narrative, Some(entry)) = (self.field, &mut self.entry) {
+                entry
+                    .narrative
+                    .get_or_insert_with(String::new)
+                    .push_str(line);
+            }
+            return None;
+        };
+
+        if tag == "86" {
+            // :86: после :62F: относится к выписке целиком и не используется
+            self.field = Field::Other;
+            if let Some(entry) = &mut self.entry {
+                entry.narrative = Some(value.to_string());
+                self.field = Field::Narrative;
+            }
+            return None;
+        }
+
+        let finished = self.entry.take();
+        self.field = Field::Other;
+        match tag {
+            "25" => self.account = Some(value.trim().to_string()),
+            "60F" | "60M" => self.currency = value.get(7..10).map(str::to_string),
+            "61" => {
+                self.record += 1;
+                self.entry = Some(Entry {
+                    line: self.line,
+                    record: self.record,
+                    statement_line: value.to_string(),
+                    narrative: None,
+                    account: self.account.clone(),
+                    currency: self.currency.clone(),
+                });
+                self.field = Field::StatementLine;
+            }
+            _ => {}
+        }
+        finished
+    }
+
+    fn finish_entry(&mut self, entry: Entry) -> Result<Option<Message>> {
+        let location = Location {
+            source: self.source.clone(),
+            line: Some(entry.line),
+            record: Some(entry.record),
+            ..Location::default()
+        };
+        let message = entry
+            .into_message(&location)
+            .map_err(|e| AppError::Mt940ParseError(Box::new(e)))?;
+        if self.transfers.is_repeated(&message) {
+            return Ok(None);
+        }
+        Ok(Some(message))
+    }
+}
+
+impl<R: std::io::Read> Iterator for Mt940RecordReader<R> {
+    type Item = Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            let entry = match self.lines.next() {
+                Some(Ok(line)) => {
+                    self.line += 1;
+                    self.read_line(line.trim_end_matches('\r'))
+                }
+                Some(Err(e)) => {
+                    self.finished = true;
+                    return Some(Err(AppError::IOError(e)));
+                }
+                None => {
+                    self.finished = true;
+                    self.entry.take()
+                }
+            };
+            if let Some(entry) = entry {
+                match self.finish_entry(entry) {
+                    Ok(Some(message)) => return Some(Ok(message)),
+                    Ok(None) => continue,
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Entry {
+    fn into_message(self, location: &Location) -> std::result::Result<Message, ParseError> {
+        let error = |field: &str, message: String| ParseError {
+            location: Location {
+                field: Some(field.to_string()),
+                ..location.clone()
+            },
+            message,
+        };
+
+        let account = self
+            .account
+            .ok_or_else(|| error("field 25", "missing account identification".to_string()))?;
+        let account = account.parse::<u64>().map_err(|_| {
+            error(
+                "field 25",
+                format!("account {:?} is not a YPBank user id", account),
+            )
+        })?;
+        let code = self
+            .currency
+            .ok_or_else(|| error("field 60F", "missing opening balance currency".to_string()))?;
+        let currency = Currency::from_code(&code)
+            .map_err(|_| error("field 60F", format!("unknown currency {:?}", code)))?;
+
+        let line = StatementLine::parse(&self.statement_line).map_err(|e| error("field 61", e))?;
+        let narrative = Narrative::parse(self.narrative.as_deref().unwrap_or_default());
+
+        let amount = line.amount.strip_suffix(',').unwrap_or(line.amount);
+        let money = Money::from_decimal(&amount.replace(',', "."), currency).map_err(|_| {
+            error(
+                "field 61",
+                format!(
+                    "invalid amount {:?}, {} allows {} decimals",
+                    line.amount,
+                    currency,
+                    currency.exponent()
+                ),
+            )
+        })?;
+
+        let reference = match (line.reference, narrative.reference) {
+            (NONREF, Some(reference)) => reference,
+            (reference, _) => reference,
+        };
+        let tx_id = reference
+            .parse::<u64>()
+            .map_err(|_| error("field 61", format!("invalid reference {:?}", reference)))?;
+
+        let tx_type = match narrative.tx_type {
+            Some(tx_type) => match tx_type {
+                "DEPOSIT" => TypeTransaction::Deposit,
+                "TRANSFER" => TypeTransaction::Transfer,
+                "WITHDRAWAL" => TypeTransaction::Withdrawal,
+                _ => return Err(error("field 86", format!("invalid TYPE {:?}", tx_type))),
+            },
+            None if line.code == "TRF" => TypeTransaction::Transfer,
+            None if line.credit => TypeTransaction::Deposit,
+            None => TypeTransaction::Withdrawal,
+        };
+        // Выписки содержат только проведенные операции
+        let status = match narrative.status {
+            None | Some("SUCCESS") => StatusTransaction::Success,
+            Some("FAILURE") => StatusTransaction::Failure,
+            Some("PENDING") => StatusTransaction::Pending,
+            Some(status) => return Err(error("field 86", format!("invalid STAT {:?}", status))),
+        };
+        let counterparty = match narrative.counterparty {
+            Some(id) => id
+                .parse::<u64>()
+                .map_err(|_| error("field 86", format!("invalid CPTY {:?}", id)))?,
+            None => 0,
+        };
+        let time = match narrative.time {
+            Some(time) => parse_time(time)
+                .ok_or_else(|| error("field 86", format!("invalid TIME {:?}", time)))?,
+            None => 0,
+        };
+
+        let (from_user_id, to_user_id) = statement::accounts(account, counterparty, line.credit);
+        Ok(Message {
+            tx_id,
+            tx_type,
+            from_user_id,
+            to_user_id,
+            amount: money.minor_units,
+            timestamp: line.date + time,
+            status,
+            description: narrative.description.to_string(),
+            currency: Some(currency),
+        })
+    }
+}
+
+/// Подполя строки выписки `:61:`
+#[derive(Debug)]
+struct StatementLine<'a> {
+    // Дата валютирования, полночь UTC
+    date: u64,
+    credit: bool,
+    amount: &'a str,
+    code: &'a str,
+    reference: &'a str,
+}
+
+impl<'a> StatementLine<'a> {
+    /// Разбор `6!n[4!n]2a[1!a]15d1!a3!c16x[//16x]`
+    fn parse(value: &'a str) -> std::result::Result<Self, String> {
+        let value_date = value.get(..6).unwrap_or(value);
+        let date = parse_yymmdd(value_date)
+            .ok_or_else(|| format!("invalid value date {:?}", value_date))?;
+        let mut rest = &value[6..];
+        // Необязательная дата проводки MMDD
+        if rest.len() >= 4 && rest.as_bytes()[..4].iter().all(u8::is_ascii_digit) {
+            rest = &rest[4..];
+        }
+
+        let credit = if rest.starts_with("RC") || rest.starts_with("RD") {
+            return Err("reversal entries are not supported".to_string());
+        } else if let Some(tail) = rest.strip_prefix('C') {
+            rest = tail;
+            true
+        } else if let Some(tail) = rest.strip_prefix('D') {
+            rest = tail;
+            false
+        } else {
+            return Err(format!("invalid debit/credit mark in {:?}", value));
+        };
+        // Необязательный код средств, третья буква кода валюты
+        if rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            rest = &rest[1..];
+        }
+
+        let amount_len = rest
+            .find(|c: char| !c.is_ascii_digit() && c != ',')
+            .unwrap_or(rest.len());
+        let (amount, rest) = rest.split_at(amount_len);
+        if amount.matches(',').count() != 1 || amount.starts_with(',') {
+            return Err(format!("invalid amount {:?}", amount));
+        }
+
+        if !rest.starts_with(['N', 'F', 'S']) || rest.len() < 4 || !rest.is_char_boundary(4) {
+            return Err(format!("invalid transaction type in {:?}", value));
+        }
+        let code = &rest[1..4];
+        let reference = rest[4..].split("//").next().unwrap_or_default();
+        Ok(Self {
+            date,
+            credit,
+            amount,
+            code,
+            reference,
+        })
+    }
+}
+
+/// Структурированное описание `:86:` YPBank: `/TYPE/.../STAT/.../TIME/.../CPTY/.../REMI/...`.
+/// Описание без кодов (выписки других банков) целиком считается текстом `REMI`.
+#[derive(Debug, Default)]
+struct Narrative<'a> {
+    tx_type: Option<&'a str>,
+    status: Option<&'a str>,
+    time: Option<&'a str>,
+    counterparty: Option<&'a str>,
+    reference: Option<&'a str>,
+    description: &'a str,
+}
+
+impl<'a> Narrative<'a> {
+    fn parse(text: &'a str) -> Self {
+        let mut narrative = Narrative::default();
+        let mut rest = text;
+        loop {
+            // REMI последний, его текст может содержать `/`
+            if let Some(description) = rest.strip_prefix("/REMI/") {
+                narrative.description = description;
+                return narrative;
+            }
+            let Some((code, tail)) = rest.strip_prefix('/').and_then(|rest| rest.split_once('/'))
+            else {
+                // Текст без кодов, например из выписки другого банка
+                if rest == text {
+                    narrative.description = text;
+                }
+                return narrative;
+            };
+            let end = tail.find('/').unwrap_or(tail.len());
+            let value = &tail[..end];
+            match code {
+                "TYPE" => narrative.tx_type = Some(value),
+                "STAT" => narrative.status = Some(value),
+                "TIME" => narrative.time = Some(value),
+                "CPTY" => narrative.counterparty = Some(value),
+                "EREF" => narrative.reference = Some(value),
+                _ => {}
+            }
+            rest = &tail[end..];
+        }
+    }
+}
+
+/// Писатель MT940, по сообщению на каждую выписку [`Statements`]
+pub struct Mt940RecordWriter<W: std::io::Write> {
+    writer: W,
+    amount_policy: AmountPolicy,
+    statements: Statements,
+}
+
+impl<W: std::io::Write> Mt940RecordWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            amount_policy: AmountPolicy::default(),
+            statements: Statements::default(),
+        }
+    }
+
+    pub fn with_amount_policy(mut self, amount_policy: AmountPolicy) -> Self {
+        self.amount_policy = amount_policy;
+        self
+    }
+
+    pub fn write_message(&mut self, message: &Message) -> Result<()> {
+        let message = self.amount_policy.apply(message, &DataFormat::MT940)?;
+        let currency = message
+            .currency
+            .ok_or_else(|| mapping_error(&message, "CURRENCY is required for balances"))?;
+        if format_amount(message.amount, currency).len() > MAX_AMOUNT_LEN {
+            return Err(mapping_error(
+                &message,
+                &format!("AMOUNT exceeds {} characters", MAX_AMOUNT_LEN),
+            ));
+        }
+        if message.from_user_id == 0 && message.to_user_id == 0 {
+            return Err(mapping_error(
+                &message,
+                "FROM_USER_ID and TO_USER_ID are 0, there is no account statement",
+            ));
+        }
+        // Длина :86: зависит от счета второй стороны, проверяются обе строки выписок
+        for credit in [false, true] {
+            let entry = StatementEntry {
+                message: message.clone().into_owned(),
+                credit,
+            };
+            narrative(&entry).map_err(|reason| mapping_error(&message, &reason))?;
+        }
+        self.statements.push(&message, currency);
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W> {
+        let statements = std::mem::take(&mut self.statements);
+        for (number, statement) in statements.into_statements().enumerate() {
+            self.write_statement(number + 1, &statement)?;
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_statement(&mut self, number: usize, statement: &Statement) -> Result<()> {
+        let entries = &statement.entries;
+        let (Some(first), Some(last)) = (entries.first(), entries.last()) else {
+            return Ok(());
+        };
+        let currency = statement.currency;
+
+        // Начальный остаток неизвестен, конечный равен обороту за выписку
+        let mut balance = 0i64;
+        for entry in entries {
+            balance = balance
+                .checked_add(entry.signed_amount())
+                .filter(|balance| format_amount(*balance, currency).len() <= MAX_AMOUNT_LEN)
+                .ok_or_else(|| mapping_error(&entry.message, "closing balance overflow"))?;
+        }
+
+        let w = &mut self.writer;
+        write!(w, ":20:YPB{}\r\n", number)?;
+        write!(w, ":25:{}\r\n", statement.account)?;
+        write!(w, ":28C:{}/1\r\n", number)?;
+        write!(
+            w,
+            ":60F:C{}{}{}\r\n",
+            format_yymmdd(first.message.timestamp),
+            currency,
+            format_amount(0, currency)
+        )?;
+        for entry in entries {
+            let message = &entry.message;
+            let date = DateTime::from_timestamp(message.timestamp);
+            let reference = message.tx_id.to_string();
+            write!(
+                w,
+                ":61:{}{:02}{:02}{}{}N{}{}\r\n",
+                format_yymmdd(message.timestamp),
+                date.month,
+                date.day,
+                if entry.credit { 'C' } else { 'D' },
+                format_amount(message.amount, currency),
+                if message.tx_type == TypeTransaction::Transfer {
+                    "TRF"
+                } else {
+                    "MSC"
+                },
+                if reference.len() <= MAX_REFERENCE_LEN {
+                    &reference
+                } else {
+                    NONREF
+                }
+            )?;
+            let lines =
+                narrative(entry).map_err(|reason| mapping_error(&entry.message, &reason))?;
+            for (i, line) in lines.iter().enumerate() {
+                let tag = if i == 0 { ":86:" } else { "" };
+                write!(w, "{}{}\r\n", tag, line)?;
+            }
+        }
+        write!(
+            w,
+            ":62F:{}{}{}{}\r\n",
+            if balance < 0 { 'D' } else { 'C' },
+            format_yymmdd(last.message.timestamp),
+            currency,
+            format_amount(balance.abs(), currency)
+        )?;
+        write!(w, "-\r\n")?;
+        Ok(())
+    }
+}
+
+/// Строки `:86:` по [`MAX_LINE_LEN`] символов, не больше [`MAX_NARRATIVE_LINES`].
+/// Описание транслитерируется в набор символов SWIFT.
+fn narrative(entry: &StatementEntry) -> std::result::Result<Vec<String>, String> {
+    let message = &entry.message;
+    let date = DateTime::from_timestamp(message.timestamp);
+    let mut text = format!(
+        "/TYPE/{}/STAT/{}/TIME/{:02}{:02}{:02}{:03}",
+        message.tx_type, message.status, date.hour, date.minute, date.second, date.millis
+    );
+    if entry.counterparty() != 0 {
+        text.push_str(&format!("/CPTY/{}", entry.counterparty()));
+    }
+    if message.tx_id.to_string().len() > MAX_REFERENCE_LEN {
+        text.push_str(&format!("/EREF/{}", message.tx_id));
+    }
+    if !message.description.is_empty() {
+        text.push_str("/REMI/");
+        text.push_str(&transliterate(&message.description));
+    }
+
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut lines = chars
+        .chunks(MAX_LINE_LEN)
+        .map(|line| line.iter().collect::<String>())
+        .collect::<Vec<_>>();
+    // Строка `-` завершает выписку, поэтому последний символ предыдущей строки переносится
+    if let [.., previous, last] = lines.as_mut_slice()
+        && last == "-"
+        && let Some(c) = previous.pop()
+    {
+        last.insert(0, c);
+    }
+    if lines.len() > MAX_NARRATIVE_LINES {
+        return Err(format!(
+            ":86: needs {} characters, at most {} lines of {} characters fit",
+            chars.len(),
+            MAX_NARRATIVE_LINES,
+            MAX_LINE_LEN
+        ));
+    }
+    Ok(lines)
+}
+
+/// Перевод текста в набор символов SWIFT X: латиница, цифры и `/-?().,'+` с пробелом.
+/// Кириллица транслитерируется, латинские буквы с диакритикой заменяются базовыми,
+/// переводы строк и табуляция - пробелами, двоеточие и прочие символы - точкой,
+/// чтобы строка продолжения не читалась как тег поля.
+fn transliterate(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() || "/-?().,'+ ".contains(c) {
+            result.push(c);
+            continue;
+        }
+        let lower = c.to_lowercase().next().unwrap_or(c);
+        let latin = match lower {
+            'а' => "a",
+            'б' => "b",
+            'в' => "v",
+            'г' => "g",
+            'д' => "d",
+            'е' | 'ё' | 'э' => "e",
+            'ж' => "zh",
+            'з' => "z",
+            'и' | 'й' => "i",
+            'к' => "k",
+            'л' => "l",
+            'м' => "m",
+            'н' => "n",
+            'о' => "o",
+            'п' => "p",
+            'р' => "r",
+            'с' => "s",
+            'т' => "t",
+            'у' => "u",
+            'ф' => "f",
+            'х' => "kh",
+            'ц' => "ts",
+            'ч' => "ch",
+            'ш' => "sh",
+            'щ' => "shch",
+            'ъ' => "ie",
+            'ы' => "y",
+            'ь' => "",
+            'ю' => "iu",
+            'я' => "ia",
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => "a",
+            'æ' => "ae",
+            'ç' => "c",
+            'è' | 'é' | 'ê' | 'ë' => "e",
+            'ì' | 'í' | 'î' | 'ï' => "i",
+            'ñ' => "n",
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' => "o",
+            'œ' => "oe",
+            'ß' => "ss",
+            'ù' | 'ú' | 'û' | 'ü' => "u",
+            'ý' | 'ÿ' => "y",
+            '\n' | '\r' | '\t' => " ",
+            '"' => "'",
+            _ => ".",
+        };
+        // Заглавная буква сохраняется в первой букве транслитерации
+        if lower != c {
+            let mut chars = latin.chars();
+            if let Some(first) = chars.next() {
+                result.push(first.to_ascii_uppercase());
+                result.push_str(chars.as_str());
+            }
+        } else {
+            result.push_str(latin);
+        }
+    }
+    result
+}
+
+/// Сумма с запятой, например `123,45` или `100,` для валюты без дробной части
+fn format_amount(amount: i64, currency: Currency) -> String {
+    let decimal = Money::new(amount, currency).to_decimal().replace('.', ",");
+    if decimal.contains(',') {
+        decimal
+    } else {
+        decimal + ","
+    }
+}
+
+/// Время `HHMMSSmmm` в миллисекундах от начала суток
+fn parse_time(value: &str) -> Option<u64> {
+    if value.len() != 9 || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let date_time = DateTime {
+        hour: value[..2].parse().ok()?,
+        minute: value[2..4].parse().ok()?,
+        second: value[4..6].parse().ok()?,
+        millis: value[6..].parse().ok()?,
+        ..DateTime::from_timestamp(0)
+    };
+    date_time.timestamp()
+}
+
+/// Тег и значение строки `:TAG:value`
+fn split_tag(line: &str) -> Option<(&str, &str)> {
+    let (tag, value) = line.strip_prefix(':')?.split_once(':')?;
+    let valid = (2..=3).contains(&tag.len())
+        && tag.as_bytes()[..2].iter().all(u8::is_ascii_digit)
+        && tag.bytes().skip(2).all(|b| b.is_ascii_uppercase());
+    valid.then_some((tag, value))
+}
+
+fn mapping_error(message: &Message, reason: &str) -> AppError {
+    AppError::MappingError {
+        format: DataFormat::MT940,
+        tx_id: message.tx_id,
+        reason: reason.to_string(),
+    }
+}
+
+impl Default for Mt940YPBankRecord {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BankRecord for Mt940YPBankRecord {
+    fn write_to<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        let mut writer = Mt940RecordWriter::new(writer);
+        for message in &self.data {
+            writer.write_message(message)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+
+    fn push(&mut self, value: Message) {
+        self.data.push(value);
+    }
+
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn pop(&mut self) -> Option<Message> {
+        self.data.pop()
+    }
+
+    fn iter(&self) -> std::slice::Iter<'_, Message> {
+        self.data.iter()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convertor::test_records::{self, transaction};
+
+    fn write(messages: &[Message]) -> String {
+        test_records::write_text(messages, &DataFormat::MT940)
+    }
+
+    fn read(text: &str) -> Vec<Result<Message>> {
+        test_records::read(text, &DataFormat::MT940, "statement.sta")
+    }
+
+    #[test]
+    fn test_write() {
+        let mut withdrawal = transaction(2, TypeTransaction::Withdrawal, 11, 0);
+        withdrawal.timestamp += 86_400_000;
+        withdrawal.amount = 100;
+        let text = write(&[
+            withdrawal,
+            transaction(1, TypeTransaction::Transfer, 11, 22),
+        ]);
+        assert_eq!(
+            text,
+            concat!(
+                ":20:YPB1\r\n",
+                ":25:11\r\n",
+                ":28C:1/1\r\n",
+                ":60F:C210930EUR0,00\r\n",
+                ":61:2109300930D123,45NTRF1\r\n",
+                ":86:/TYPE/TRANSFER/STAT/SUCCESS/TIME/212000123/CPTY/22/REMI/Record 1\r\n",
+                ":61:2110011001D1,00NMSC2\r\n",
+                ":86:/TYPE/WITHDRAWAL/STAT/SUCCESS/TIME/212000123/REMI/Record 2\r\n",
+                ":62F:D211001EUR124,45\r\n",
+                "-\r\n",
+                ":20:YPB2\r\n",
+                ":25:22\r\n",
+                ":28C:2/1\r\n",
+                ":60F:C210930EUR0,00\r\n",
+                ":61:2109300930C123,45NTRF1\r\n",
+                ":86:/TYPE/TRANSFER/STAT/SUCCESS/TIME/212000123/CPTY/11/REMI/Record 1\r\n",
+                ":62F:C210930EUR123,45\r\n",
+                "-\r\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut messages = vec![
+            transaction(1, TypeTransaction::Deposit, 0, 22),
+            transaction(2, TypeTransaction::Transfer, 11, 22),
+            transaction(u64::MAX, TypeTransaction::Withdrawal, 11, 0),
+            transaction(4, TypeTransaction::Transfer, 33, 33),
+        ];
+        messages[1].status = StatusTransaction::Pending;
+        messages[1].description = "a/REMI/b (c) 'd' + e?".to_string();
+        messages[2].status = StatusTransaction::Failure;
+        messages[2].currency = Some(Currency::from_code("JPY").unwrap());
+        messages[3].description = String::new();
+
+        let text = write(&messages);
+        assert!(text.contains(":61:2109300930D12345,NMSCNONREF\r\n"));
+        let mut actual = Mt940YPBankRecord::from_read(text.as_bytes())
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        actual.sort_by_key(|message| message.tx_id);
+        messages.sort_by_key(|message| message.tx_id);
+        assert_eq!(actual, messages);
+    }
+
+    #[test]
+    fn test_transliterate() {
+        assert_eq!(
+            transliterate("Оплата: счёт №5 «Café»\nЩука ЮЛЯ"),
+            "Oplata. schet .5 .Cafe. Shchuka IuLIa"
+        );
+        assert_eq!(transliterate("a/b-c?(d).e,f'g+h"), "a/b-c?(d).e,f'g+h");
+    }
+
+    #[test]
+    fn test_narrative_limits() {
+        let mut message = transaction(1, TypeTransaction::Deposit, 0, 22);
+        let prefix = narrative(&StatementEntry {
+            message: message.clone(),
+            credit: true,
+        })
+        .unwrap()
+        .concat()
+        .len()
+            - message.description.len();
+
+        message.description = "x".repeat(MAX_LINE_LEN * MAX_NARRATIVE_LINES - prefix);
+        let lines = narrative(&StatementEntry {
+            message: message.clone(),
+            credit: true,
+        })
+        .unwrap();
+        assert_eq!(lines.len(), MAX_NARRATIVE_LINES);
+        assert!(lines.iter().all(|line| line.len() == MAX_LINE_LEN));
+
+        message.description.push('x');
+        let mut writer = Mt940RecordWriter::new(Vec::new());
+        assert_eq!(
+            writer.write_message(&message).unwrap_err().to_string(),
+            "MT940 mapping error: TX_ID 1: :86: needs 399 characters, at most 6 lines of 65 characters fit"
+        );
+    }
+
+    #[test]
+    fn test_narrative_dash_line() {
+        // Строка продолжения из одного `-` прочиталась бы как конец выписки
+        let mut first = transaction(1, TypeTransaction::Deposit, 0, 22);
+        let prefix = "/TYPE/DEPOSIT/STAT/SUCCESS/TIME/212000123/REMI/".len();
+        first.description = format!("{}-", "x".repeat(MAX_LINE_LEN - prefix));
+        let mut second = transaction(2, TypeTransaction::Deposit, 0, 22);
+        second.timestamp += 60_000;
+
+        let text = write(&[first.clone(), second.clone()]);
+        assert!(text.contains("x\r\nx-\r\n"));
+        let data = read(&text).into_iter().collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(data, vec![first, second]);
+    }
+
+    #[test]
+    fn test_write_errors() {
+        let mut writer = Mt940RecordWriter::new(Vec::new());
+
+        let mut no_currency = transaction(1, TypeTransaction::Deposit, 0, 22);
+        no_currency.currency = None;
+        let mut no_account = transaction(2, TypeTransaction::Deposit, 0, 0);
+        no_account.description = String::new();
+        let mut large = transaction(3, TypeTransaction::Deposit, 0, 22);
+        large.amount = i64::MAX;
+        let mut negative = transaction(4, TypeTransaction::Deposit, 0, 22);
+        negative.amount = -1;
+
+        for message in [&no_currency, &no_account, &large] {
+            assert!(matches!(
+                writer.write_message(message),
+                Err(AppError::MappingError { tx_id, format: DataFormat::MT940, .. }) if tx_id == message.tx_id
+            ));
+        }
+        assert!(matches!(
+            writer.write_message(&negative),
+            Err(AppError::NegativeAmountError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_read_other_bank() {
+        let text = concat!(
+            "{1:F01BANKDEFFXXXX0000000000}{2:O9400000000000BANKDEFFXXXX00000000000000000000N}{4:\r\n",
+            ":20:STMT\r\n",
+            ":25:42\r\n",
+            ":28C:7/1\r\n",
+            ":60F:C211001CHF100,00\r\n",
+            ":61:2110011002DF10,5NCHK777//BANKREF\r\n",
+            "SUPPLEMENTARY\r\n",
+            ":86:Rent October\r\n",
+            " flat 3\r\n",
+            ":62F:C211001CHF89,50\r\n",
+            ":86:statement information\r\n",
+            "-}\r\n",
+        );
+        let results = read(text);
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[0].as_ref().unwrap(),
+            &Message {
+                tx_id: 777,
+                tx_type: TypeTransaction::Withdrawal,
+                from_user_id: 42,
+                to_user_id: 0,
+                amount: 1050,
+                timestamp: 1633046400000,
+                status: StatusTransaction::Success,
+                description: "Rent October flat 3".to_string(),
+                currency: Some(Currency::from_code("CHF").unwrap()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let text = concat!(
+            ":20:STMT\n",
+            ":25:DE89370400440532013000\n",
+            ":60F:C211001EUR0,00\n",
+            ":61:211001C1,00NMSC1\n",
+            "-\n",
+            ":25:42\n",
+            ":60F:C211001EUR0,00\n",
+            ":61:211001RC1,00NMSC2\n",
+            ":61:211001C1,001NMSC3\n",
+            ":61:211001C1,00NMSCREF\n",
+            ":61:211001C1,00NMSC5\n",
+            ":86:/TYPE/REFUND\n",
+            ":61:211001C1,00NMSC6\n",
+            "-\n",
+        );
+        let results = read(text);
+        assert_eq!(results.len(), 6);
+        let errors = results[..5]
+            .iter()
+            .map(|result| result.as_ref().unwrap_err().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            [
+                "statement.sta:4: field 25: account \"DE89370400440532013000\" is not a YPBank user id",
+                "statement.sta:8: field 61: reversal entries are not supported",
+                "statement.sta:9: field 61: invalid amount \"1,001\", EUR allows 2 decimals",
+                "statement.sta:10: field 61: invalid reference \"REF\"",
+                "statement.sta:11: field 86: invalid TYPE \"REFUND\"",
+            ]
+        );
+        assert!(
+            results[..5]
+                .iter()
+                .all(|r| r.as_ref().unwrap_err().is_record_error())
+        );
+        assert_eq!(results[5].as_ref().unwrap().tx_id, 6);
+    }
+}
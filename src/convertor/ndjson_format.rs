@@ -0,0 +1,210 @@
+use std::io::BufRead;
+
+use crate::{
+    convertor::{BankRecord, Message, json_format::json_parse_error},
+    error::{AppError, Location, ParseError, Result},
+};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct NdjsonYPBankRecord {
+    data: Vec<Message>,
+}
+
+impl NdjsonYPBankRecord {
+    pub fn from_read<R: std::io::Read>(r: R) -> Result<Self> {
+        let data = NdjsonRecordReader::new(r).collect::<Result<Vec<_>>>()?;
+        Ok(Self { data })
+    }
+
+    pub fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+}
+
+/// Потоковый читатель NDJSON, по одному объекту в строке
+pub struct NdjsonRecordReader<R: std::io::Read> {
+    lines: std::io::Lines<std::io::BufReader<R>>,
+    finished: bool,
+    source: Option<String>,
+    // Номер последней прочитанной строки
+    line: u64,
+    // Количество прочитанных записей
+    record: u64,
+}
+
+impl<R: std::io::Read> NdjsonRecordReader<R> {
+    pub fn new(r: R) -> Self {
+        Self {
+            lines: std::io::BufReader::new(r).lines(),
+            finished: false,
+            source: None,
+            line: 0,
+            record: 0,
+        }
+    }
+
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    fn location(&self) -> Location {
+        Location {
+            source: self.source.clone(),
+            line: Some(self.line),
+            record: Some(self.record),
+            ..Location::default()
+        }
+    }
+}
+
+impl<R: std::io::Read> Iterator for NdjsonRecordReader<R> {
+    type Item = Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        loop {
+            match self.lines.next()? {
+                Ok(line) => {
+                    self.line += 1;
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    self.record += 1;
+                    // Каждая строка независима, поэтому любая ошибка относится к одной записи
+                    return Some(
+                        serde_json::from_str(&line)
+                            .map_err(|e| json_parse_error(self.location(), &e)),
+                    );
+                }
+                Err(e) => {
+                    self.finished = true;
+                    if e.kind() == std::io::ErrorKind::InvalidData {
+                        self.line += 1;
+                        self.record += 1;
+                        return Some(Err(AppError::JsonParseError(Box::new(ParseError {
+                            location: self.location(),
+                            message: "invalid UTF-8".to_string(),
+                        }))));
+                    }
+                    return Some(Err(AppError::IOError(e)));
+                }
+            }
+        }
+    }
+}
+
+/// Потоковый писатель NDJSON, принимает записи по одной
+pub struct NdjsonRecordWriter<W: std::io::Write> {
+    writer: W,
+}
+
+impl<W: std::io::Write> NdjsonRecordWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn write_message(&mut self, message: &Message) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, message)?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl Default for NdjsonYPBankRecord {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BankRecord for NdjsonYPBankRecord {
+    fn write_to<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        let mut writer = NdjsonRecordWriter::new(writer);
+        for message in &self.data {
+            writer.write_message(message)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+
+    fn push(&mut self, value: Message) {
+        self.data.push(value);
+    }
+
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn pop(&mut self) -> Option<Message> {
+        self.data.pop()
+    }
+
+    fn iter(&self) -> std::slice::Iter<'_, Message> {
+        self.data.iter()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+    use crate::convertor::JsonYPBankRecord;
+
+    static TEST_JSON: &str = "tests/data/examples_file/records_example.json";
+    static TEST_NDJSON: &str = "tests/data/examples_file/records_example.ndjson";
+
+    #[test]
+    fn test_read() {
+        let data = NdjsonYPBankRecord::from_read(File::open(TEST_NDJSON).unwrap()).unwrap();
+        let json = JsonYPBankRecord::from_read(File::open(TEST_JSON).unwrap()).unwrap();
+        assert!(data.iter().eq(json.iter()));
+    }
+
+    #[test]
+    fn test_write() {
+        let data = NdjsonYPBankRecord::from_read(File::open(TEST_NDJSON).unwrap()).unwrap();
+        let mut buf = Vec::new();
+        data.write_to(&mut buf).unwrap();
+        assert_eq!(buf, std::fs::read(TEST_NDJSON).unwrap());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let input = concat!(
+            r#"{"TX_ID":1,"TX_TYPE":"DEPOSIT","FROM_USER_ID":0,"TO_USER_ID":2,"AMOUNT":3,"TIMESTAMP":4,"STATUS":"SUCCESS","DESCRIPTION":"a"}"#,
+            "\n\n",
+            r#"{"TX_ID":2,"TX_TYPE":"DEPOSIT""#,
+            "\n",
+            r#"{"TX_ID":3,"TX_TYPE":"DEPOSIT","FROM_USER_ID":0,"TO_USER_ID":2,"AMOUNT":3,"TIMESTAMP":4,"STATUS":"SUCCESS"}"#,
+            "\n",
+            r#"{"TX_ID":4,"TX_TYPE":"DEPOSIT","FROM_USER_ID":0,"TO_USER_ID":2,"AMOUNT":3,"TIMESTAMP":4,"STATUS":"SUCCESS","DESCRIPTION":"d"}"#,
+        );
+        let results = NdjsonRecordReader::new(input.as_bytes())
+            .with_source("records.ndjson")
+            .collect::<Vec<_>>();
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].as_ref().unwrap().tx_id, 1);
+        assert_eq!(
+            results[1].as_ref().unwrap_err().to_string(),
+            "records.ndjson:3: EOF while parsing an object"
+        );
+        assert_eq!(
+            results[2].as_ref().unwrap_err().to_string(),
+            "records.ndjson:4: missing field `DESCRIPTION`"
+        );
+        assert_eq!(results[3].as_ref().unwrap().tx_id, 4);
+    }
+}
@@ -0,0 +1,912 @@
+use quick_xml::events::Event;
+
+use crate::{
+    DataFormat,
+    convertor::{
+        AmountPolicy, BankRecord, Currency, Message, Money, StatusTransaction, TypeTransaction,
+        datetime::DateTime,
+        statement::{self, Statement, StatementEntry, Statements, Transfers},
+        xml::{self, XmlReader, xml_reader},
+    },
+    error::{AppError, Location, ParseError, Result},
+};
+
+// Идентификатор банка в BANKID
+const BANK_ID: &str = "YPBANK";
+
+// Максимальная длина NAME по спецификации OFX
+const MAX_NAME_LEN: usize = 32;
+
+// Максимальная длина MEMO по спецификации OFX
+const MAX_MEMO_LEN: usize = 255;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct OfxYPBankRecord {
+    data: Vec<Message>,
+}
+
+impl OfxYPBankRecord {
+    pub fn from_read<R: std::io::Read>(r: R) -> Result<Self> {
+        let data = OfxRecordReader::new(r).collect::<Result<Vec<_>>>()?;
+        Ok(Self { data })
+    }
+
+    pub fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+}
+
+/// Счет и валюта текущего элемента `STMTRS`
+#[derive(Debug, Default)]
+struct StatementInfo {
+    account: Option<String>,
+    currency: Option<String>,
+}
+
+/// Поля элемента `STMTTRN` в виде текста, разбираются после закрывающего тега
+#[derive(Debug, Default)]
+struct Entry {
+    byte: u64,
+    tx_type: Option<String>,
+    posted: Option<String>,
+    amount: Option<String>,
+    tx_id: Option<String>,
+    name: Option<String>,
+    memo: Option<String>,
+    counterparty: Option<String>,
+    currency: Option<String>,
+}
+
+impl Entry {
+    /// Текст элемента по пути относительно `STMTTRN`
+    fn push_text(&mut self, path: &[String], text: &str) {
+        let path = path.iter().map(String::as_str).collect::<Vec<_>>();
+        let field = match path.as_slice() {
+            ["TRNTYPE"] => &mut self.tx_type,
+            ["DTPOSTED"] => &mut self.posted,
+            ["TRNAMT"] => &mut self.amount,
+            ["FITID"] => &mut self.tx_id,
+            ["NAME"] => &mut self.name,
+            ["MEMO"] => &mut self.memo,
+            ["BANKACCTTO" | "CCACCTTO", "ACCTID"] => &mut self.counterparty,
+            ["CURRENCY", "CURSYM"] => &mut self.currency,
+            _ => return,
+        };
+        field.get_or_insert_with(String::new).push_str(text);
+    }
+
+    fn into_message(
+        self,
+        statement: &StatementInfo,
+        location: &Location,
+    ) -> std::result::Result<Message, ParseError> {
+        let error = |field: &str, message: String| ParseError {
+            location: Location {
+                field: Some(field.to_string()),
+                ..location.clone()
+            },
+            message,
+        };
+        let required = |value: Option<String>, field: &str| {
+            value
+                .map(|value| value.trim().to_string())
+                .ok_or_else(|| error(field, "missing element".to_string()))
+        };
+        let user_id = |value: &str, field: &str| {
+            value.trim().parse::<u64>().map_err(|_| {
+                error(
+                    field,
+                    format!("account id {:?} is not a YPBank user id", value.trim()),
+                )
+            })
+        };
+
+        let tx_id = required(self.tx_id, "FITID")?;
+        let tx_id = tx_id
+            .parse::<u64>()
+            .map_err(|_| error("FITID", format!("invalid integer {:?}", tx_id)))?;
+
+        let account = statement
+            .account
+            .as_deref()
+            .ok_or_else(|| error("ACCTID", "missing statement account".to_string()))?;
+        let account = user_id(account, "ACCTID")?;
+        let counterparty = match &self.counterparty {
+            Some(counterparty) => user_id(counterparty, "BANKACCTTO")?,
+            None => 0,
+        };
+
+        // Валюта операции задает CURRENCY, по умолчанию - валюта выписки CURDEF
+        let (code, field) = match (self.currency, &statement.currency) {
+            (Some(code), _) => (code, "CURSYM"),
+            (None, Some(code)) => (code.clone(), "CURDEF"),
+            (None, None) => return Err(error("CURDEF", "missing element".to_string())),
+        };
+        let currency = Currency::from_code(code.trim())
+            .map_err(|_| error(field, format!("unknown currency {:?}", code.trim())))?;
+
+        // Направление задает знак суммы, в том числе у -0.00
+        let amount = required(self.amount, "TRNAMT")?;
+        let (credit, digits) = match amount.strip_prefix('-') {
+            Some(digits) => (false, digits),
+            None => (true, amount.strip_prefix('+').unwrap_or(&amount)),
+        };
+        let money = Money::from_decimal(digits, currency)
+            .ok()
+            .filter(|money| money.minor_units >= 0)
+            .ok_or_else(|| {
+                error(
+                    "TRNAMT",
+                    format!(
+                        "invalid amount {:?}, {} allows {} decimals",
+                        amount,
+                        currency,
+                        currency.exponent()
+                    ),
+                )
+            })?;
+
+        let posted = required(self.posted, "DTPOSTED")?;
+        let timestamp = parse_datetime(&posted)
+            .ok_or_else(|| error("DTPOSTED", format!("invalid date {:?}", posted)))?;
+
+        // Типы других банков (POS, ATM, FEE и т.п.) определяются направлением
+        let tx_type = match self.tx_type.as_deref().map(str::trim) {
+            Some("XFER") => TypeTransaction::Transfer,
+            Some("CREDIT") => TypeTransaction::Deposit,
+            Some("DEBIT") => TypeTransaction::Withdrawal,
+            Some(_) if credit => TypeTransaction::Deposit,
+            Some(_) => TypeTransaction::Withdrawal,
+            None => return Err(error("TRNTYPE", "missing element".to_string())),
+        };
+
+        let (from_user_id, to_user_id) = statement::accounts(account, counterparty, credit);
+        Ok(Message {
+            tx_id,
+            tx_type,
+            from_user_id,
+            to_user_id,
+            amount: money.minor_units,
+            timestamp,
+            status: StatusTransaction::Success,
+            description: self.memo.or(self.name).unwrap_or_default(),
+            currency: Some(currency),
+        })
+    }
+}
+
+/// Потоковый читатель OFX 2.x, возвращает по записи на каждый элемент `STMTTRN`
+pub struct OfxRecordReader<R: std::io::Read> {
+    reader: XmlReader<R>,
+    // Имена открытых элементов
+    path: Vec<String>,
+    statement: StatementInfo,
+    // Поля текущего элемента STMTTRN
+    entry: Option<Box<Entry>>,
+    transfers: Transfers,
+    finished: bool,
+    source: Option<String>,
+    // Количество прочитанных записей
+    record: u64,
+}
+
+impl<R: std::io::Read> OfxRecordReader<R> {
+    pub fn new(r: R) -> Self {
+        Self {
+            reader: xml_reader(r),
+            path: Vec::new(),
+            statement: StatementInfo::default(),
+            entry: None,
+            transfers: Transfers::default(),
+            finished: false,
+            source: None,
+            record: 0,
+        }
+    }
+
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    fn location(&self, byte: u64) -> Location {
+        Location {
+            source: self.source.clone(),
+            record: self.entry.as_ref().map(|_| self.record),
+            byte: Some(byte),
+            ..Location::default()
+        }
+    }
+
+    fn xml_error(&self, message: impl ToString) -> AppError {
+        AppError::XmlError(Box::new(ParseError {
+            location: self.location(self.reader.error_position()),
+            message: message.to_string(),
+        }))
+    }
+
+    fn read_message(&mut self) -> Result<Option<Message>> {
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            let position = self.reader.buffer_position();
+            let event = self
+                .reader
+                .read_event_into(&mut buf)
+                .map_err(|e| self.xml_error(e))?;
+            match event {
+                Event::Start(element) => {
+                    let name = xml::local_name(&element);
+                    if self.path.is_empty() && name != "OFX" {
+                        return Err(self.xml_error(format!("expected <OFX>, found <{}>", name)));
+                    }
+                    match name.as_str() {
+                        "STMTRS" | "CCSTMTRS" => self.statement = StatementInfo::default(),
+                        "STMTTRN" if self.entry.is_none() => {
+                            self.record += 1;
+                            self.entry = Some(Box::new(Entry {
+                                byte: position,
+                                ..Entry::default()
+                            }));
+                        }
+                        _ => {}
+                    }
+                    self.path.push(name);
+                }
+                Event::End(_) => {
+                    if self.path.pop().as_deref() != Some("STMTTRN") {
+                        continue;
+                    }
+                    let location = self.location(self.entry.as_ref().map_or(0, |e| e.byte));
+                    let Some(entry) = self.entry.take() else {
+                        continue;
+                    };
+                    let message = entry
+                        .into_message(&self.statement, &location)
+                        .map_err(|e| AppError::OfxParseError(Box::new(e)))?;
+                    if !self.transfers.is_repeated(&message) {
+                        return Ok(Some(message));
+                    }
+                }
+                Event::Text(text) => {
+                    let text = text.unescape().map_err(|e| self.xml_error(e))?;
+                    self.push_text(&text);
+                }
+                Event::CData(text) => {
+                    let text = String::from_utf8(text.into_inner().into_owned())
+                        .map_err(|e| self.xml_error(e))?;
+                    self.push_text(&text);
+                }
+                Event::Eof => {
+                    if let Some(name) = self.path.last() {
+                        return Err(self.xml_error(format!(
+                            "unexpected end of file, <{}> is not closed",
+                            name
+                        )));
+                    }
+                    return Ok(None);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn push_text(&mut self, text: &str) {
+        if let Some(entry) = &mut self.entry {
+            if let Some(start) = self.path.iter().rposition(|name| name == "STMTTRN") {
+                entry.push_text(&self.path[start + 1..], text);
+            }
+            return;
+        }
+        let Some(start) = self
+            .path
+            .iter()
+            .rposition(|name| name == "STMTRS" || name == "CCSTMTRS")
+        else {
+            return;
+        };
+        let path = self.path[start + 1..]
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let field = match path.as_slice() {
+            ["CURDEF"] => &mut self.statement.currency,
+            ["BANKACCTFROM" | "CCACCTFROM", "ACCTID"] => &mut self.statement.account,
+            _ => return,
+        };
+        field.get_or_insert_with(String::new).push_str(text);
+    }
+}
+
+impl<R: std::io::Read> Iterator for OfxRecordReader<R> {
+    type Item = Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        match self.read_message() {
+            Ok(Some(message)) => Some(Ok(message)),
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(e) => {
+                if !e.is_record_error() {
+                    self.finished = true;
+                }
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Писатель OFX 2.x, по `STMTTRNRS` на каждую выписку [`Statements`]
+pub struct OfxRecordWriter<W: std::io::Write> {
+    writer: W,
+    amount_policy: AmountPolicy,
+    statements: Statements,
+    created: Option<u64>,
+}
+
+impl<W: std::io::Write> OfxRecordWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            amount_policy: AmountPolicy::default(),
+            statements: Statements::default(),
+            created: None,
+        }
+    }
+
+    pub fn with_amount_policy(mut self, amount_policy: AmountPolicy) -> Self {
+        self.amount_policy = amount_policy;
+        self
+    }
+
+    /// Время создания документа для `DTSERVER`, по умолчанию время последней записи
+    pub fn with_created(mut self, created: u64) -> Self {
+        self.created = Some(created);
+        self
+    }
+
+    pub fn write_message(&mut self, message: &Message) -> Result<()> {
+        let message = self.amount_policy.apply(message, &DataFormat::OFX)?;
+        let currency = message
+            .currency
+            .ok_or_else(|| mapping_error(&message, "CURRENCY is required for CURDEF"))?;
+        if message.status != StatusTransaction::Success {
+            return Err(mapping_error(
+                &message,
+                &format!(
+                    "STATUS {}, OFX statements contain posted transactions only",
+                    message.status
+                ),
+            ));
+        }
+        if message.from_user_id == 0 && message.to_user_id == 0 {
+            return Err(mapping_error(
+                &message,
+                "FROM_USER_ID and TO_USER_ID are 0, there is no account statement",
+            ));
+        }
+        if message.description.chars().count() > MAX_MEMO_LEN {
+            return Err(mapping_error(
+                &message,
+                &format!("DESCRIPTION exceeds {} characters of MEMO", MAX_MEMO_LEN),
+            ));
+        }
+        if let Err(c) = xml::escape_text(&message.description) {
+            return Err(mapping_error(
+                &message,
+                &format!("DESCRIPTION contains {:?}, not allowed in XML", c),
+            ));
+        }
+        self.statements.push(&message, currency);
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W> {
+        let created = self
+            .created
+            .unwrap_or_else(|| self.statements.latest_timestamp());
+        write!(
+            self.writer,
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n",
+                "<?OFX OFXHEADER=\"200\" VERSION=\"220\" SECURITY=\"NONE\" ",
+                "OLDFILEUID=\"NONE\" NEWFILEUID=\"NONE\"?>\n",
+                "<OFX>\n",
+                "  <SIGNONMSGSRSV1>\n",
+                "    <SONRS>\n",
+                "      <STATUS>\n",
+                "        <CODE>0</CODE>\n",
+                "        <SEVERITY>INFO</SEVERITY>\n",
+                "      </STATUS>\n",
+                "      <DTSERVER>{}</DTSERVER>\n",
+                "      <LANGUAGE>ENG</LANGUAGE>\n",
+                "    </SONRS>\n",
+                "  </SIGNONMSGSRSV1>\n",
+                "  <BANKMSGSRSV1>\n",
+            ),
+            format_datetime(created)
+        )?;
+        let statements = std::mem::take(&mut self.statements);
+        for (number, statement) in statements.into_statements().enumerate() {
+            self.write_statement(number + 1, &statement)?;
+        }
+        self.writer.write_all(b"  </BANKMSGSRSV1>\n</OFX>\n")?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_statement(&mut self, number: usize, statement: &Statement) -> Result<()> {
+        let entries = &statement.entries;
+        let (Some(first), Some(last)) = (entries.first(), entries.last()) else {
+            return Ok(());
+        };
+        let currency = statement.currency;
+
+        // Остатки счета неизвестны, LEDGERBAL равен обороту за выписку
+        let mut balance = 0i64;
+        for entry in entries {
+            balance = balance
+                .checked_add(entry.signed_amount())
+                .ok_or_else(|| mapping_error(&entry.message, "LEDGERBAL overflow"))?;
+        }
+
+        let w = &mut self.writer;
+        writeln!(w, "    <STMTTRNRS>")?;
+        writeln!(w, "      <TRNUID>{}</TRNUID>", number)?;
+        writeln!(w, "      <STATUS>")?;
+        writeln!(w, "        <CODE>0</CODE>")?;
+        writeln!(w, "        <SEVERITY>INFO</SEVERITY>")?;
+        writeln!(w, "      </STATUS>")?;
+        writeln!(w, "      <STMTRS>")?;
+        writeln!(w, "        <CURDEF>{}</CURDEF>", currency)?;
+        writeln!(w, "        <BANKACCTFROM>")?;
+        writeln!(w, "          <BANKID>{}</BANKID>", BANK_ID)?;
+        writeln!(w, "          <ACCTID>{}</ACCTID>", statement.account)?;
+        writeln!(w, "          <ACCTTYPE>CHECKING</ACCTTYPE>")?;
+        writeln!(w, "        </BANKACCTFROM>")?;
+        writeln!(w, "        <BANKTRANLIST>")?;
+        writeln!(
+            w,
+            "          <DTSTART>{}</DTSTART>",
+            format_datetime(first.message.timestamp)
+        )?;
+        writeln!(
+            w,
+            "          <DTEND>{}</DTEND>",
+            format_datetime(last.message.timestamp)
+        )?;
+        for entry in entries {
+            write_transaction(w, entry, currency)?;
+        }
+        writeln!(w, "        </BANKTRANLIST>")?;
+        writeln!(w, "        <LEDGERBAL>")?;
+        writeln!(
+            w,
+            "          <BALAMT>{}</BALAMT>",
+            Money::new(balance, currency).to_decimal()
+        )?;
+        writeln!(
+            w,
+            "          <DTASOF>{}</DTASOF>",
+            format_datetime(last.message.timestamp)
+        )?;
+        writeln!(w, "        </LEDGERBAL>")?;
+        writeln!(w, "      </STMTRS>")?;
+        writeln!(w, "    </STMTTRNRS>")?;
+        Ok(())
+    }
+}
+
+/// Элемент `STMTTRN` строки выписки
+fn write_transaction<W: std::io::Write>(
+    w: &mut W,
+    entry: &StatementEntry,
+    currency: Currency,
+) -> Result<()> {
+    let message = &entry.message;
+    let tx_type = match message.tx_type {
+        TypeTransaction::Deposit => "CREDIT",
+        TypeTransaction::Withdrawal => "DEBIT",
+        TypeTransaction::Transfer => "XFER",
+    };
+    // Знак задает направление для счета выписки, поэтому списание нуля пишется как -0.00
+    let sign = if entry.credit { "" } else { "-" };
+
+    writeln!(w, "          <STMTTRN>")?;
+    writeln!(w, "            <TRNTYPE>{}</TRNTYPE>", tx_type)?;
+    writeln!(
+        w,
+        "            <DTPOSTED>{}</DTPOSTED>",
+        format_datetime(message.timestamp)
+    )?;
+    writeln!(
+        w,
+        "            <TRNAMT>{}{}</TRNAMT>",
+        sign,
+        Money::new(message.amount, currency).to_decimal()
+    )?;
+    writeln!(w, "            <FITID>{}</FITID>", message.tx_id)?;
+    if !message.description.is_empty() {
+        // Описание проверено в write_message, поэтому экранирование не завершается ошибкой
+        let name = message
+            .description
+            .chars()
+            .take(MAX_NAME_LEN)
+            .collect::<String>();
+        let escape = |text: &str| xml::escape_text(text).unwrap_or_default();
+        writeln!(w, "            <NAME>{}</NAME>", escape(&name))?;
+        writeln!(
+            w,
+            "            <MEMO>{}</MEMO>",
+            escape(&message.description)
+        )?;
+    }
+    if entry.counterparty() != 0 {
+        writeln!(w, "            <BANKACCTTO>")?;
+        writeln!(w, "              <BANKID>{}</BANKID>", BANK_ID)?;
+        writeln!(w, "              <ACCTID>{}</ACCTID>", entry.counterparty())?;
+        writeln!(w, "              <ACCTTYPE>CHECKING</ACCTTYPE>")?;
+        writeln!(w, "            </BANKACCTTO>")?;
+    }
+    writeln!(w, "          </STMTTRN>")?;
+    Ok(())
+}
+
+/// Дата и время OFX `YYYYMMDDHHMMSS.XXX[0:GMT]`
+fn format_datetime(timestamp: u64) -> String {
+    let date = DateTime::from_timestamp(timestamp);
+    format!(
+        "{:04}{:02}{:02}{:02}{:02}{:02}.{:03}[0:GMT]",
+        date.year, date.month, date.day, date.hour, date.minute, date.second, date.millis
+    )
+}
+
+/// Разбор даты OFX `YYYYMMDD[HHMMSS[.XXX]][[±HH[.MM][:TZ]]]` в Unix time в миллисекундах.
+/// Время без зоны считается UTC.
+fn parse_datetime(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let (value, offset) = match value.split_once('[') {
+        Some((value, zone)) => {
+            let zone = zone.strip_suffix(']')?;
+            let offset = zone.split_once(':').map_or(zone, |(offset, _)| offset);
+            let (sign, offset) = match offset.strip_prefix('-') {
+                Some(offset) => (-1, offset),
+                None => (1, offset.strip_prefix('+').unwrap_or(offset)),
+            };
+            let (hours, minutes) = offset.split_once('.').unwrap_or((offset, "0"));
+            let digits =
+                |s: &str| !s.is_empty() && s.len() <= 2 && s.bytes().all(|b| b.is_ascii_digit());
+            if !digits(hours) || !digits(minutes) {
+                return None;
+            }
+            let minutes = hours.parse::<i64>().ok()? * 60 + minutes.parse::<i64>().ok()?;
+            (value, sign * minutes * 60_000)
+        }
+        None => (value, 0),
+    };
+
+    let (value, millis) = match value.split_once('.') {
+        Some((value, fraction)) => {
+            if fraction.is_empty() || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            let millis = format!("{:0<3}", &fraction[..fraction.len().min(3)]);
+            (value, millis.parse().ok()?)
+        }
+        None => (value, 0),
+    };
+    if !matches!(value.len(), 8 | 12 | 14) || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let number =
+        |range: std::ops::Range<usize>| value.get(range).map_or(Some(0), |s| s.parse().ok());
+    let date = DateTime::from_date(value[..4].parse().ok()?, number(4..6)?, number(6..8)?)?;
+    let date_time = DateTime {
+        hour: number(8..10)?,
+        minute: number(10..12)?,
+        second: number(12..14)?,
+        millis,
+        ..date
+    };
+    let timestamp = date_time.timestamp()? as i64 - offset;
+    u64::try_from(timestamp).ok()
+}
+
+fn mapping_error(message: &Message, reason: &str) -> AppError {
+    AppError::MappingError {
+        format: DataFormat::OFX,
+        tx_id: message.tx_id,
+        reason: reason.to_string(),
+    }
+}
+
+impl Default for OfxYPBankRecord {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BankRecord for OfxYPBankRecord {
+    fn write_to<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        let mut writer = OfxRecordWriter::new(writer);
+        for message in &self.data {
+            writer.write_message(message)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+
+    fn push(&mut self, value: Message) {
+        self.data.push(value);
+    }
+
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn pop(&mut self) -> Option<Message> {
+        self.data.pop()
+    }
+
+    fn iter(&self) -> std::slice::Iter<'_, Message> {
+        self.data.iter()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convertor::test_records::{self, transaction};
+
+    fn message(tx_id: u64, tx_type: TypeTransaction, from: u64, to: u64) -> Message {
+        let message = transaction(tx_id, tx_type, from, to);
+        Message {
+            timestamp: message.timestamp + tx_id,
+            ..message
+        }
+    }
+
+    fn write(messages: &[Message]) -> String {
+        test_records::write_text(messages, &DataFormat::OFX)
+    }
+
+    fn read(xml: &str) -> Vec<Result<Message>> {
+        test_records::read(xml, &DataFormat::OFX, "statement.ofx")
+    }
+
+    fn statement(transactions: &str) -> String {
+        format!(
+            "<OFX><BANKMSGSRSV1><STMTTRNRS><STMTRS><CURDEF>USD</CURDEF><BANKACCTFROM><ACCTID>11</ACCTID></BANKACCTFROM><BANKTRANLIST>{}</BANKTRANLIST></STMTRS></STMTTRNRS></BANKMSGSRSV1></OFX>",
+            transactions
+        )
+    }
+
+    #[test]
+    fn test_write() {
+        let mut transfer = message(1, TypeTransaction::Transfer, 11, 22);
+        transfer.description = "Перевод <за> аренду & коммунальные услуги".to_string();
+        let xml = write(&[transfer, message(2, TypeTransaction::Deposit, 0, 11)]);
+
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n<?OFX OFXHEADER=\"200\" VERSION=\"220\""));
+        assert!(xml.contains("<DTSERVER>20210930212000.125[0:GMT]</DTSERVER>"));
+        // Перевод попадает в выписки обоих счетов
+        assert_eq!(xml.matches("<STMTTRNRS>").count(), 2);
+        assert_eq!(xml.matches("<FITID>1</FITID>").count(), 2);
+        assert!(xml.contains("<TRNTYPE>XFER</TRNTYPE>"));
+        assert!(xml.contains("<TRNTYPE>CREDIT</TRNTYPE>"));
+        assert!(xml.contains("<DTPOSTED>20210930212000.124[0:GMT]</DTPOSTED>"));
+        assert!(xml.contains("<TRNAMT>-123.45</TRNAMT>"));
+        assert!(xml.contains("<NAME>Перевод &lt;за&gt; аренду &amp; коммунальн</NAME>"));
+        // Выписка счета 11: списание перевода и зачисление пополнения
+        assert!(xml.contains("<BALAMT>0.00</BALAMT>"));
+        assert!(xml.contains("<BALAMT>123.45</BALAMT>"));
+    }
+
+    #[test]
+    fn test_write_created() {
+        let messages = [message(1, TypeTransaction::Deposit, 0, 11)];
+        assert_eq!(write(&messages), write(&messages));
+
+        let mut writer = OfxRecordWriter::new(Vec::new()).with_created(1700000000000);
+        writer.write_message(&messages[0]).unwrap();
+        let xml = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert!(xml.contains("<DTSERVER>20231114221320.000[0:GMT]</DTSERVER>"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut messages = vec![
+            message(1, TypeTransaction::Transfer, 11, 22),
+            message(2, TypeTransaction::Deposit, 0, 11),
+            message(3, TypeTransaction::Withdrawal, 22, 0),
+            message(4, TypeTransaction::Withdrawal, 11, 0),
+        ];
+        messages[1].description = format!("\"q\"\r\n\t{}", "x".repeat(100));
+        messages[2].description = String::new();
+        messages[2].currency = Some(Currency::from_code("JPY").unwrap());
+        messages[3].amount = 0;
+
+        let xml = write(&messages);
+        assert!(xml.contains("<TRNAMT>-0.00</TRNAMT>"));
+        let mut data = OfxYPBankRecord::from_read(xml.as_bytes())
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        data.sort_by_key(|message| message.tx_id);
+        assert_eq!(data, messages);
+    }
+
+    #[test]
+    fn test_write_errors() {
+        let mut message = message(7, TypeTransaction::Deposit, 0, 11);
+        let mut writer = OfxRecordWriter::new(Vec::new());
+
+        message.status = StatusTransaction::Pending;
+        let err = writer.write_message(&message).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "OFX mapping error: TX_ID 7: STATUS PENDING, OFX statements contain posted transactions only"
+        );
+
+        message.status = StatusTransaction::Success;
+        message.currency = None;
+        assert!(matches!(
+            writer.write_message(&message),
+            Err(AppError::MappingError { tx_id: 7, .. })
+        ));
+
+        message.currency = Some(Currency::from_code("EUR").unwrap());
+        message.to_user_id = 0;
+        assert!(matches!(
+            writer.write_message(&message),
+            Err(AppError::MappingError { .. })
+        ));
+
+        message.to_user_id = 11;
+        message.description = "x".repeat(256);
+        assert!(matches!(
+            writer.write_message(&message),
+            Err(AppError::MappingError { .. })
+        ));
+
+        message.description = String::new();
+        message.amount = -100;
+        assert!(matches!(
+            writer.write_message(&message),
+            Err(AppError::NegativeAmountError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_read_other_bank() {
+        // Выписка по кредитной карте другого банка: CCSTMTRS, тип POS, зона EST
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<?OFX OFXHEADER="200" VERSION="211" SECURITY="NONE" OLDFILEUID="NONE" NEWFILEUID="NONE"?>
+<OFX>
+  <CREDITCARDMSGSRSV1><CCSTMTTRNRS><TRNUID>0</TRNUID>
+    <CCSTMTRS>
+      <CURDEF>USD</CURDEF>
+      <CCACCTFROM><ACCTID>4100</ACCTID></CCACCTFROM>
+      <BANKTRANLIST>
+        <STMTTRN>
+          <TRNTYPE>POS</TRNTYPE>
+          <DTPOSTED>20211001120000[-5:EST]</DTPOSTED>
+          <TRNAMT>-10.5</TRNAMT>
+          <FITID>42</FITID>
+          <NAME>Coffee &amp; cake</NAME>
+          <CURRENCY><CURRATE>1.1</CURRATE><CURSYM>EUR</CURSYM></CURRENCY>
+        </STMTTRN>
+      </BANKTRANLIST>
+    </CCSTMTRS>
+  </CCSTMTTRNRS></CREDITCARDMSGSRSV1>
+</OFX>"#;
+        let data = OfxYPBankRecord::from_read(xml.as_bytes()).unwrap();
+        assert_eq!(
+            data.iter().next(),
+            Some(&Message {
+                tx_id: 42,
+                tx_type: TypeTransaction::Withdrawal,
+                from_user_id: 4100,
+                to_user_id: 0,
+                amount: 1050,
+                timestamp: 1633107600000,
+                status: StatusTransaction::Success,
+                description: "Coffee & cake".to_string(),
+                currency: Some(Currency::from_code("EUR").unwrap()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let transaction = |body: &str| {
+            format!(
+                "<STMTTRN><TRNTYPE>CREDIT</TRNTYPE><DTPOSTED>20211001</DTPOSTED>{}</STMTTRN>",
+                body
+            )
+        };
+        let xml = statement(
+            &[
+                transaction("<TRNAMT>1.234</TRNAMT><FITID>1</FITID>"),
+                transaction("<TRNAMT>1</TRNAMT><FITID>ABC-1</FITID>"),
+                transaction("<TRNAMT>1</TRNAMT><FITID>3</FITID><BANKACCTTO><ACCTID>DE89 3704</ACCTID></BANKACCTTO>"),
+                transaction("<TRNAMT>1</TRNAMT>"),
+                transaction("<TRNAMT>1</TRNAMT><FITID>5</FITID>"),
+            ]
+            .concat(),
+        );
+        let results = read(&xml);
+        assert_eq!(results.len(), 5);
+        let messages = results[..4]
+            .iter()
+            .map(|r| r.as_ref().unwrap_err().to_string())
+            .collect::<Vec<_>>();
+        assert!(messages[0].starts_with("statement.ofx: record 1: byte "));
+        assert!(messages[0].ends_with(": TRNAMT: invalid amount \"1.234\", USD allows 2 decimals"));
+        assert!(messages[1].ends_with(": FITID: invalid integer \"ABC-1\""));
+        assert!(
+            messages[2].ends_with(": BANKACCTTO: account id \"DE89 3704\" is not a YPBank user id")
+        );
+        assert!(messages[3].ends_with(": FITID: missing element"));
+        assert!(
+            results[..4]
+                .iter()
+                .all(|r| r.as_ref().unwrap_err().is_record_error())
+        );
+        assert_eq!(results[4].as_ref().unwrap().to_user_id, 11);
+    }
+
+    #[test]
+    fn test_xml_errors() {
+        let results = read(&statement("<STMTTRN><FITID>1</STMTTRN>"));
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0], Err(AppError::XmlError(_))));
+
+        // OFX 1.x в формате SGML без закрывающих тегов не поддерживается
+        let results = read("OFXHEADER:100\nDATA:OFXSGML\n\n<OFX><SIGNONMSGSRSV1>");
+        assert!(
+            results[0]
+                .as_ref()
+                .unwrap_err()
+                .to_string()
+                .ends_with("unexpected end of file, <SIGNONMSGSRSV1> is not closed")
+        );
+
+        let results = read("<Document/>");
+        assert!(
+            results[0]
+                .as_ref()
+                .unwrap_err()
+                .to_string()
+                .ends_with("expected <OFX>, found <Document>")
+        );
+    }
+
+    #[test]
+    fn test_parse_datetime() {
+        assert_eq!(parse_datetime("20210930"), Some(1632960000000));
+        assert_eq!(
+            parse_datetime("20210930212000.123[0:GMT]"),
+            Some(1633036800123)
+        );
+        assert_eq!(parse_datetime("202109302120"), Some(1633036800000));
+        assert_eq!(
+            parse_datetime("20210930235000[+2.30:IST]"),
+            Some(1633036800000)
+        );
+        assert_eq!(parse_datetime("2021-09-30"), None);
+        assert_eq!(parse_datetime("20210931"), None);
+        assert_eq!(parse_datetime("20210930[x]"), None);
+    }
+}
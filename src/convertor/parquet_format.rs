@@ -0,0 +1,347 @@
+use parquet::{
+    arrow::{
+        ArrowWriter,
+        arrow_reader::{ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder},
+    },
+    basic::Compression,
+    file::properties::WriterProperties,
+};
+
+use crate::{
+    DataFormat,
+    convertor::{
+        BankRecord, Message,
+        arrow_format::{BATCH_SIZE, Batch, Rows, record_batch_schema},
+    },
+    error::{AppError, Result},
+};
+
+/// Размер групп строк Parquet. Группа - единица чтения и пропуска данных
+/// в аналитических системах, поэтому слишком мелкие группы замедляют запросы.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RowGroupSize {
+    /// Максимальное количество строк в группе
+    pub rows: usize,
+    /// Размер данных группы в памяти писателя в байтах, после которого группа
+    /// записывается раньше, чем наберется `rows` строк
+    pub bytes: Option<usize>,
+}
+
+impl Default for RowGroupSize {
+    fn default() -> Self {
+        Self {
+            rows: 1024 * 1024,
+            bytes: None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParquetYPBankRecord {
+    data: Vec<Message>,
+}
+
+impl ParquetYPBankRecord {
+    pub fn from_read<R: std::io::Read>(r: R) -> Result<Self> {
+        let data = ParquetRecordReader::new(r).collect::<Result<Vec<_>>>()?;
+        Ok(Self { data })
+    }
+
+    pub fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+}
+
+/// Читатель Parquet. Метаданные файла находятся в его конце, поэтому вход читается
+/// в память целиком, а записи разбираются по одному RecordBatch.
+pub struct ParquetRecordReader<R: std::io::Read> {
+    input: Option<R>,
+    finished: bool,
+    batches: Option<Box<ParquetRecordBatchReader>>,
+    rows: Rows,
+}
+
+impl<R: std::io::Read> ParquetRecordReader<R> {
+    pub fn new(r: R) -> Self {
+        Self {
+            input: Some(r),
+            finished: false,
+            batches: None,
+            rows: Rows::default(),
+        }
+    }
+
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.rows.source = Some(source.into());
+        self
+    }
+
+    fn open(input: &mut R) -> Result<ParquetRecordBatchReader> {
+        let mut data = Vec::new();
+        input.read_to_end(&mut data)?;
+        let batches = ParquetRecordBatchReaderBuilder::try_new(bytes::Bytes::from(data))?
+            .with_batch_size(BATCH_SIZE)
+            .build()?;
+        Ok(batches)
+    }
+
+    /// Переход к следующему RecordBatch, `false` в конце файла
+    fn next_batch(&mut self) -> Result<bool> {
+        if let Some(mut input) = self.input.take() {
+            self.batches = Some(Box::new(Self::open(&mut input)?));
+        }
+        match self.batches.as_mut().and_then(|batches| batches.next()) {
+            Some(batch) => {
+                self.rows.set(&batch?)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+impl<R: std::io::Read> Iterator for ParquetRecordReader<R> {
+    type Item = Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            if let Some(result) = self.rows.next(AppError::ParquetRecordError) {
+                return Some(result);
+            }
+            // Ошибка файла или схемы завершает чтение
+            match self.next_batch() {
+                Ok(true) => {}
+                Ok(false) => self.finished = true,
+                Err(e) => {
+                    self.finished = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Потоковый писатель Parquet. Записи накапливаются в RecordBatch, готовые группы строк
+/// сразу передаются в выходной поток, в памяти остается только незаписанная группа.
+pub struct ParquetRecordWriter<W: std::io::Write> {
+    output: W,
+    // Создается при первой записи, так как создание может завершиться ошибкой
+    writer: Option<Box<ArrowWriter<Vec<u8>>>>,
+    row_group_size: RowGroupSize,
+    batch: Box<Batch>,
+}
+
+impl<W: std::io::Write> ParquetRecordWriter<W> {
+    pub fn new(output: W) -> Self {
+        Self {
+            output,
+            writer: None,
+            row_group_size: RowGroupSize::default(),
+            batch: Box::default(),
+        }
+    }
+
+    pub fn with_row_group_size(mut self, row_group_size: RowGroupSize) -> Self {
+        self.row_group_size = row_group_size;
+        self
+    }
+
+    pub fn write_message(&mut self, message: &Message) -> Result<()> {
+        self.batch.push(message, DataFormat::PARQUET)?;
+        if self.batch.len() >= BATCH_SIZE.min(self.row_group_size.rows.max(1)) {
+            self.write_batch()?;
+        }
+        Ok(())
+    }
+
+    fn arrow_writer(&mut self) -> Result<&mut ArrowWriter<Vec<u8>>> {
+        if self.writer.is_none() {
+            let properties = WriterProperties::builder()
+                .set_max_row_group_size(self.row_group_size.rows.max(1))
+                .set_compression(Compression::SNAPPY)
+                .build();
+            self.writer = Some(Box::new(ArrowWriter::try_new(
+                Vec::new(),
+                record_batch_schema(),
+                Some(properties),
+            )?));
+        }
+        Ok(self.writer.as_mut().expect("writer is created above"))
+    }
+
+    fn write_batch(&mut self) -> Result<()> {
+        let batch = std::mem::take(&mut self.batch).into_record_batch()?;
+        let bytes = self.row_group_size.bytes;
+        let writer = self.arrow_writer()?;
+        writer.write(&batch)?;
+        if let Some(bytes) = bytes
+            && writer.in_progress_size() >= bytes
+        {
+            writer.flush()?;
+        }
+        // Записанные байты не нужны писателю Parquet, он считает смещения сам
+        let data = std::mem::take(writer.inner_mut());
+        self.output.write_all(&data)?;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W> {
+        if self.batch.len() > 0 || self.writer.is_none() {
+            self.write_batch()?;
+        }
+        let writer = self
+            .writer
+            .take()
+            .expect("writer is created by write_batch");
+        let data = writer.into_inner()?;
+        self.output.write_all(&data)?;
+        self.output.flush()?;
+        Ok(self.output)
+    }
+}
+
+impl Default for ParquetYPBankRecord {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BankRecord for ParquetYPBankRecord {
+    fn write_to<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        let mut writer = ParquetRecordWriter::new(writer);
+        for message in &self.data {
+            writer.write_message(message)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+
+    fn push(&mut self, value: Message) {
+        self.data.push(value);
+    }
+
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn pop(&mut self) -> Option<Message> {
+        self.data.pop()
+    }
+
+    fn iter(&self) -> std::slice::Iter<'_, Message> {
+        self.data.iter()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::{ArrayRef, RecordBatch, UInt64Array};
+    use arrow_schema::{DataType, Field, Schema, SchemaRef};
+
+    use super::*;
+    use crate::convertor::test_records::{pending_transfer, pending_transfers};
+
+    fn write(messages: &[Message], row_group_size: RowGroupSize) -> Vec<u8> {
+        let mut writer = ParquetRecordWriter::new(Vec::new()).with_row_group_size(row_group_size);
+        for message in messages {
+            writer.write_message(message).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    fn metadata(data: Vec<u8>) -> (SchemaRef, Vec<usize>) {
+        let builder = ParquetRecordBatchReaderBuilder::try_new(bytes::Bytes::from(data)).unwrap();
+        let row_groups = builder
+            .metadata()
+            .row_groups()
+            .iter()
+            .map(|row_group| row_group.num_rows() as usize)
+            .collect();
+        (builder.schema().clone(), row_groups)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let messages = pending_transfers();
+
+        let data = write(&messages, RowGroupSize::default());
+        let record = ParquetYPBankRecord::from_read(data.as_slice()).unwrap();
+        assert_eq!(record.iter().cloned().collect::<Vec<_>>(), messages);
+
+        let (file_schema, row_groups) = metadata(data);
+        assert_eq!(file_schema, record_batch_schema());
+        assert_eq!(row_groups, [2500]);
+
+        let empty = write(&[], RowGroupSize::default());
+        assert!(
+            ParquetYPBankRecord::from_read(empty.as_slice())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_row_group_size() {
+        let messages = (1..=2500).map(pending_transfer).collect::<Vec<_>>();
+        let data = write(
+            &messages,
+            RowGroupSize {
+                rows: 1000,
+                bytes: None,
+            },
+        );
+        assert_eq!(metadata(data).1, [1000, 1000, 500]);
+
+        // Группа записывается после RecordBatch, превысившего размер
+        let data = write(
+            &messages,
+            RowGroupSize {
+                rows: 1_000_000,
+                bytes: Some(1),
+            },
+        );
+        assert_eq!(metadata(data.clone()).1, [1024, 1024, 452]);
+        assert_eq!(
+            ParquetYPBankRecord::from_read(data.as_slice())
+                .unwrap()
+                .len(),
+            2500
+        );
+    }
+
+    #[test]
+    fn test_read_errors() {
+        let err = ParquetYPBankRecord::from_read("TX_ID,TX_TYPE\n".as_bytes()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parquet error: Invalid Parquet file. Corrupt footer"
+        );
+        assert!(!err.is_record_error());
+
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "TX_ID",
+            DataType::UInt64,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(UInt64Array::from(vec![1])) as ArrayRef],
+        )
+        .unwrap();
+        let mut writer = ArrowWriter::try_new(Vec::new(), schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        let data = writer.into_inner().unwrap();
+        let err = ParquetYPBankRecord::from_read(data.as_slice()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Arrow error: Schema error: column TX_TYPE is missing"
+        );
+    }
+}
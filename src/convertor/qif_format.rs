@@ -0,0 +1,822 @@
+use std::io::BufRead;
+
+use crate::{
+    DataFormat,
+    convertor::{
+        AmountPolicy, BankRecord, Currency, Message, Money, StatusTransaction, TxIdStrategy,
+        TypeTransaction,
+        datetime::{DateTime, MILLIS_PER_DAY},
+        statement::{self, Statement, Statements, Transfers},
+    },
+    error::{AppError, Location, ParseError, Result},
+    validator::{AccountConsistency, Rule, TransferAccounts},
+};
+
+// Типы счетов, транзакции которых читаются как записи
+const ACCOUNT_TYPES: [&str; 5] = ["Bank", "Cash", "CCard", "Oth A", "Oth L"];
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct QifYPBankRecord {
+    data: Vec<Message>,
+}
+
+impl QifYPBankRecord {
+    pub fn from_read<R: std::io::Read>(r: R) -> Result<Self> {
+        let data = QifRecordReader::new(r).collect::<Result<Vec<_>>>()?;
+        Ok(Self { data })
+    }
+
+    pub fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+}
+
+/// Раздел файла, заданный последним заголовком `!`
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Section {
+    // Список счетов !Account
+    Account,
+    // Транзакции счета !Type:Bank и т.п.
+    Transactions,
+    // Категории, классы, инвестиции и прочие разделы без записей YPBank
+    Other,
+}
+
+/// Счет, описанный в разделе `!Account`
+#[derive(Debug, Default, Clone)]
+struct Account {
+    name: Option<String>,
+    description: Option<String>,
+}
+
+/// Строки транзакции, разбираются после строки `^`
+#[derive(Debug, Default)]
+struct Entry {
+    line: u64,
+    record: u64,
+    date: Option<String>,
+    amount: Option<String>,
+    payee: Option<String>,
+    memo: Option<String>,
+    number: Option<String>,
+    account: Account,
+}
+
+impl Entry {
+    /// Запись и ее направление для счета раздела: зачисление или списание
+    fn into_message(
+        self,
+        strategy: TxIdStrategy,
+        location: &Location,
+    ) -> std::result::Result<(Message, bool), ParseError> {
+        let error = |field: &str, message: String| ParseError {
+            location: Location {
+                field: Some(field.to_string()),
+                ..location.clone()
+            },
+            message,
+        };
+        let required = |value: &Option<String>, field: &str| {
+            value
+                .as_deref()
+                .map(|value| value.trim().to_string())
+                .ok_or_else(|| error(field, "missing line".to_string()))
+        };
+
+        let account = match self.account.name.as_deref().map(str::trim) {
+            Some(name) => name
+                .parse::<u64>()
+                .map_err(|_| error("N", format!("account {:?} is not a YPBank user id", name)))?,
+            None => 0,
+        };
+
+        // Валюта записывается описанием счета, без нее сумма читается с двумя знаками
+        // дробной части, как в большинстве программ учета
+        let currency = self
+            .account
+            .description
+            .as_deref()
+            .and_then(|code| Currency::from_code(code.trim()).ok());
+        let amount = required(&self.amount, "T")?;
+        // Разделители тысяч 1,234.56
+        let digits = amount.replace(',', "");
+        let (credit, digits) = match digits.strip_prefix('-') {
+            Some(digits) => (false, digits),
+            None => (true, digits.strip_prefix('+').unwrap_or(&digits)),
+        };
+        let scale = match currency {
+            Some(currency) => currency,
+            None => Currency::from_code("USD").expect("USD is a known currency"),
+        };
+        let money = Money::from_decimal(digits, scale)
+            .ok()
+            .filter(|money| money.minor_units >= 0)
+            .ok_or_else(|| {
+                error(
+                    "T",
+                    format!(
+                        "invalid amount {:?}, {} decimals allowed",
+                        amount,
+                        scale.exponent()
+                    ),
+                )
+            })?;
+
+        let date = required(&self.date, "D")?;
+        let timestamp =
+            parse_date(&date).ok_or_else(|| error("D", format!("invalid date {:?}", date)))?;
+
+        // Числовой получатель - счет второй стороны, иначе имя получателя другой программы
+        let payee = self.payee.as_deref().map(str::trim);
+        let counterparty = payee.and_then(|payee| payee.parse::<u64>().ok());
+        let description = match (self.memo, payee) {
+            (Some(memo), _) => memo,
+            (None, Some(payee)) if counterparty.is_none() => payee.to_string(),
+            _ => String::new(),
+        };
+        let counterparty = counterparty.unwrap_or(0);
+        let (from_user_id, to_user_id) = statement::accounts(account, counterparty, credit);
+
+        // Перевод записан в разделах обоих счетов, поэтому TX_ID не зависит от направления
+        let tx_id = match strategy {
+            TxIdStrategy::Number => {
+                let number = required(&self.number, "N")?;
+                number
+                    .parse::<u64>()
+                    .map_err(|_| error("N", format!("invalid integer {:?}", number)))?
+            }
+            // Номер присваивает читатель после пропуска второй строки перевода
+            TxIdStrategy::Sequence(_) => 0,
+            TxIdStrategy::Hash => hash(&[
+                &from_user_id.to_string(),
+                &to_user_id.to_string(),
+                &timestamp.to_string(),
+                &money.minor_units.to_string(),
+                &description,
+                self.number.as_deref().map_or("", str::trim),
+            ]),
+        };
+
+        let tx_type = if account != 0 && counterparty != 0 {
+            TypeTransaction::Transfer
+        } else if credit {
+            TypeTransaction::Deposit
+        } else {
+            TypeTransaction::Withdrawal
+        };
+        let message = Message {
+            tx_id,
+            tx_type,
+            from_user_id,
+            to_user_id,
+            amount: money.minor_units,
+            timestamp,
+            status: StatusTransaction::Success,
+            description,
+            currency,
+        };
+        Ok((message, credit))
+    }
+}
+
+/// Потоковый читатель QIF, возвращает по записи на каждую транзакцию счета
+pub struct QifRecordReader<R: std::io::Read> {
+    lines: std::io::Lines<std::io::BufReader<R>>,
+    finished: bool,
+    source: Option<String>,
+    // Номер последней прочитанной строки
+    line: u64,
+    // Количество прочитанных транзакций
+    record: u64,
+    section: Section,
+    // Счет, описание которого читается в разделе !Account
+    pending: Account,
+    // Счет последующих разделов транзакций
+    account: Account,
+    entry: Option<Entry>,
+    tx_id_strategy: TxIdStrategy,
+    // Количество записей, получивших номер TxIdStrategy::Sequence
+    sequence: u64,
+    transfers: Transfers,
+}
+
+impl<R: std::io::Read> QifRecordReader<R> {
+    pub fn new(r: R) -> Self {
+        Self {
+            lines: std::io::BufReader::new(r).lines(),
+            finished: false,
+            source: None,
+            line: 0,
+            record: 0,
+            section: Section::Other,
+            pending: Account::default(),
+            account: Account::default(),
+            entry: None,
+            tx_id_strategy: TxIdStrategy::default(),
+            sequence: 0,
+            transfers: Transfers::default(),
+        }
+    }
+
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    pub fn with_tx_id_strategy(mut self, tx_id_strategy: TxIdStrategy) -> Self {
+        self.tx_id_strategy = tx_id_strategy;
+        self
+    }
+
+    /// Разбор строки, возвращает завершенную транзакцию
+    fn read_line(&mut self, line: &str) -> Option<Entry> {
+        if line.trim().is_empty() {
+            return None;
+        }
+
+        if let Some(header) = line.strip_prefix('!') {
+            let header = header.trim();
+            // Флаги !Option:AutoSwitch и !Clear:AutoSwitch не меняют раздел
+            if header.starts_with("Option:") || header.starts_with("Clear:") {
+                return None;
+            }
+            self.section = match header.strip_prefix("Type:") {
+                _ if header.eq_ignore_ascii_case("Account") => Section::Account,
+                Some(kind)
+                    if ACCOUNT_TYPES
+                        .iter()
+                        .any(|t| t.eq_ignore_ascii_case(kind.trim())) =>
+                {
+                    Section::Transactions
+                }
+                _ => Section::Other,
+            };
+            self.pending = Account::default();
+            // Транзакция без завершающей строки ^ заканчивается заголовком раздела
+            return self.entry.take();
+        }
+
+        if line.starts_with('^') {
+            if self.section == Section::Account {
+                self.account = std::mem::take(&mut self.pending);
+            }
+            return self.entry.take();
+        }
+
+        let mut chars = line.chars();
+        let code = chars.next()?;
+        let value = chars.as_str();
+        match self.section {
+            Section::Account => match code {
+                'N' => self.pending.name = Some(value.to_string()),
+                'D' => self.pending.description = Some(value.to_string()),
+                _ => {}
+            },
+            Section::Transactions => {
+                let entry = self.entry.get_or_insert_with(|| {
+                    self.record += 1;
+                    Entry {
+                        line: self.line,
+                        record: self.record,
+                        account: self.account.clone(),
+                        ..Entry::default()
+                    }
+                });
+                // Категории L, отметки C, адреса A и части разделенной транзакции
+                // S, E, $ не используются
+                let field = match code {
+                    'D' => &mut entry.date,
+                    'T' => &mut entry.amount,
+                    'P' => &mut entry.payee,
+                    'M' => &mut entry.memo,
+                    'N' => &mut entry.number,
+                    _ => return None,
+                };
+                *field = Some(value.to_string());
+            }
+            Section::Other => {}
+        }
+        None
+    }
+
+    fn finish_entry(&mut self, entry: Entry) -> Result<Option<Message>> {
+        let location = Location {
+            source: self.source.clone(),
+            line: Some(entry.line),
+            record: Some(entry.record),
+            ..Location::default()
+        };
+        let (mut message, credit) = entry
+            .into_message(self.tx_id_strategy, &location)
+            .map_err(|e| AppError::QifParseError(Box::new(e)))?;
+        let TxIdStrategy::Sequence(start) = self.tx_id_strategy else {
+            return Ok((!self.transfers.is_repeated(&message)).then_some(message));
+        };
+        // Номера разных строк перевода различаются, поэтому он ищется по полям
+        if self.transfers.is_repeated_entry(&message, credit) {
+            return Ok(None);
+        }
+        message.tx_id = start.checked_add(self.sequence).ok_or_else(|| {
+            AppError::QifParseError(Box::new(ParseError {
+                location: Location {
+                    field: Some("N".to_string()),
+                    ..location
+                },
+                message: "TX_ID sequence overflow".to_string(),
+            }))
+        })?;
+        self.sequence += 1;
+        Ok(Some(message))
+    }
+}
+
+impl<R: std::io::Read> Iterator for QifRecordReader<R> {
+    type Item = Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            let entry = match self.lines.next() {
+                Some(Ok(line)) => {
+                    self.line += 1;
+                    self.read_line(line.trim_end_matches('\r'))
+                }
+                Some(Err(e)) => {
+                    self.finished = true;
+                    return Some(Err(AppError::IOError(e)));
+                }
+                None => {
+                    self.finished = true;
+                    self.entry.take()
+                }
+            };
+            if let Some(entry) = entry {
+                match self.finish_entry(entry) {
+                    Ok(Some(message)) => return Some(Ok(message)),
+                    Ok(None) => continue,
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Писатель QIF, по разделу `!Type:Bank` на каждую выписку [`Statements`]
+pub struct QifRecordWriter<W: std::io::Write> {
+    writer: W,
+    amount_policy: AmountPolicy,
+    statements: Statements,
+}
+
+impl<W: std::io::Write> QifRecordWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            amount_policy: AmountPolicy::default(),
+            statements: Statements::default(),
+        }
+    }
+
+    pub fn with_amount_policy(mut self, amount_policy: AmountPolicy) -> Self {
+        self.amount_policy = amount_policy;
+        self
+    }
+
+    pub fn write_message(&mut self, message: &Message) -> Result<()> {
+        let message = self.amount_policy.apply(message, &DataFormat::QIF)?;
+        let currency = message
+            .currency
+            .ok_or_else(|| mapping_error(&message, "CURRENCY is required for the account"))?;
+        if message.status != StatusTransaction::Success {
+            return Err(mapping_error(
+                &message,
+                &format!(
+                    "STATUS {}, QIF contains posted transactions only",
+                    message.status
+                ),
+            ));
+        }
+        if message.from_user_id == 0 && message.to_user_id == 0 {
+            return Err(mapping_error(
+                &message,
+                "FROM_USER_ID and TO_USER_ID are 0, there is no account",
+            ));
+        }
+        if message.timestamp % MILLIS_PER_DAY != 0 {
+            return Err(mapping_error(
+                &message,
+                &format!(
+                    "TIMESTAMP {} is not midnight UTC, QIF stores the date only",
+                    message.timestamp
+                ),
+            ));
+        }
+        if message.description.contains(['\r', '\n']) {
+            return Err(mapping_error(
+                &message,
+                "DESCRIPTION contains a line break, QIF memo is a single line",
+            ));
+        }
+        // Тип транзакции восстанавливается при чтении по счетам и знаку суммы
+        if let Some(reason) = AccountConsistency
+            .check(&message)
+            .or_else(|| TransferAccounts.check(&message))
+        {
+            return Err(mapping_error(
+                &message,
+                &format!("{}, TX_TYPE cannot be restored from QIF", reason),
+            ));
+        }
+        self.statements.push(&message, currency);
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W> {
+        let statements = std::mem::take(&mut self.statements);
+        for statement in statements.into_statements() {
+            self.write_statement(&statement)?;
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_statement(&mut self, statement: &Statement) -> Result<()> {
+        let currency = statement.currency;
+        let w = &mut self.writer;
+        write!(
+            w,
+            "!Account\nN{}\nTBank\nD{}\n^\n",
+            statement.account, currency
+        )?;
+        writeln!(w, "!Type:Bank")?;
+        for entry in &statement.entries {
+            let message = &entry.message;
+            let date = DateTime::from_timestamp(message.timestamp);
+            writeln!(w, "D{:02}/{:02}/{:04}", date.month, date.day, date.year)?;
+            // Знак задает направление для счета, поэтому списание нуля пишется как -0.00
+            writeln!(
+                w,
+                "T{}{}",
+                if entry.credit { "" } else { "-" },
+                Money::new(message.amount, currency).to_decimal()
+            )?;
+            if entry.counterparty() != 0 {
+                writeln!(w, "P{}", entry.counterparty())?;
+            }
+            if !message.description.is_empty() {
+                writeln!(w, "M{}", message.description)?;
+            }
+            writeln!(w, "N{}", message.tx_id)?;
+            writeln!(w, "^")?;
+        }
+        Ok(())
+    }
+}
+
+/// Дата QIF `M/D/YYYY`, `M/D/YY`, `M/D'YY` или `YYYY-MM-DD`. В записи Quicken апостроф
+/// отделяет годы XXI века, без него годы `70`-`99` относятся к XX веку.
+fn parse_date(value: &str) -> Option<u64> {
+    let value = value.replace(' ', "");
+    let parts = value.split(['/', '\'', '-', '.']).collect::<Vec<_>>();
+    let [first, second, third] = parts.as_slice() else {
+        return None;
+    };
+    if ![first, second, third]
+        .iter()
+        .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+    {
+        return None;
+    }
+    let (year, month, day) = if first.len() == 4 {
+        (*first, *second, *third)
+    } else {
+        (*third, *first, *second)
+    };
+    if month.len() > 2 || day.len() > 2 {
+        return None;
+    }
+    let year = match (year.len(), year.parse::<i64>().ok()?) {
+        (4, year) => year,
+        (1 | 2, year) if value.contains('\'') || year < 70 => 2000 + year,
+        (1 | 2, year) => 1900 + year,
+        _ => return None,
+    };
+    DateTime::from_date(year, month.parse().ok()?, day.parse().ok()?)?.timestamp()
+}
+
+/// FNV-1a по полям транзакции: хеш стандартной библиотеки может меняться между версиями
+fn hash(fields: &[&str]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for field in fields {
+        for byte in field.bytes().chain(std::iter::once(0)) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+fn mapping_error(message: &Message, reason: &str) -> AppError {
+    AppError::MappingError {
+        format: DataFormat::QIF,
+        tx_id: message.tx_id,
+        reason: reason.to_string(),
+    }
+}
+
+impl Default for QifYPBankRecord {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BankRecord for QifYPBankRecord {
+    fn write_to<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        let mut writer = QifRecordWriter::new(writer);
+        for message in &self.data {
+            writer.write_message(message)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+
+    fn push(&mut self, value: Message) {
+        self.data.push(value);
+    }
+
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn pop(&mut self) -> Option<Message> {
+        self.data.pop()
+    }
+
+    fn iter(&self) -> std::slice::Iter<'_, Message> {
+        self.data.iter()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convertor::test_records::{self, transaction};
+
+    /// QIF хранит только дату, поэтому записи начинаются в полночь UTC
+    fn message(tx_id: u64, tx_type: TypeTransaction, from: u64, to: u64) -> Message {
+        Message {
+            timestamp: 1632960000000 + tx_id * 86_400_000,
+            ..transaction(tx_id, tx_type, from, to)
+        }
+    }
+
+    fn write(messages: &[Message]) -> String {
+        test_records::write_text(messages, &DataFormat::QIF)
+    }
+
+    fn read(qif: &str, strategy: TxIdStrategy) -> Vec<Result<Message>> {
+        QifRecordReader::new(qif.as_bytes())
+            .with_source("export.qif")
+            .with_tx_id_strategy(strategy)
+            .collect()
+    }
+
+    #[test]
+    fn test_write() {
+        let qif = write(&[
+            message(1, TypeTransaction::Transfer, 11, 22),
+            message(2, TypeTransaction::Deposit, 0, 11),
+        ]);
+        assert_eq!(
+            qif,
+            concat!(
+                "!Account\nN11\nTBank\nDEUR\n^\n!Type:Bank\n",
+                "D10/01/2021\nT-123.45\nP22\nMRecord 1\nN1\n^\n",
+                "D10/02/2021\nT123.45\nMRecord 2\nN2\n^\n",
+                "!Account\nN22\nTBank\nDEUR\n^\n!Type:Bank\n",
+                "D10/01/2021\nT123.45\nP11\nMRecord 1\nN1\n^\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut messages = vec![
+            message(1, TypeTransaction::Transfer, 11, 22),
+            message(2, TypeTransaction::Deposit, 0, 11),
+            message(3, TypeTransaction::Withdrawal, 22, 0),
+            message(4, TypeTransaction::Withdrawal, 11, 0),
+        ];
+        messages[1].description = "Зарплата ^ за сентябрь".to_string();
+        messages[2].description = String::new();
+        messages[2].currency = Some(Currency::from_code("JPY").unwrap());
+        messages[3].amount = 0;
+
+        let qif = write(&messages);
+        assert!(qif.contains("T-0.00\n"));
+        let mut data = QifYPBankRecord::from_read(qif.as_bytes())
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        data.sort_by_key(|message| message.tx_id);
+        assert_eq!(data, messages);
+    }
+
+    #[test]
+    fn test_write_errors() {
+        let mut message = message(7, TypeTransaction::Deposit, 0, 11);
+        let mut writer = QifRecordWriter::new(Vec::new());
+
+        message.from_user_id = 22;
+        let err = writer.write_message(&message).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "QIF mapping error: TX_ID 7: DEPOSIT must have FROM_USER_ID 0, found 22, TX_TYPE cannot be restored from QIF"
+        );
+
+        message.from_user_id = 0;
+        message.status = StatusTransaction::Failure;
+        assert!(matches!(
+            writer.write_message(&message),
+            Err(AppError::MappingError { tx_id: 7, .. })
+        ));
+
+        message.status = StatusTransaction::Success;
+        message.currency = None;
+        assert!(matches!(
+            writer.write_message(&message),
+            Err(AppError::MappingError { .. })
+        ));
+
+        message.currency = Some(Currency::from_code("EUR").unwrap());
+        message.to_user_id = 0;
+        assert!(matches!(
+            writer.write_message(&message),
+            Err(AppError::MappingError { .. })
+        ));
+
+        message.to_user_id = 11;
+        message.amount = -100;
+        assert!(matches!(
+            writer.write_message(&message),
+            Err(AppError::NegativeAmountError { .. })
+        ));
+
+        message.amount = 100;
+        message.timestamp = 1633036800123;
+        assert_eq!(
+            writer.write_message(&message).unwrap_err().to_string(),
+            "QIF mapping error: TX_ID 7: TIMESTAMP 1633036800123 is not midnight UTC, QIF stores the date only"
+        );
+
+        message.timestamp = 1632960000000;
+        message.description = "line 1\nline 2".to_string();
+        assert_eq!(
+            writer.write_message(&message).unwrap_err().to_string(),
+            "QIF mapping error: TX_ID 7: DESCRIPTION contains a line break, QIF memo is a single line"
+        );
+    }
+
+    #[test]
+    fn test_read_other_program() {
+        // Экспорт Quicken: счет без валюты, даты с апострофом, получатели-имена,
+        // номера чеков и разделы категорий
+        let qif = concat!(
+            "!Type:Cat\nNGroceries\nE\n^\n",
+            "!Option:AutoSwitch\n!Account\nN4100\nTBank\n^\n!Clear:AutoSwitch\n",
+            "!Type:Bank \n",
+            "D 1/ 5'21\nT-1,234.50\nCX\nPCorner Shop\nLGroceries\nN101\n^\n",
+            "D12/31/99\nT25\nPRefund\nMCoupon\n",
+        );
+        let results = read(qif, TxIdStrategy::Sequence(1000));
+        let messages = results.into_iter().collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(
+            messages,
+            vec![
+                Message {
+                    tx_id: 1000,
+                    tx_type: TypeTransaction::Withdrawal,
+                    from_user_id: 4100,
+                    to_user_id: 0,
+                    amount: 123450,
+                    timestamp: 1609804800000,
+                    status: StatusTransaction::Success,
+                    description: "Corner Shop".to_string(),
+                    currency: None,
+                },
+                Message {
+                    tx_id: 1001,
+                    tx_type: TypeTransaction::Deposit,
+                    from_user_id: 0,
+                    to_user_id: 4100,
+                    amount: 2500,
+                    timestamp: 946598400000,
+                    status: StatusTransaction::Success,
+                    description: "Coupon".to_string(),
+                    currency: None,
+                },
+            ]
+        );
+
+        // Хеш не зависит от порядка чтения, но различает транзакции
+        let first = read(qif, TxIdStrategy::Hash);
+        let second = read(qif, TxIdStrategy::Hash);
+        let ids = |results: &[Result<Message>]| {
+            results
+                .iter()
+                .map(|r| r.as_ref().unwrap().tx_id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(&first), ids(&second));
+        assert_ne!(ids(&first)[0], ids(&first)[1]);
+    }
+
+    #[test]
+    fn test_transfer_tx_id() {
+        // Перевод записан в разделах обоих счетов и читается один раз,
+        // одинаковые по полям переводы остаются разными записями
+        let first = message(1, TypeTransaction::Transfer, 11, 22);
+        let mut same = message(2, TypeTransaction::Transfer, 11, 22);
+        same.timestamp = first.timestamp;
+        same.description = first.description.clone();
+        let deposit = message(3, TypeTransaction::Deposit, 0, 22);
+        let expected = [first, same, deposit];
+        let qif = write(&expected);
+
+        for strategy in [TxIdStrategy::Sequence(1), TxIdStrategy::Hash] {
+            let mut messages = read(&qif, strategy)
+                .into_iter()
+                .collect::<Result<Vec<_>>>()
+                .unwrap();
+            let mut tx_ids = messages.iter().map(|m| m.tx_id).collect::<Vec<_>>();
+            tx_ids.sort();
+            tx_ids.dedup();
+            assert_eq!(tx_ids.len(), expected.len(), "{:?}", strategy);
+            if strategy == TxIdStrategy::Sequence(1) {
+                assert_eq!(tx_ids, vec![1, 2, 3]);
+            }
+
+            messages.sort_by_key(|m| (m.tx_type == TypeTransaction::Deposit, m.tx_id));
+            let messages = messages
+                .into_iter()
+                .zip(&expected)
+                .map(|(actual, expected)| Message {
+                    tx_id: expected.tx_id,
+                    ..actual
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(messages, expected, "{:?}", strategy);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let qif = concat!(
+            "!Account\nNChecking\n^\n!Type:Bank\nD01/05/2021\nT1\nN1\n^\n",
+            "!Account\nN11\nDEUR\n^\n!Type:Bank\n",
+            "D01/05/2021\nT1.234\nN2\n^\n",
+            "D13/05/2021\nT1\nN3\n^\n",
+            "D01/05/2021\nT1\nNATM\n^\n",
+            "D01/05/2021\nN5\n^\n",
+            "D01/05/2021\nT1\nN6\n^\n",
+        );
+        let results = read(qif, TxIdStrategy::Number);
+        assert_eq!(results.len(), 6);
+        let messages = results[..5]
+            .iter()
+            .map(|r| r.as_ref().unwrap_err().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                "export.qif:5: N: account \"Checking\" is not a YPBank user id",
+                "export.qif:14: T: invalid amount \"1.234\", 2 decimals allowed",
+                "export.qif:18: D: invalid date \"13/05/2021\"",
+                "export.qif:22: N: invalid integer \"ATM\"",
+                "export.qif:26: T: missing line",
+            ]
+        );
+        assert!(
+            results[..5]
+                .iter()
+                .all(|r| r.as_ref().unwrap_err().is_record_error())
+        );
+        assert_eq!(results[5].as_ref().unwrap().tx_id, 6);
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("09/30/2021"), Some(1632960000000));
+        assert_eq!(parse_date("9/30'21"), Some(1632960000000));
+        assert_eq!(parse_date(" 9/30/21"), Some(1632960000000));
+        assert_eq!(parse_date("2021-09-30"), Some(1632960000000));
+        assert_eq!(parse_date("12/31/99"), Some(946598400000));
+        assert_eq!(parse_date("02/30/2021"), None);
+        assert_eq!(parse_date("30.09"), None);
+    }
+}
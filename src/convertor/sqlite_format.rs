@@ -0,0 +1,615 @@
+use std::collections::VecDeque;
+
+use rusqlite::{Connection, MAIN_DB, Statement, types::ValueRef};
+
+use crate::{
+    DataFormat,
+    convertor::{BankRecord, Currency, Message, StatusTransaction, TypeTransaction},
+    error::{AppError, Location, ParseError, Result},
+};
+
+// Колонки таблицы записей в порядке полей записи
+const COLUMNS: [&str; 9] = [
+    "tx_id",
+    "tx_type",
+    "from_user_id",
+    "to_user_id",
+    "amount",
+    "timestamp",
+    "status",
+    "description",
+    "currency",
+];
+
+// Колонки с индексами для выборок по транзакции, счету и времени
+const INDEXED_COLUMNS: [&str; 4] = ["tx_id", "from_user_id", "to_user_id", "timestamp"];
+
+/// Таблица SQLite с записями YPBank
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqliteTable {
+    /// Имя таблицы, в которую пишутся и из которой читаются записи
+    pub name: String,
+    /// Произвольный `SELECT` для чтения вместо всей таблицы `name`.
+    /// Колонки результата ищутся по имени без учета регистра, запрос, изменяющий
+    /// базу, - ошибка.
+    pub query: Option<String>,
+}
+
+impl Default for SqliteTable {
+    fn default() -> Self {
+        Self {
+            name: "transactions".to_string(),
+            query: None,
+        }
+    }
+}
+
+impl SqliteTable {
+    /// Запрос чтения: `query` или все строки таблицы в порядке вставки
+    fn select(&self) -> String {
+        match &self.query {
+            Some(query) => query.clone(),
+            None => format!("SELECT * FROM {} ORDER BY rowid", quote(&self.name)),
+        }
+    }
+}
+
+/// Имя таблицы или индекса в кавычках, кавычки внутри имени удваиваются
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Запись в таблицу `table.name`: таблица и индексы по `tx_id`, `from_user_id`, `to_user_id`
+/// и `timestamp` создаются, если их нет, записи добавляются в одной транзакции.
+/// Запись, которую нельзя сохранить, отменяет всю вставку.
+pub fn write_sqlite(
+    connection: &Connection,
+    table: &SqliteTable,
+    messages: &[Message],
+) -> Result<()> {
+    let transaction = connection.unchecked_transaction()?;
+    create_table(&transaction, &table.name)?;
+    {
+        let mut statement = transaction.prepare(&insert(&table.name))?;
+        for message in messages {
+            insert_message(&mut statement, message)?;
+        }
+    }
+    transaction.commit()?;
+    Ok(())
+}
+
+/// Записи из таблицы `table.name` или результата `table.query`, первая строка,
+/// которая не является записью, - ошибка
+pub fn read_sqlite(connection: &Connection, table: &SqliteTable) -> Result<Vec<Message>> {
+    select(connection, table, None)?.into_iter().collect()
+}
+
+fn create_table(connection: &Connection, table: &str) -> Result<()> {
+    let mut sql = format!(
+        "CREATE TABLE IF NOT EXISTS {} (
+            tx_id INTEGER NOT NULL,
+            tx_type TEXT NOT NULL CHECK (tx_type IN ('DEPOSIT', 'TRANSFER', 'WITHDRAWAL')),
+            from_user_id INTEGER NOT NULL,
+            to_user_id INTEGER NOT NULL,
+            amount INTEGER NOT NULL,
+            timestamp INTEGER NOT NULL,
+            status TEXT NOT NULL CHECK (status IN ('SUCCESS', 'FAILURE', 'PENDING')),
+            description TEXT NOT NULL,
+            currency TEXT
+        );",
+        quote(table)
+    );
+    for column in INDEXED_COLUMNS {
+        sql.push_str(&format!(
+            "CREATE INDEX IF NOT EXISTS {} ON {} ({});",
+            quote(&format!("{}_{}", table, column)),
+            quote(table),
+            column
+        ));
+    }
+    connection.execute_batch(&sql)?;
+    Ok(())
+}
+
+fn insert(table: &str) -> String {
+    format!(
+        "INSERT INTO {} ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        quote(table),
+        COLUMNS.join(", ")
+    )
+}
+
+/// INTEGER SQLite хранит i64, поэтому значения больше i64::MAX - ошибка записи
+fn insert_message(statement: &mut Statement<'_>, message: &Message) -> Result<()> {
+    let integer = |name: &str, value: u64| {
+        i64::try_from(value).map_err(|_| AppError::MappingError {
+            format: DataFormat::SQLITE,
+            tx_id: message.tx_id,
+            reason: format!("{} {} does not fit SQLite INTEGER", name, value),
+        })
+    };
+    statement.execute((
+        integer("TX_ID", message.tx_id)?,
+        message.tx_type.to_string(),
+        integer("FROM_USER_ID", message.from_user_id)?,
+        integer("TO_USER_ID", message.to_user_id)?,
+        message.amount,
+        integer("TIMESTAMP", message.timestamp)?,
+        message.status.to_string(),
+        &message.description,
+        message.currency.as_ref().map(Currency::code),
+    ))?;
+    Ok(())
+}
+
+/// Результат запроса: ошибки строк - ошибки записи, отсутствие колонки или ошибка
+/// запроса завершают чтение
+fn select(
+    connection: &Connection,
+    table: &SqliteTable,
+    source: Option<&str>,
+) -> Result<Vec<Result<Message>>> {
+    let mut statement = connection.prepare(&table.select())?;
+    // read_sqlite принимает соединение вызывающего, которое может быть открыто на запись
+    if !statement.readonly() {
+        return Err(rusqlite::Error::InvalidQuery.into());
+    }
+    // Номера колонок ищутся до чтения строк, CURRENCY необязательна
+    let mut indexes = [None; COLUMNS.len()];
+    for (index, column) in indexes.iter_mut().zip(COLUMNS) {
+        *index = match statement.column_index(column) {
+            Ok(i) => Some(i),
+            Err(_) if column == "currency" => None,
+            Err(e) => return Err(e.into()),
+        };
+    }
+    let mut rows = statement.query([])?;
+    let mut messages = Vec::new();
+    let mut record = 0;
+    while let Some(row) = rows.next()? {
+        record += 1;
+        let value = |i: usize| match indexes[i] {
+            Some(index) => row.get_ref(index),
+            None => Ok(ValueRef::Null),
+        };
+        let result = message(value).map_err(|(field, message)| {
+            AppError::SqliteRecordError(Box::new(ParseError {
+                location: Location {
+                    source: source.map(str::to_string),
+                    record: Some(record),
+                    field: Some(field.to_string()),
+                    ..Location::default()
+                },
+                message,
+            }))
+        });
+        messages.push(result);
+    }
+    Ok(messages)
+}
+
+/// Запись строки, `value` возвращает значение колонки по ее номеру в [`COLUMNS`].
+/// При ошибке возвращаются имя поля и сообщение.
+fn message<'a>(
+    value: impl Fn(usize) -> rusqlite::Result<ValueRef<'a>>,
+) -> std::result::Result<Message, (&'static str, String)> {
+    const FIELDS: [&str; 9] = [
+        "TX_ID",
+        "TX_TYPE",
+        "FROM_USER_ID",
+        "TO_USER_ID",
+        "AMOUNT",
+        "TIMESTAMP",
+        "STATUS",
+        "DESCRIPTION",
+        "CURRENCY",
+    ];
+    let get = |i: usize| value(i).map_err(|e| (FIELDS[i], e.to_string()));
+    // Таблицы, импортированные из CSV, хранят числа текстом
+    let integer = |i: usize| match get(i)? {
+        ValueRef::Integer(value) => Ok(value),
+        ValueRef::Text(text) => std::str::from_utf8(text)
+            .ok()
+            .and_then(|text| text.trim().parse().ok())
+            .ok_or_else(|| {
+                (
+                    FIELDS[i],
+                    format!("invalid integer {:?}", String::from_utf8_lossy(text)),
+                )
+            }),
+        ValueRef::Null => Err((FIELDS[i], "value is null".to_string())),
+        other => Err((
+            FIELDS[i],
+            format!("expected an integer, found {}", other.data_type()),
+        )),
+    };
+    let unsigned = |i: usize| {
+        let value = integer(i)?;
+        u64::try_from(value).map_err(|_| (FIELDS[i], format!("negative value {}", value)))
+    };
+    let text = |i: usize| match get(i)? {
+        ValueRef::Text(text) => std::str::from_utf8(text)
+            .map(str::to_string)
+            .map_err(|e| (FIELDS[i], e.to_string())),
+        ValueRef::Null => Err((FIELDS[i], "value is null".to_string())),
+        other => Err((
+            FIELDS[i],
+            format!("expected text, found {}", other.data_type()),
+        )),
+    };
+
+    let tx_type = match text(1)?.as_str() {
+        "DEPOSIT" => TypeTransaction::Deposit,
+        "TRANSFER" => TypeTransaction::Transfer,
+        "WITHDRAWAL" => TypeTransaction::Withdrawal,
+        value => return Err(("TX_TYPE", format!("unknown TX_TYPE {:?}", value))),
+    };
+    let status = match text(6)?.as_str() {
+        "SUCCESS" => StatusTransaction::Success,
+        "FAILURE" => StatusTransaction::Failure,
+        "PENDING" => StatusTransaction::Pending,
+        value => return Err(("STATUS", format!("unknown STATUS {:?}", value))),
+    };
+    // Пустая строка вместо NULL встречается в таблицах, импортированных из CSV
+    let currency = match get(8)? {
+        ValueRef::Null => None,
+        _ => match text(8)?.as_str() {
+            "" => None,
+            code => Some(Currency::from_code(code).map_err(|e| ("CURRENCY", e.to_string()))?),
+        },
+    };
+
+    Ok(Message {
+        tx_id: unsigned(0)?,
+        tx_type,
+        from_user_id: unsigned(2)?,
+        to_user_id: unsigned(3)?,
+        amount: integer(4)?,
+        timestamp: unsigned(5)?,
+        status,
+        description: text(7)?,
+        currency,
+    })
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SqliteYPBankRecord {
+    data: Vec<Message>,
+}
+
+impl SqliteYPBankRecord {
+    pub fn from_read<R: std::io::Read>(r: R) -> Result<Self> {
+        let data = SqliteRecordReader::new(r).collect::<Result<Vec<_>>>()?;
+        Ok(Self { data })
+    }
+
+    pub fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+}
+
+/// Читатель файла базы SQLite. Файл читается в память целиком и открывается только
+/// для чтения, результат запроса разбирается при первом обращении.
+pub struct SqliteRecordReader<R: std::io::Read> {
+    input: Option<R>,
+    source: Option<String>,
+    table: SqliteTable,
+    records: VecDeque<Result<Message>>,
+}
+
+impl<R: std::io::Read> SqliteRecordReader<R> {
+    pub fn new(r: R) -> Self {
+        Self {
+            input: Some(r),
+            source: None,
+            table: SqliteTable::default(),
+            records: VecDeque::new(),
+        }
+    }
+
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    pub fn with_table(mut self, table: SqliteTable) -> Self {
+        self.table = table;
+        self
+    }
+
+    fn read(&self, mut input: R) -> Result<Vec<Result<Message>>> {
+        let mut data = Vec::new();
+        input.read_to_end(&mut data)?;
+        let mut connection = Connection::open_in_memory()?;
+        // Пустой вход - пустая база, запрос к ней завершится ошибкой отсутствия таблицы
+        if !data.is_empty() {
+            connection.deserialize_read_exact(MAIN_DB, data.as_slice(), data.len(), true)?;
+        }
+        select(&connection, &self.table, self.source.as_deref())
+    }
+}
+
+impl<R: std::io::Read> Iterator for SqliteRecordReader<R> {
+    type Item = Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(input) = self.input.take() {
+            match self.read(input) {
+                Ok(records) => self.records = records.into(),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        self.records.pop_front()
+    }
+}
+
+/// Писатель файла базы SQLite. Записи вставляются в базу в памяти в одной транзакции,
+/// файл базы выводится целиком при завершении.
+pub struct SqliteRecordWriter<W: std::io::Write> {
+    output: W,
+    table: SqliteTable,
+    // Создается при первой записи, так как открытие базы может завершиться ошибкой
+    connection: Option<Connection>,
+}
+
+impl<W: std::io::Write> SqliteRecordWriter<W> {
+    pub fn new(output: W) -> Self {
+        Self {
+            output,
+            table: SqliteTable::default(),
+            connection: None,
+        }
+    }
+
+    pub fn with_table(mut self, table: SqliteTable) -> Self {
+        self.table = table;
+        self
+    }
+
+    fn connection(&mut self) -> Result<&Connection> {
+        if self.connection.is_none() {
+            let connection = Connection::open_in_memory()?;
+            connection.execute_batch("BEGIN")?;
+            create_table(&connection, &self.table.name)?;
+            self.connection = Some(connection);
+        }
+        Ok(self
+            .connection
+            .as_ref()
+            .expect("connection is created above"))
+    }
+
+    pub fn write_message(&mut self, message: &Message) -> Result<()> {
+        let sql = insert(&self.table.name);
+        let connection = self.connection()?;
+        let mut statement = connection.prepare_cached(&sql)?;
+        insert_message(&mut statement, message)
+    }
+
+    pub fn finish(mut self) -> Result<W> {
+        // Таблица создается и для пустого входа
+        self.connection()?.execute_batch("COMMIT")?;
+        let connection = self.connection.take().expect("connection is created above");
+        self.output.write_all(&connection.serialize(MAIN_DB)?)?;
+        self.output.flush()?;
+        Ok(self.output)
+    }
+}
+
+impl Default for SqliteYPBankRecord {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BankRecord for SqliteYPBankRecord {
+    fn write_to<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        let mut writer = SqliteRecordWriter::new(writer);
+        for message in &self.data {
+            writer.write_message(message)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+
+    fn push(&mut self, value: Message) {
+        self.data.push(value);
+    }
+
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn pop(&mut self) -> Option<Message> {
+        self.data.pop()
+    }
+
+    fn iter(&self) -> std::slice::Iter<'_, Message> {
+        self.data.iter()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convertor::test_records::pending_transfer;
+
+    fn messages() -> Vec<Message> {
+        let mut messages = (1..=3).map(pending_transfer).collect::<Vec<_>>();
+        messages[1].tx_type = TypeTransaction::Deposit;
+        messages[1].from_user_id = 0;
+        messages[1].status = StatusTransaction::Success;
+        messages[2].currency = None;
+        messages
+    }
+
+    #[test]
+    fn test_write_and_query() {
+        let connection = Connection::open_in_memory().unwrap();
+        let table = SqliteTable {
+            name: "my \"records\"".to_string(),
+            query: None,
+        };
+        write_sqlite(&connection, &table, &messages()).unwrap();
+        // Повторная запись дополняет существующую таблицу
+        write_sqlite(&connection, &table, &messages()[..1]).unwrap();
+
+        let mut expected = messages();
+        expected.push(pending_transfer(1));
+        assert_eq!(read_sqlite(&connection, &table).unwrap(), expected);
+
+        let indexes: Vec<String> = connection
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'index' ORDER BY name")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            indexes,
+            vec![
+                "my \"records\"_from_user_id",
+                "my \"records\"_timestamp",
+                "my \"records\"_to_user_id",
+                "my \"records\"_tx_id",
+            ]
+        );
+
+        // Произвольный запрос с колонками в другом порядке и регистре
+        let query = SqliteTable {
+            query: Some(
+                "SELECT DESCRIPTION, STATUS, TIMESTAMP, AMOUNT, TO_USER_ID, FROM_USER_ID, \
+                 TX_TYPE, TX_ID FROM \"my \"\"records\"\"\" \
+                 WHERE status = 'SUCCESS' OR tx_id = 3 ORDER BY tx_id DESC"
+                    .to_string(),
+            ),
+            ..table
+        };
+        let mut selected = vec![messages()[2].clone(), messages()[1].clone()];
+        selected[1].currency = None;
+        assert_eq!(read_sqlite(&connection, &query).unwrap(), selected);
+    }
+
+    #[test]
+    fn test_write_large_values() {
+        let connection = Connection::open_in_memory().unwrap();
+        let mut message = pending_transfer(1);
+        message.to_user_id = u64::MAX;
+        let error = write_sqlite(&connection, &SqliteTable::default(), &[message]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "SQLITE mapping error: TX_ID 1: TO_USER_ID 18446744073709551615 does not fit SQLite INTEGER"
+        );
+    }
+
+    #[test]
+    fn test_file_round_trip() {
+        let mut writer = SqliteRecordWriter::new(Vec::new());
+        for message in messages() {
+            writer.write_message(&message).unwrap();
+        }
+        let data = writer.finish().unwrap();
+        assert!(data.starts_with(b"SQLite format 3\0"));
+
+        let records = SqliteRecordReader::new(data.as_slice())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(records, messages());
+
+        // Пустой вход - файл с пустой таблицей
+        let data = SqliteRecordWriter::new(Vec::new()).finish().unwrap();
+        assert_eq!(SqliteRecordReader::new(data.as_slice()).count(), 0);
+    }
+
+    #[test]
+    fn test_read_errors() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE transactions (tx_id, tx_type, from_user_id, to_user_id, amount,
+                     timestamp, status, description, currency);
+                 INSERT INTO transactions VALUES
+                     ('1', 'DEPOSIT', '0', '22', '100', '1633036800000', 'SUCCESS', 'csv', ''),
+                     (2, 'REFUND', 0, 22, 100, 1633036800000, 'SUCCESS', '', NULL),
+                     (3, 'DEPOSIT', -1, 22, 100, 1633036800000, 'SUCCESS', '', NULL),
+                     (4, 'DEPOSIT', 0, 22, 1.5, 1633036800000, 'SUCCESS', '', NULL),
+                     (5, 'DEPOSIT', 0, 22, 100, 1633036800000, 'SUCCESS', NULL, NULL),
+                     (6, 'DEPOSIT', 0, 22, 100, 1633036800000, 'SUCCESS', '', 'XXX');",
+            )
+            .unwrap();
+        let data = connection.serialize(MAIN_DB).unwrap();
+        let records = SqliteRecordReader::new(&data[..])
+            .with_source("records.sqlite")
+            .collect::<Vec<_>>();
+
+        assert_eq!(records.len(), 6);
+        let record = records[0].as_ref().unwrap();
+        assert_eq!(
+            (record.tx_id, record.amount, record.currency),
+            (1, 100, None)
+        );
+        let errors = records[1..]
+            .iter()
+            .map(|record| record.as_ref().unwrap_err())
+            .inspect(|e| assert!(e.is_record_error()))
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors[0],
+            "records.sqlite: record 2: TX_TYPE: unknown TX_TYPE \"REFUND\""
+        );
+        assert_eq!(
+            errors[1],
+            "records.sqlite: record 3: FROM_USER_ID: negative value -1"
+        );
+        assert_eq!(
+            errors[2],
+            "records.sqlite: record 4: AMOUNT: expected an integer, found Real"
+        );
+        assert_eq!(
+            errors[3],
+            "records.sqlite: record 5: DESCRIPTION: value is null"
+        );
+        assert!(errors[4].starts_with("records.sqlite: record 6: CURRENCY: "));
+
+        // Отсутствие колонки и ошибка запроса завершают чтение
+        let table = SqliteTable {
+            query: Some("SELECT tx_id FROM transactions".to_string()),
+            ..SqliteTable::default()
+        };
+        let records = SqliteRecordReader::new(&data[..])
+            .with_table(table)
+            .collect::<Vec<_>>();
+        assert_eq!(records.len(), 1);
+        let error = records[0].as_ref().unwrap_err();
+        assert!(!error.is_record_error());
+        assert_eq!(
+            error.to_string(),
+            "SQLite error: Invalid column name: tx_type"
+        );
+
+        let table = SqliteTable {
+            name: "missing".to_string(),
+            query: None,
+        };
+        let error = read_sqlite(&connection, &table).unwrap_err();
+        assert_eq!(error.to_string(), "SQLite error: no such table: missing");
+
+        // Запрос, изменяющий базу, не выполняется
+        let table = SqliteTable {
+            query: Some("DELETE FROM transactions RETURNING *".to_string()),
+            ..SqliteTable::default()
+        };
+        let error = read_sqlite(&connection, &table).unwrap_err();
+        assert_eq!(error.to_string(), "SQLite error: Query is not read-only");
+        let count: i64 = connection
+            .query_row("SELECT COUNT(*) FROM transactions", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 6);
+    }
+}
@@ -0,0 +1,142 @@
+//! Группировка записей в выписки по счетам для форматов банковских выписок (camt.053, MT940, OFX, QIF, BAI2).
+//! Запись попадает в выписку каждого ненулевого счета: списанием у FROM_USER_ID
+//! и зачислением у TO_USER_ID.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::convertor::{Currency, Message};
+
+/// Строка выписки: запись и ее направление для счета выписки
+#[derive(Debug, Clone)]
+pub(crate) struct StatementEntry {
+    pub(crate) message: Message,
+    pub(crate) credit: bool,
+}
+
+impl StatementEntry {
+    /// Счет второй стороны, `0` если ее нет
+    pub(crate) fn counterparty(&self) -> u64 {
+        if self.credit {
+            self.message.from_user_id
+        } else {
+            self.message.to_user_id
+        }
+    }
+
+    /// Сумма со знаком для счета выписки, сумма записи неотрицательна
+    pub(crate) fn signed_amount(&self) -> i64 {
+        if self.credit {
+            self.message.amount
+        } else {
+            -self.message.amount
+        }
+    }
+}
+
+/// Выписка по одному счету в одной валюте
+#[derive(Debug)]
+pub(crate) struct Statement {
+    pub(crate) account: u64,
+    pub(crate) currency: Currency,
+    pub(crate) entries: Vec<StatementEntry>,
+}
+
+/// Выписки по счету и валюте. Выписка пишется целиком, поэтому писатели выписок
+/// накапливают записи в памяти и выводят их в `finish`
+#[derive(Debug, Default)]
+pub(crate) struct Statements {
+    statements: BTreeMap<(u64, String), Statement>,
+}
+
+impl Statements {
+    /// Добавление записи в выписки ее ненулевых счетов
+    pub(crate) fn push(&mut self, message: &Message, currency: Currency) {
+        for (account, credit) in [(message.from_user_id, false), (message.to_user_id, true)] {
+            if account == 0 {
+                continue;
+            }
+            self.statements
+                .entry((account, currency.code().to_string()))
+                .or_insert_with(|| Statement {
+                    account,
+                    currency,
+                    entries: Vec::new(),
+                })
+                .entries
+                .push(StatementEntry {
+                    message: message.clone(),
+                    credit,
+                });
+        }
+    }
+
+    /// Время последней записи выписок, `0` без записей
+    pub(crate) fn latest_timestamp(&self) -> u64 {
+        self.statements
+            .values()
+            .flat_map(|statement| &statement.entries)
+            .map(|entry| entry.message.timestamp)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Выписки по возрастанию счета и кода валюты, строки по времени
+    pub(crate) fn into_statements(self) -> impl Iterator<Item = Statement> {
+        self.statements.into_values().map(|mut statement| {
+            statement
+                .entries
+                .sort_by_key(|entry| entry.message.timestamp);
+            statement
+        })
+    }
+}
+
+/// TX_ID записей с двумя счетами, прочитанных из выписки одного из них.
+/// Та же запись в выписке второго счета при чтении пропускается.
+#[derive(Debug, Default)]
+pub(crate) struct Transfers {
+    tx_ids: HashSet<u64>,
+    // Строки переводов без общего TX_ID, для которых еще не прочитана строка второго счета
+    entries: HashMap<(u64, u64, i64, u64, bool), usize>,
+}
+
+impl Transfers {
+    /// Запись уже прочитана из выписки другого счета
+    pub(crate) fn is_repeated(&mut self, message: &Message) -> bool {
+        message.from_user_id != 0 && message.to_user_id != 0 && !self.tx_ids.insert(message.tx_id)
+    }
+
+    /// Строка перевода парная уже прочитанной строке другого направления с теми же счетами,
+    /// суммой и временем. Одинаковые переводы образуют столько же пар.
+    pub(crate) fn is_repeated_entry(&mut self, message: &Message, credit: bool) -> bool {
+        if message.from_user_id == 0 || message.to_user_id == 0 {
+            return false;
+        }
+        let key = |credit| {
+            (
+                message.from_user_id,
+                message.to_user_id,
+                message.amount,
+                message.timestamp,
+                credit,
+            )
+        };
+        if let Some(count) = self.entries.get_mut(&key(!credit))
+            && *count > 0
+        {
+            *count -= 1;
+            return true;
+        }
+        *self.entries.entry(key(credit)).or_default() += 1;
+        false
+    }
+}
+
+/// FROM_USER_ID и TO_USER_ID по счету выписки, счету второй стороны и направлению
+pub(crate) fn accounts(account: u64, counterparty: u64, credit: bool) -> (u64, u64) {
+    if credit {
+        (counterparty, account)
+    } else {
+        (account, counterparty)
+    }
+}
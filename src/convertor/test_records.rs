@@ -0,0 +1,63 @@
+//! Общие записи и помощники тестов форматов
+
+use crate::{
+    DataFormat, RecordReader, RecordWriter,
+    convertor::{Currency, Message, StatusTransaction, TypeTransaction},
+    error::Result,
+};
+
+/// Проведенная транзакция на 123,45 EUR с заданным типом и счетами
+pub(crate) fn transaction(tx_id: u64, tx_type: TypeTransaction, from: u64, to: u64) -> Message {
+    Message {
+        tx_id,
+        tx_type,
+        from_user_id: from,
+        to_user_id: to,
+        amount: 12345,
+        timestamp: 1633036800123,
+        status: StatusTransaction::Success,
+        description: format!("Record {}", tx_id),
+        currency: Some(Currency::from_code("EUR").unwrap()),
+    }
+}
+
+/// Перевод в ожидании с отрицательной суммой и описанием с кавычками и переводом строки
+#[cfg(any(feature = "arrow", feature = "sqlite"))]
+pub(crate) fn pending_transfer(tx_id: u64) -> Message {
+    Message {
+        amount: -12345,
+        timestamp: 1633036800123 + tx_id,
+        status: StatusTransaction::Pending,
+        description: format!("Record {}, \"quoted\"\n", tx_id),
+        ..transaction(tx_id, TypeTransaction::Transfer, 11, 22)
+    }
+}
+
+/// Записи на несколько RecordBatch: без валюты, с пустым описанием и наименьшей суммой
+#[cfg(feature = "arrow")]
+pub(crate) fn pending_transfers() -> Vec<Message> {
+    let mut messages = (1..=2500).map(pending_transfer).collect::<Vec<_>>();
+    messages[1].tx_type = TypeTransaction::Deposit;
+    messages[1].status = StatusTransaction::Success;
+    messages[1].currency = None;
+    messages[2].description = String::new();
+    messages[3].amount = i64::MIN;
+    messages
+}
+
+/// Текст записей, записанных потоковым писателем формата
+pub(crate) fn write_text(messages: &[Message], format: &DataFormat) -> String {
+    let mut writer = RecordWriter::new(Vec::new(), format);
+    for message in messages {
+        writer.write_message(message).unwrap();
+    }
+    String::from_utf8(writer.finish().unwrap()).unwrap()
+}
+
+/// Все записи и ошибки потокового читателя формата
+pub(crate) fn read(text: &str, format: &DataFormat, source: &str) -> Vec<Result<Message>> {
+    RecordReader::new(text.as_bytes(), format)
+        .unwrap()
+        .with_source(source)
+        .collect()
+}
@@ -1,11 +1,27 @@
 use std::{fmt::Display, io::BufRead};
 
 use crate::{
-    BinYPBankRecord, CsvYPBankRecord,
-    convertor::{BankRecord, Message, StatusTransaction, TypeTransaction},
-    error::{AppError, Result},
+    BinYPBankRecord, CsvYPBankRecord, DataFormat,
+    convertor::{AmountPolicy, BankRecord, Currency, Message, StatusTransaction, TypeTransaction},
+    error::{AppError, Location, ParseError, RecordError, Result},
 };
 
+/// Поля записи, каждое встречается ровно один раз
+const FIELDS: [&str; 9] = [
+    "TX_ID",
+    "TX_TYPE",
+    "FROM_USER_ID",
+    "TO_USER_ID",
+    "AMOUNT",
+    "TIMESTAMP",
+    "STATUS",
+    "DESCRIPTION",
+    "CURRENCY",
+];
+
+// Единственное необязательное поле
+const OPTIONAL_FIELD: &str = "CURRENCY";
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct TxtYPBankRecord {
     data: Vec<Message>,
@@ -13,34 +29,158 @@ pub struct TxtYPBankRecord {
 
 impl TxtYPBankRecord {
     pub fn from_read<R: std::io::Read>(r: R) -> Result<Self> {
-        let reader = std::io::BufReader::new(r);
-        let mut block = Vec::new();
-        let mut parse_data = Vec::new();
+        let data = TxtRecordReader::new(r).collect::<Result<Vec<_>>>()?;
+        Ok(Self { data })
+    }
+
+    pub fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+}
+
+/// Потоковый читатель текстового формата, возвращает записи по одной
+pub struct TxtRecordReader<R: std::io::Read> {
+    lines: std::io::Lines<std::io::BufReader<R>>,
+    finished: bool,
+    source: Option<String>,
+    // Номер последней прочитанной строки
+    line: u64,
+    // Количество прочитанных записей
+    record: u64,
+    recovery: bool,
+}
+
+impl<R: std::io::Read> TxtRecordReader<R> {
+    pub fn new(r: R) -> Self {
+        Self {
+            lines: std::io::BufReader::new(r).lines(),
+            finished: false,
+            source: None,
+            line: 0,
+            record: 0,
+            recovery: false,
+        }
+    }
+
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Режим восстановления: ошибки всех строк записи собираются в одну
+    /// [`AppError::TxtRecordError`] вместе с исходным текстом записи
+    pub fn with_recovery(mut self, recovery: bool) -> Self {
+        self.recovery = recovery;
+        self
+    }
+
+    fn parse_block(&mut self, block: Vec<(u64, String)>) -> Result<Message> {
+        self.record += 1;
+        let location = Location {
+            source: self.source.clone(),
+            record: Some(self.record),
+            ..Location::default()
+        };
+        Message::from_str(&block, &location).map_err(|mut errors| {
+            if !self.recovery {
+                return AppError::TxtParseError(Box::new(errors.remove(0)));
+            }
+            AppError::TxtRecordError(Box::new(RecordError {
+                location: Location {
+                    line: block.first().map(|(line, _)| *line),
+                    ..location
+                },
+                errors,
+                text: block.into_iter().map(|(_, l)| l + "\n").collect(),
+            }))
+        })
+    }
+}
+
+/// Потоковый писатель текстового формата, принимает записи по одной
+pub struct TxtRecordWriter<W: std::io::Write> {
+    writer: W,
+    amount_policy: AmountPolicy,
+}
+
+impl<W: std::io::Write> TxtRecordWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            amount_policy: AmountPolicy::default(),
+        }
+    }
+
+    pub fn with_amount_policy(mut self, amount_policy: AmountPolicy) -> Self {
+        self.amount_policy = amount_policy;
+        self
+    }
 
-        for line in reader.lines() {
-            match line {
-                Ok(l) => {
+    pub fn write_message(&mut self, message: &Message) -> Result<()> {
+        let message = self.amount_policy.apply(message, &DataFormat::TXT)?;
+        write!(self.writer, "{}", message)?;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl<R: std::io::Read> Iterator for TxtRecordReader<R> {
+    type Item = Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let mut block = Vec::new();
+        loop {
+            match self.lines.next() {
+                Some(Ok(l)) => {
+                    self.line += 1;
                     if l.trim().is_empty() {
-                        if !block.is_empty() {
-                            parse_data.push(Message::from_str(block)?);
-                            block = Vec::new();
+                        if is_record(&block) {
+                            return Some(self.parse_block(block));
                         }
+                        block.clear();
                     } else {
-                        block.push(l);
+                        block.push((self.line, l));
                     }
                 }
-                Err(e) => {
-                    return Err(AppError::TxtParseError(e.to_string()));
+                Some(Err(e)) => {
+                    self.finished = true;
+                    if e.kind() == std::io::ErrorKind::InvalidData {
+                        return Some(Err(AppError::TxtParseError(Box::new(ParseError {
+                            location: Location {
+                                source: self.source.clone(),
+                                line: Some(self.line + 1),
+                                record: Some(self.record + 1),
+                                ..Location::default()
+                            },
+                            message: "invalid UTF-8".to_string(),
+                        }))));
+                    }
+                    return Some(Err(AppError::IOError(e)));
+                }
+                // Последняя запись может не заканчиваться пустой строкой
+                None => {
+                    self.finished = true;
+                    if !is_record(&block) {
+                        return None;
+                    }
+                    return Some(self.parse_block(block));
                 }
             }
         }
-
-        Ok(Self { data: parse_data })
     }
+}
 
-    pub fn new() -> Self {
-        Self { data: Vec::new() }
-    }
+/// Блок из одних комментариев записью не считается
+fn is_record(block: &[(u64, String)]) -> bool {
+    block.iter().any(|(_, line)| !line.starts_with('#'))
 }
 
 impl Default for TxtYPBankRecord {
@@ -51,11 +191,11 @@ impl Default for TxtYPBankRecord {
 
 impl BankRecord for TxtYPBankRecord {
     fn write_to<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        let mut writer = TxtRecordWriter::new(writer);
         for message in &self.data {
-            let mes = message.to_string();
-            // dbg!(mes.len());
-            write!(writer, "{}", mes)?;
+            writer.write_message(message)?;
         }
+        writer.finish()?;
         Ok(())
     }
 
@@ -82,148 +222,149 @@ impl BankRecord for TxtYPBankRecord {
 
 impl From<BinYPBankRecord> for TxtYPBankRecord {
     fn from(value: BinYPBankRecord) -> Self {
-        let mut record = Self::new();
-        for mes in value.iter() {
-            record.push(Message {
-                tx_id: mes.tx_id,
-                tx_type: mes.tx_type,
-                from_user_id: mes.from_user_id,
-                to_user_id: mes.to_user_id,
-                amount: mes.amount,
-                timestamp: mes.timestamp,
-                status: mes.status,
-                description: mes.description.clone(),
-            });
+        Self {
+            data: value.iter().cloned().collect(),
         }
-        record
     }
 }
 
 impl From<CsvYPBankRecord> for TxtYPBankRecord {
     fn from(value: CsvYPBankRecord) -> Self {
-        let mut record = Self::new();
-        for mes in value.iter() {
-            record.push(Message {
-                tx_id: mes.tx_id,
-                tx_type: mes.tx_type,
-                from_user_id: mes.from_user_id,
-                to_user_id: mes.to_user_id,
-                amount: mes.amount,
-                timestamp: mes.timestamp,
-                status: mes.status,
-                description: format!("\"{}\"", mes.description.clone()),
-            });
+        Self {
+            data: value.iter().cloned().collect(),
         }
-        record
     }
 }
 
 impl Message {
-    fn from_str(lines: Vec<String>) -> Result<Self> {
-        let mut tx_id = 0;
-        let mut tx_type = TypeTransaction::Transfer;
-        let mut from_user_id = 0;
-        let mut to_user_id = 0;
-        let mut amount = 0;
-        let mut timestamp = 0;
-        let mut status = StatusTransaction::Failure;
-        let mut description = String::new();
-
-        for line in lines {
-            match line {
-                // пропускаем комментарий
-                l if l.starts_with("#") => continue,
+    /// Разбор блока строк одной записи. Разбор не прерывается на первой ошибке,
+    /// возвращаются ошибки всех строк записи, а также пропущенные обязательные поля.
+    fn from_str(
+        lines: &[(u64, String)],
+        location: &Location,
+    ) -> std::result::Result<Self, Vec<ParseError>> {
+        let mut tx_id = None;
+        let mut tx_type = None;
+        let mut from_user_id = None;
+        let mut to_user_id = None;
+        let mut amount = None;
+        let mut timestamp = None;
+        let mut status = None;
+        let mut description = None;
+        let mut currency = None;
+        let mut errors = Vec::new();
+        // Встреченные поля и номера строк, на которых они определены
+        let mut seen: Vec<(&str, u64)> = Vec::new();
+
+        for (line_number, line) in lines {
+            let error = |field: Option<&str>, message: String| ParseError {
+                location: Location {
+                    line: Some(*line_number),
+                    field: field.map(str::to_string),
+                    ..location.clone()
+                },
+                message,
+            };
+
+            // пропускаем комментарий
+            if line.starts_with('#') {
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once(':') else {
+                errors.push(error(
+                    None,
+                    format!("expected \"KEY: VALUE\", found {:?}", line),
+                ));
+                continue;
+            };
+            let value = value.strip_prefix(' ').unwrap_or(value);
+
+            if !FIELDS.contains(&key) {
+                errors.push(error(Some(key), "unknown field".to_string()));
+                continue;
+            }
+            if let Some((_, first_line)) = seen.iter().find(|(field, _)| *field == key) {
+                errors.push(error(
+                    Some(key),
+                    format!("duplicate field, first defined at line {}", first_line),
+                ));
+                continue;
+            }
+            seen.push((key, *line_number));
 
+            let result = match key {
                 // неотрицательное целое число, идентифицирующее транзакцию
-                l if l.starts_with("TX_ID") => {
-                    if let Some(num) = l.split(": ").nth(1)
-                        && let Ok(num) = num.parse::<u64>()
-                    {
-                        tx_id = num;
-                        continue;
-                    }
-                    return Err(AppError::TxtParseError(l.to_string()));
-                }
+                "TX_ID" => parse_integer(value).map(|v| tx_id = Some(v)),
 
                 //  тип транзакции
-                l if l.starts_with("TX_TYPE") => match l.split(": ").nth(1) {
-                    Some("DEPOSIT") => tx_type = TypeTransaction::Deposit,
-                    Some("TRANSFER") => tx_type = TypeTransaction::Transfer,
-                    Some("WITHDRAWAL") => tx_type = TypeTransaction::Withdrawal,
-                    _ => return Err(AppError::TxtParseError(l.to_string())),
-                },
+                "TX_TYPE" => match value {
+                    "DEPOSIT" => Ok(TypeTransaction::Deposit),
+                    "TRANSFER" => Ok(TypeTransaction::Transfer),
+                    "WITHDRAWAL" => Ok(TypeTransaction::Withdrawal),
+                    _ => Err(format!("invalid transaction type {:?}", value)),
+                }
+                .map(|v| tx_type = Some(v)),
 
                 //  неотрицательное целое число, идентифицирующее отправитель счета (0 для Deposit).
-                l if l.starts_with("FROM_USER_ID") => {
-                    if let Some(num) = l.split(": ").nth(1)
-                        && let Ok(num) = num.parse::<u64>()
-                    {
-                        from_user_id = num;
-                        continue;
-                    }
-                    return Err(AppError::TxtParseError(l.to_string()));
-                }
+                "FROM_USER_ID" => parse_integer(value).map(|v| from_user_id = Some(v)),
 
                 // неотрицательное целое число, идентифицирующее получателя счета (0 для Withdrawal)
-                l if l.starts_with("TO_USER_ID") => {
-                    if let Some(num) = l.split(": ").nth(1)
-                        && let Ok(num) = num.parse::<u64>()
-                    {
-                        to_user_id = num;
-                        continue;
-                    }
-                    return Err(AppError::TxtParseError(l.to_string()));
-                }
+                "TO_USER_ID" => parse_integer(value).map(|v| to_user_id = Some(v)),
 
                 // неотрицательное целое число, представляющее сумму в наименьшей единице валюты.
-                l if l.starts_with("AMOUNT") => {
-                    if let Some(num) = l.split(": ").nth(1)
-                        && let Ok(num) = num.parse::<u64>()
-                    {
-                        amount = num;
-                        continue;
-                    }
-
-                    return Err(AppError::TxtParseError(l.to_string()));
+                "AMOUNT" => match parse_integer::<i64>(value) {
+                    Ok(v) if v < 0 => Err(format!(
+                        "negative amount {}, TXT format allows only non-negative amounts",
+                        v
+                    )),
+                    result => result,
                 }
+                .map(|v| amount = Some(v)),
+
+                // необязательный код валюты ISO 4217
+                "CURRENCY" => value
+                    .parse::<Currency>()
+                    .map(|v| currency = Some(v))
+                    .map_err(|_| format!("unknown currency {:?}", value)),
 
                 // Unix epoch timestamp в миллисекундах
-                l if l.starts_with("TIMESTAMP") => {
-                    if let Some(num) = l.split(": ").nth(1)
-                        && let Ok(num) = num.parse::<u64>()
-                    {
-                        timestamp = num;
-                        continue;
-                    }
-                    return Err(AppError::TxtParseError(l.to_string()));
-                }
+                "TIMESTAMP" => parse_integer(value).map(|v| timestamp = Some(v)),
 
                 //состояние транзакции
-                l if l.starts_with("STATUS") => match l.split(": ").nth(1) {
-                    Some("SUCCESS") => status = StatusTransaction::Success,
-                    Some("FAILURE") => status = StatusTransaction::Failure,
-                    Some("PENDING") => status = StatusTransaction::Pending,
-                    _ => return Err(AppError::TxtParseError(l.to_string())),
-                },
+                "STATUS" => match value {
+                    "SUCCESS" => Ok(StatusTransaction::Success),
+                    "FAILURE" => Ok(StatusTransaction::Failure),
+                    "PENDING" => Ok(StatusTransaction::Pending),
+                    _ => Err(format!("invalid status {:?}", value)),
+                }
+                .map(|v| status = Some(v)),
 
                 // произвольное текстовое описание, UTF-8 в двойныхкавычках
-                l if l.starts_with("DESCRIPTION") => {
-                    if let Some(desc) = l.split(": ").nth(1) {
-                        description = desc.to_string();
-                        continue;
-                    }
-                    return Err(AppError::TxtParseError(l.to_string()));
-                }
+                "DESCRIPTION" => parse_description(value).map(|v| description = Some(v)),
 
-                // строки, которые не подошди под формат
-                l => {
-                    dbg!(l);
-                    continue;
-                }
+                _ => Err("unknown field".to_string()),
+            };
+            if let Err(message) = result {
+                errors.push(error(Some(key), message));
+            }
+        }
+
+        // Отсутствующие обязательные поля указываются на первой строке записи
+        for field in FIELDS.iter().filter(|field| **field != OPTIONAL_FIELD) {
+            if !seen.iter().any(|(seen, _)| seen == field) {
+                errors.push(ParseError {
+                    location: Location {
+                        line: lines.first().map(|(line, _)| *line),
+                        field: Some(field.to_string()),
+                        ..location.clone()
+                    },
+                    message: "missing field".to_string(),
+                });
             }
         }
 
-        Ok(Message {
+        match (
             tx_id,
             tx_type,
             from_user_id,
@@ -232,23 +373,108 @@ impl Message {
             timestamp,
             status,
             description,
-        })
+        ) {
+            (
+                Some(tx_id),
+                Some(tx_type),
+                Some(from_user_id),
+                Some(to_user_id),
+                Some(amount),
+                Some(timestamp),
+                Some(status),
+                Some(description),
+            ) if errors.is_empty() => Ok(Message {
+                tx_id,
+                tx_type,
+                from_user_id,
+                to_user_id,
+                amount,
+                timestamp,
+                status,
+                description,
+                currency,
+            }),
+            _ => Err(errors),
+        }
+    }
+}
+
+/// Разбор целочисленного значения поля
+fn parse_integer<T: std::str::FromStr>(value: &str) -> std::result::Result<T, String> {
+    value
+        .parse::<T>()
+        .map_err(|_| format!("invalid integer {:?}", value))
+}
+
+/// Описание в двойных кавычках с экранированием `\\`, `\"`, `\n`, `\r` и `\t`,
+/// чтобы перевод строки не разрывал блок записи
+fn escape_description(description: &str) -> String {
+    let mut escaped = String::with_capacity(description.len() + 2);
+    escaped.push('"');
+    for c in description.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Разбор описания в двойных кавычках с экранированием.
+/// Описание без кавычек читается как есть, без обработки `\`.
+fn parse_description(value: &str) -> std::result::Result<String, String> {
+    let Some(quoted) = value.strip_prefix('"') else {
+        return Ok(value.to_string());
+    };
+
+    let mut description = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                let rest = chars.as_str();
+                if !rest.is_empty() {
+                    return Err(format!("unexpected text after closing quote {:?}", rest));
+                }
+                return Ok(description);
+            }
+            '\\' => match chars.next() {
+                Some('\\') => description.push('\\'),
+                Some('"') => description.push('"'),
+                Some('n') => description.push('\n'),
+                Some('r') => description.push('\r'),
+                Some('t') => description.push('\t'),
+                Some(c) => return Err(format!("invalid escape sequence \"\\{}\"", c)),
+                None => break,
+            },
+            c => description.push(c),
+        }
     }
+    Err("missing closing quote".to_string())
 }
 
 impl Display for Message {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "TX_ID: {}\nTX_TYPE: {}\nFROM_USER_ID: {}\nTO_USER_ID: {}\nAMOUNT: {}\nTIMESTAMP: {}\nSTATUS: {}\nDESCRIPTION: {}\n\n",
-            self.tx_id,
-            self.tx_type,
-            self.from_user_id,
-            self.to_user_id,
-            self.amount,
+            "TX_ID: {}\nTX_TYPE: {}\nFROM_USER_ID: {}\nTO_USER_ID: {}\nAMOUNT: {}\n",
+            self.tx_id, self.tx_type, self.from_user_id, self.to_user_id, self.amount,
+        )?;
+        // CURRENCY необязательное поле, пишется только если задано
+        if let Some(currency) = &self.currency {
+            writeln!(f, "CURRENCY: {}", currency)?;
+        }
+        write!(
+            f,
+            "TIMESTAMP: {}\nSTATUS: {}\nDESCRIPTION: {}\n\n",
             self.timestamp,
             self.status,
-            self.description,
+            escape_description(&self.description),
         )
     }
 }
@@ -280,4 +506,211 @@ mod tests {
         let wr_data = TxtYPBankRecord::from_read(&mut file).unwrap();
         assert_eq!(data, wr_data);
     }
+
+    #[test]
+    fn test_read_last_record_without_blank_line() {
+        let input = "TX_ID: 1\nTX_TYPE: DEPOSIT\nFROM_USER_ID: 0\nTO_USER_ID: 2\nAMOUNT: 3\nTIMESTAMP: 4\nSTATUS: SUCCESS\nDESCRIPTION: \"a\"";
+        let data = TxtRecordReader::new(input.as_bytes())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].tx_id, 1);
+    }
+
+    #[test]
+    fn test_write_negative_amount() {
+        let message = Message {
+            tx_id: 1,
+            tx_type: TypeTransaction::Withdrawal,
+            from_user_id: 2,
+            to_user_id: 0,
+            amount: -100,
+            timestamp: 3,
+            status: StatusTransaction::Success,
+            description: String::new(),
+            currency: None,
+        };
+
+        let mut writer = TxtRecordWriter::new(Vec::new());
+        assert!(matches!(
+            writer.write_message(&message),
+            Err(AppError::NegativeAmountError { amount: -100, .. })
+        ));
+
+        let mut writer =
+            TxtRecordWriter::new(Vec::new()).with_amount_policy(AmountPolicy::Absolute);
+        writer.write_message(&message).unwrap();
+        let buf = writer.finish().unwrap();
+        let data = TxtRecordReader::new(buf.as_slice())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(data[0].amount, 100);
+    }
+
+    #[test]
+    fn test_currency() {
+        let input = "TX_ID: 1\nTX_TYPE: DEPOSIT\nFROM_USER_ID: 0\nTO_USER_ID: 2\nAMOUNT: 12345\nCURRENCY: EUR\nTIMESTAMP: 4\nSTATUS: SUCCESS\nDESCRIPTION: \"a\"\n\n";
+        let data = TxtYPBankRecord::from_read(input.as_bytes()).unwrap();
+        let message = data.iter().next().unwrap();
+        assert_eq!(message.money().unwrap().to_string(), "123.45 EUR");
+
+        let mut buf = Vec::new();
+        data.write_to(&mut buf).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), input);
+    }
+
+    #[test]
+    fn test_parse_error_location() {
+        let input = "# Record 1\nTX_ID: 1\nAMOUNT: 3\n\n# Record 2\nAMOUNT: 3\nTX_ID: 12a\n";
+        let err = TxtRecordReader::new(input.as_bytes())
+            .with_source("records.txt")
+            .nth(1)
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "records.txt:7: TX_ID: invalid integer \"12a\""
+        );
+        let AppError::TxtParseError(err) = err else {
+            panic!("unexpected error: {err:?}");
+        };
+        assert_eq!(err.location.record, Some(2));
+    }
+
+    // Поля записи кроме TX_ID и STATUS
+    const REST_FIELDS: &str = "TX_TYPE: DEPOSIT\nFROM_USER_ID: 0\nTO_USER_ID: 2\nAMOUNT: 3\nTIMESTAMP: 4\nDESCRIPTION: \"a\"\n";
+
+    #[test]
+    fn test_recovery_collects_record_errors() {
+        let input = format!(
+            "TX_ID: 1\nSTATUS: SUCCESS\n{REST_FIELDS}\nTX_ID: 12a\nSTATUS: DONE\n{REST_FIELDS}\nTX_ID: 3\nSTATUS: SUCCESS\n{REST_FIELDS}"
+        );
+
+        // Без восстановления возвращается первая ошибка записи
+        let mut reader = TxtRecordReader::new(input.as_bytes()).with_source("records.txt");
+        assert!(reader.next().unwrap().is_ok());
+        assert!(matches!(
+            reader.next(),
+            Some(Err(AppError::TxtParseError(err))) if err.location.line == Some(10)
+        ));
+
+        let results = TxtRecordReader::new(input.as_bytes())
+            .with_source("records.txt")
+            .with_recovery(true)
+            .collect::<Vec<_>>();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().tx_id, 1);
+        assert_eq!(results[2].as_ref().unwrap().tx_id, 3);
+
+        let Err(AppError::TxtRecordError(err)) = &results[1] else {
+            panic!("unexpected result: {:?}", results[1]);
+        };
+        assert_eq!(err.location.line, Some(10));
+        assert_eq!(err.location.record, Some(2));
+        assert_eq!(err.text, format!("TX_ID: 12a\nSTATUS: DONE\n{REST_FIELDS}"));
+        assert_eq!(
+            err.to_string(),
+            "records.txt:10: invalid record 2, 2 error(s)\n  records.txt:10: TX_ID: invalid integer \"12a\"\n  records.txt:11: STATUS: invalid status \"DONE\""
+        );
+    }
+
+    #[test]
+    fn test_required_and_unique_fields() {
+        let input = format!(
+            "# only comment\n\n# Record 1\nTX_ID: 1\nSTATUS: SUCCESS\nSTATUS: PENDING\nNOTE: x\nbroken line\n{REST_FIELDS}\nTX_ID: 2\n{REST_FIELDS}"
+        );
+        let results = TxtRecordReader::new(input.as_bytes())
+            .with_source("records.txt")
+            .with_recovery(true)
+            .collect::<Vec<_>>();
+        assert_eq!(results.len(), 2);
+
+        let Err(AppError::TxtRecordError(err)) = &results[0] else {
+            panic!("unexpected result: {:?}", results[0]);
+        };
+        let errors = err
+            .errors
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            [
+                "records.txt:6: STATUS: duplicate field, first defined at line 5",
+                "records.txt:7: NOTE: unknown field",
+                "records.txt:8: expected \"KEY: VALUE\", found \"broken line\"",
+            ]
+        );
+
+        let Err(AppError::TxtRecordError(err)) = &results[1] else {
+            panic!("unexpected result: {:?}", results[1]);
+        };
+        assert_eq!(err.errors.len(), 1);
+        assert_eq!(
+            err.errors[0].to_string(),
+            "records.txt:16: STATUS: missing field"
+        );
+    }
+
+    #[test]
+    fn test_description_escapes() {
+        let descriptions = [
+            "",
+            "plain",
+            "quote \" inside",
+            "\"quoted\"",
+            "back\\slash\\",
+            "line 1\nline 2",
+            "blank\n\nline",
+            "\n# not a comment\nTX_ID: 1",
+            "crlf\r\n tab\t",
+            "key: value, comma",
+            "\\\"\\n",
+            "юникод ✓",
+        ];
+        let mut writer = TxtRecordWriter::new(Vec::new());
+        for (tx_id, description) in descriptions.iter().enumerate() {
+            writer
+                .write_message(&Message {
+                    tx_id: tx_id as u64,
+                    tx_type: TypeTransaction::Deposit,
+                    from_user_id: 0,
+                    to_user_id: 2,
+                    amount: 3,
+                    timestamp: 4,
+                    status: StatusTransaction::Success,
+                    description: description.to_string(),
+                    currency: None,
+                })
+                .unwrap();
+        }
+        let buf = writer.finish().unwrap();
+
+        let data = TxtRecordReader::new(buf.as_slice())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let actual = data
+            .iter()
+            .map(|m| m.description.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(actual, descriptions);
+    }
+
+    #[test]
+    fn test_description_escape_errors() {
+        assert_eq!(parse_description(r#""a\"b\\c\n""#).unwrap(), "a\"b\\c\n");
+        assert_eq!(parse_description("unquoted \\n").unwrap(), "unquoted \\n");
+        assert_eq!(
+            parse_description(r#""a\x""#).unwrap_err(),
+            r#"invalid escape sequence "\x""#
+        );
+        assert_eq!(
+            parse_description(r#""a"b""#).unwrap_err(),
+            r#"unexpected text after closing quote "b\"""#
+        );
+        assert_eq!(
+            parse_description(r#""a\""#).unwrap_err(),
+            "missing closing quote"
+        );
+    }
 }
@@ -0,0 +1,342 @@
+use rust_xlsxwriter::{ExcelDateTime, Format, FormatAlign, IgnoreError, Workbook, Worksheet};
+
+use crate::{
+    DataFormat,
+    convertor::{BankRecord, Message},
+    error::{AppError, Result},
+};
+
+// Заголовки колонок и их ширина в символах
+const COLUMNS: [(&str, f64); 9] = [
+    ("TX_ID", 21.0),
+    ("TX_TYPE", 12.0),
+    ("FROM_USER_ID", 21.0),
+    ("TO_USER_ID", 21.0),
+    ("AMOUNT", 18.0),
+    ("TIMESTAMP", 24.0),
+    ("STATUS", 10.0),
+    ("DESCRIPTION", 40.0),
+    ("CURRENCY", 10.0),
+];
+
+// Строк на листе вместе с заголовком, следующие записи пишутся на новый лист
+const SHEET_ROWS: u32 = 1_048_576;
+
+// Число табличного процессора хранит 15 значащих цифр
+const MAX_AMOUNT: u64 = 999_999_999_999_999;
+
+// Длина текста ячейки в символах
+const MAX_TEXT: usize = 32_767;
+
+// Начало эпохи Unix как номер дня табличного процессора
+const UNIX_EPOCH_SERIAL: f64 = 25_569.0;
+const MILLIS_PER_DAY: f64 = 86_400_000.0;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct XlsxYPBankRecord {
+    data: Vec<Message>,
+}
+
+impl XlsxYPBankRecord {
+    pub fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+}
+
+/// Форматы ячеек книги
+struct Formats {
+    header: Format,
+    text: Format,
+    timestamp: Format,
+    // Формат суммы по количеству знаков дробной части валюты
+    amounts: Vec<Format>,
+}
+
+impl Default for Formats {
+    fn default() -> Self {
+        Self {
+            header: Format::new().set_bold().set_align(FormatAlign::Center),
+            text: Format::new().set_num_format("@"),
+            timestamp: Format::new().set_num_format("yyyy-mm-dd hh:mm:ss.000"),
+            amounts: (0..=4)
+                .map(|exponent| match exponent {
+                    0 => Format::new().set_num_format("#,##0"),
+                    _ => Format::new().set_num_format(format!("#,##0.{}", "0".repeat(exponent))),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Писатель книги XLSX для табличных процессоров. Счета и TX_ID пишутся текстом,
+/// чтобы не терять цифры длинных идентификаторов, суммы - числами, время - датой.
+/// Книга строится в памяти и выводится целиком при завершении.
+pub struct XlsxRecordWriter<W: std::io::Write> {
+    output: W,
+    workbook: Box<Workbook>,
+    sheet: Box<Worksheet>,
+    // Количество листов вместе с текущим
+    sheets: usize,
+    // Следующая строка текущего листа
+    row: u32,
+    formats: Box<Formats>,
+}
+
+impl<W: std::io::Write> XlsxRecordWriter<W> {
+    pub fn new(output: W) -> Self {
+        let mut writer = Self {
+            output,
+            workbook: Box::default(),
+            sheet: Box::default(),
+            sheets: 0,
+            row: 0,
+            formats: Box::default(),
+        };
+        *writer.sheet = writer.new_sheet();
+        writer
+    }
+
+    /// Лист с заголовком, закрепленной первой строкой и шириной колонок
+    fn new_sheet(&mut self) -> Worksheet {
+        self.sheets += 1;
+        self.row = 1;
+        let mut sheet = Worksheet::new();
+        let name = match self.sheets {
+            1 => "Transactions".to_string(),
+            n => format!("Transactions {}", n),
+        };
+        sheet.set_name(name).expect("sheet name is valid");
+        for (col, (name, width)) in (0..).zip(COLUMNS) {
+            sheet
+                .write_string_with_format(0, col, name, &self.formats.header)
+                .expect("header is inside the sheet");
+            sheet
+                .set_column_width(col, width)
+                .expect("column is inside the sheet");
+        }
+        sheet
+            .set_freeze_panes(1, 0)
+            .expect("row is inside the sheet");
+        sheet
+    }
+
+    /// Фильтр по заголовку и отключение предупреждения о числах, сохраненных как текст
+    fn close_sheet(&mut self) -> Result<Worksheet> {
+        let last_row = self.row - 1;
+        let last_col = COLUMNS.len() as u16 - 1;
+        let mut sheet = *std::mem::take(&mut self.sheet);
+        sheet.autofilter(0, 0, last_row, last_col)?;
+        sheet.ignore_error_range(0, 0, last_row, last_col, IgnoreError::NumberStoredAsText)?;
+        Ok(sheet)
+    }
+
+    pub fn write_message(&mut self, message: &Message) -> Result<()> {
+        let mapping_error = |reason: String| AppError::MappingError {
+            format: DataFormat::XLSX,
+            tx_id: message.tx_id,
+            reason,
+        };
+        if message.amount.unsigned_abs() > MAX_AMOUNT {
+            return Err(mapping_error(format!(
+                "AMOUNT {} has more than 15 digits, spreadsheets round it",
+                message.amount
+            )));
+        }
+        if message.description.chars().count() > MAX_TEXT {
+            return Err(mapping_error(format!(
+                "DESCRIPTION is longer than {} characters",
+                MAX_TEXT
+            )));
+        }
+        // Даты табличного процессора заканчиваются 9999 годом
+        let timestamp = ExcelDateTime::from_serial_datetime(
+            UNIX_EPOCH_SERIAL + message.timestamp as f64 / MILLIS_PER_DAY,
+        )
+        .map_err(|_| {
+            mapping_error(format!(
+                "TIMESTAMP {} is after the year 9999",
+                message.timestamp
+            ))
+        })?;
+
+        if self.row == SHEET_ROWS {
+            let sheet = self.close_sheet()?;
+            self.workbook.push_worksheet(sheet);
+            *self.sheet = self.new_sheet();
+        }
+        // Сумма в единицах валюты, без валюты - в минимальных единицах, как в записи
+        let (amount, format) = match message.currency {
+            Some(currency) => (
+                message.amount as f64 / 10f64.powi(currency.exponent() as i32),
+                &self.formats.amounts[currency.exponent() as usize],
+            ),
+            None => (message.amount as f64, &self.formats.amounts[0]),
+        };
+        let row = self.row;
+        let sheet = &mut self.sheet;
+        sheet.write_string_with_format(row, 0, message.tx_id.to_string(), &self.formats.text)?;
+        sheet.write_string(row, 1, message.tx_type.to_string())?;
+        sheet.write_string_with_format(
+            row,
+            2,
+            message.from_user_id.to_string(),
+            &self.formats.text,
+        )?;
+        sheet.write_string_with_format(
+            row,
+            3,
+            message.to_user_id.to_string(),
+            &self.formats.text,
+        )?;
+        sheet.write_number_with_format(row, 4, amount, format)?;
+        sheet.write_datetime_with_format(row, 5, timestamp, &self.formats.timestamp)?;
+        sheet.write_string(row, 6, message.status.to_string())?;
+        sheet.write_string(row, 7, &message.description)?;
+        if let Some(currency) = message.currency {
+            sheet.write_string(row, 8, currency.code())?;
+        }
+        self.row += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W> {
+        let sheet = self.close_sheet()?;
+        self.workbook.push_worksheet(sheet);
+        let data = self.workbook.save_to_buffer()?;
+        self.output.write_all(&data)?;
+        self.output.flush()?;
+        Ok(self.output)
+    }
+}
+
+impl Default for XlsxYPBankRecord {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BankRecord for XlsxYPBankRecord {
+    fn write_to<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        let mut writer = XlsxRecordWriter::new(writer);
+        for message in &self.data {
+            writer.write_message(message)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+
+    fn push(&mut self, value: Message) {
+        self.data.push(value);
+    }
+
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn pop(&mut self) -> Option<Message> {
+        self.data.pop()
+    }
+
+    fn iter(&self) -> std::slice::Iter<'_, Message> {
+        self.data.iter()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use super::*;
+    use crate::convertor::{
+        Currency, StatusTransaction, TypeTransaction, test_records::transaction,
+    };
+
+    fn message(tx_id: u64) -> Message {
+        Message {
+            amount: -12345,
+            status: StatusTransaction::Pending,
+            description: "=SUM(A1:A2)".to_string(),
+            ..transaction(tx_id, TypeTransaction::Transfer, 9223372036854775807, 22)
+        }
+    }
+
+    fn write(messages: &[Message]) -> Vec<u8> {
+        let mut writer = XlsxRecordWriter::new(Vec::new());
+        for message in messages {
+            writer.write_message(message).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    fn part(data: &[u8], name: &str) -> String {
+        let mut archive = zip::ZipArchive::new(Cursor::new(data)).unwrap();
+        let mut text = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        text
+    }
+
+    #[test]
+    fn test_write_cells() {
+        let mut deposit = message(2);
+        deposit.tx_type = TypeTransaction::Deposit;
+        deposit.amount = 500;
+        deposit.currency = Some(Currency::from_code("JPY").unwrap());
+        let mut without_currency = message(3);
+        without_currency.currency = None;
+        let data = write(&[message(1), deposit, without_currency]);
+
+        let sheet = part(&data, "xl/worksheets/sheet1.xml");
+        // Заголовок закреплен и содержит фильтр по всем строкам
+        assert!(sheet.contains(
+            r#"<pane ySplit="1" topLeftCell="A2" activePane="bottomLeft" state="frozen"/>"#
+        ));
+        assert!(sheet.contains(r#"<autoFilter ref="A1:I4"/>"#));
+        // Счета - текст, суммы - числа в единицах валюты, время - дата
+        assert!(sheet.contains(r#"<c r="C2" s="2" t="s">"#));
+        assert!(sheet.contains(r#"<c r="E2" s="3"><v>-123.45</v></c>"#));
+        assert!(sheet.contains(r#"<c r="E3" s="5"><v>500</v></c>"#));
+        assert!(sheet.contains(r#"<c r="E4" s="5"><v>-12345</v></c>"#));
+        assert!(sheet.contains(r#"<c r="F2" s="4"><v>44469.8888903125"#));
+        assert!(!sheet.contains(r#"<c r="I4""#));
+        assert!(!sheet.contains("<f>"));
+
+        let strings = part(&data, "xl/sharedStrings.xml");
+        assert!(strings.contains("<t>9223372036854775807</t>"));
+        assert!(strings.contains("<t>=SUM(A1:A2)</t>"));
+        let styles = part(&data, "xl/styles.xml");
+        assert!(styles.contains(r#"formatCode="yyyy-mm-dd hh:mm:ss.000""#));
+        assert!(styles.contains(r##"formatCode="#,##0.00""##));
+        assert!(part(&data, "xl/workbook.xml").contains(r#"<sheet name="Transactions""#));
+    }
+
+    #[test]
+    fn test_write_empty() {
+        let sheet = part(&write(&[]), "xl/worksheets/sheet1.xml");
+        assert!(sheet.contains(r#"<autoFilter ref="A1:I1"/>"#));
+    }
+
+    #[test]
+    fn test_mapping_errors() {
+        let mut writer = XlsxRecordWriter::new(Vec::new());
+        let mut message = message(1);
+        message.amount = i64::MIN;
+        assert_eq!(
+            writer.write_message(&message).unwrap_err().to_string(),
+            "XLSX mapping error: TX_ID 1: AMOUNT -9223372036854775808 has more than 15 digits, spreadsheets round it"
+        );
+        message.amount = 1;
+        message.timestamp = 253402300800000;
+        assert_eq!(
+            writer.write_message(&message).unwrap_err().to_string(),
+            "XLSX mapping error: TX_ID 1: TIMESTAMP 253402300800000 is after the year 9999"
+        );
+    }
+}
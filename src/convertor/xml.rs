@@ -0,0 +1,40 @@
+//! Общие функции форматов на основе XML (camt.053, OFX)
+
+use std::io::{BufReader, Read};
+
+use quick_xml::events::BytesStart;
+
+/// Читатель XML. quick_xml::Reader содержит буфер и настройки разбора, поэтому хранится в куче
+pub(crate) type XmlReader<R> = Box<quick_xml::Reader<BufReader<R>>>;
+
+/// Читатель XML, пустые элементы `<a/>` разворачиваются в пару `<a></a>`
+pub(crate) fn xml_reader<R: Read>(r: R) -> XmlReader<R> {
+    let mut reader = quick_xml::Reader::from_reader(BufReader::new(r));
+    reader.config_mut().expand_empty_elements = true;
+    Box::new(reader)
+}
+
+/// Экранирование текста элемента XML.
+/// `\r` записывается ссылкой на символ, иначе разборщик XML заменит `\r\n` на `\n`.
+/// Управляющие символы кроме `\t`, `\n` и `\r` в XML 1.0 недопустимы и возвращаются как ошибка.
+pub(crate) fn escape_text(text: &str) -> Result<String, char> {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '\r' => escaped.push_str("&#13;"),
+            '\t' | '\n' => escaped.push(c),
+            '\u{FFFE}' | '\u{FFFF}' => return Err(c),
+            c if c < ' ' => return Err(c),
+            c => escaped.push(c),
+        }
+    }
+    Ok(escaped)
+}
+
+/// Имя элемента без префикса пространства имен
+pub(crate) fn local_name(element: &BytesStart) -> String {
+    String::from_utf8_lossy(element.local_name().as_ref()).into_owned()
+}
@@ -0,0 +1,198 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use crate::{
+    Message,
+    error::{AppError, Result},
+};
+
+/// Повторяющийся TX_ID и номера всех записей с ним, начиная с 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Duplicate {
+    /// Повторяющийся TX_ID
+    pub tx_id: u64,
+    /// Порядковые номера записей с этим TX_ID
+    pub records: Vec<u64>,
+}
+
+impl Display for Duplicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let records = self
+            .records
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        write!(f, "TX_ID {} at records {}", self.tx_id, records.join(", "))
+    }
+}
+
+/// Правило обработки повторяющихся TX_ID
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Без проверки
+    #[default]
+    Allow,
+    /// Отбросить все записи с повторяющимся TX_ID
+    Reject,
+    /// Оставить первую запись с TX_ID
+    KeepFirst,
+    /// Оставить последнюю запись с TX_ID
+    KeepLast,
+    /// Завершиться ошибкой
+    Fail,
+}
+
+impl DuplicatePolicy {
+    /// Применение правила к набору записей, возвращает оставшиеся записи и найденные повторы
+    pub(crate) fn apply(self, messages: Vec<Message>) -> Result<(Vec<Message>, Vec<Duplicate>)> {
+        if self == DuplicatePolicy::Allow {
+            return Ok((messages, Vec::new()));
+        }
+
+        let duplicates = find_duplicates(&messages);
+        if self == DuplicatePolicy::Fail && !duplicates.is_empty() {
+            return Err(AppError::DuplicateTxIdError(duplicates));
+        }
+
+        let dropped = duplicates
+            .iter()
+            .flat_map(|duplicate| {
+                let records = duplicate.records.as_slice();
+                match self {
+                    DuplicatePolicy::Reject => records,
+                    DuplicatePolicy::KeepFirst => &records[1..],
+                    DuplicatePolicy::KeepLast => &records[..records.len() - 1],
+                    DuplicatePolicy::Allow | DuplicatePolicy::Fail => &[],
+                }
+            })
+            .copied()
+            .collect::<HashSet<_>>();
+
+        let messages = messages
+            .into_iter()
+            .zip(1..)
+            .filter(|(_, record)| !dropped.contains(record))
+            .map(|(message, _)| message)
+            .collect();
+        Ok((messages, duplicates))
+    }
+
+    /// Правило можно применить при потоковой конвертации, не накапливая записи
+    pub(crate) fn is_streaming(self) -> bool {
+        matches!(
+            self,
+            DuplicatePolicy::Allow | DuplicatePolicy::KeepFirst | DuplicatePolicy::Fail
+        )
+    }
+}
+
+/// Номера записей для каждого TX_ID в порядке первого появления
+#[derive(Debug, Default)]
+pub(crate) struct Occurrences {
+    index: HashMap<u64, usize>,
+    records: Vec<(u64, Vec<u64>)>,
+}
+
+impl Occurrences {
+    /// Добавление записи, возвращает `true`, если TX_ID встретился впервые
+    pub(crate) fn insert(&mut self, tx_id: u64, record: u64) -> bool {
+        match self.index.get(&tx_id) {
+            Some(&i) => {
+                self.records[i].1.push(record);
+                false
+            }
+            None => {
+                self.index.insert(tx_id, self.records.len());
+                self.records.push((tx_id, vec![record]));
+                true
+            }
+        }
+    }
+
+    /// TX_ID, встретившиеся больше одного раза
+    pub(crate) fn duplicates(&self) -> Vec<Duplicate> {
+        self.records
+            .iter()
+            .filter(|(_, records)| records.len() > 1)
+            .map(|(tx_id, records)| Duplicate {
+                tx_id: *tx_id,
+                records: records.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Поиск повторяющихся TX_ID, записи нумеруются с 1 в порядке итерации
+pub fn find_duplicates<'a>(messages: impl IntoIterator<Item = &'a Message>) -> Vec<Duplicate> {
+    let mut occurrences = Occurrences::default();
+    for (message, record) in messages.into_iter().zip(1..) {
+        occurrences.insert(message.tx_id, record);
+    }
+    occurrences.duplicates()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{StatusTransaction, TypeTransaction};
+
+    fn messages(tx_ids: &[u64]) -> Vec<Message> {
+        tx_ids
+            .iter()
+            .zip(0..)
+            .map(|(&tx_id, timestamp)| Message {
+                tx_id,
+                tx_type: TypeTransaction::Deposit,
+                from_user_id: 0,
+                to_user_id: 2,
+                amount: 100,
+                timestamp,
+                status: StatusTransaction::Success,
+                description: String::new(),
+                currency: None,
+            })
+            .collect()
+    }
+
+    // TX_ID и TIMESTAMP оставшихся записей, TIMESTAMP равен индексу записи во входе
+    fn kept(policy: DuplicatePolicy) -> Vec<(u64, u64)> {
+        let (messages, _) = policy.apply(messages(&[1, 2, 1, 3, 2, 1])).unwrap();
+        messages.iter().map(|m| (m.tx_id, m.timestamp)).collect()
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let duplicates = find_duplicates(&messages(&[1, 2, 1, 3, 2, 1]));
+        assert_eq!(
+            duplicates,
+            [
+                Duplicate {
+                    tx_id: 1,
+                    records: vec![1, 3, 6]
+                },
+                Duplicate {
+                    tx_id: 2,
+                    records: vec![2, 5]
+                },
+            ]
+        );
+        assert_eq!(duplicates[0].to_string(), "TX_ID 1 at records 1, 3, 6");
+        assert!(find_duplicates(&messages(&[1, 2, 3])).is_empty());
+    }
+
+    #[test]
+    fn test_policies() {
+        assert_eq!(kept(DuplicatePolicy::Allow).len(), 6);
+        assert_eq!(kept(DuplicatePolicy::Reject), [(3, 3)]);
+        assert_eq!(kept(DuplicatePolicy::KeepFirst), [(1, 0), (2, 1), (3, 3)]);
+        assert_eq!(kept(DuplicatePolicy::KeepLast), [(3, 3), (2, 4), (1, 5)]);
+
+        let err = DuplicatePolicy::Fail
+            .apply(messages(&[1, 2, 1]))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Duplicate TX_ID: TX_ID 1 at records 1, 3");
+        assert!(DuplicatePolicy::Fail.apply(messages(&[1, 2])).is_ok());
+    }
+}
@@ -1,3 +1,5 @@
+use std::fmt::Display;
+
 use thiserror::Error;
 
 /// Result type alias
@@ -10,35 +12,331 @@ pub enum AppError {
     #[error("I/O error: {0}")]
     IOError(#[from] std::io::Error),
     /// TXT parse error
-    #[error("TXT parse error: {0}")]
-    TxtParseError(String),
+    #[error("{0}")]
+    TxtParseError(Box<ParseError>),
+    /// All errors of one TXT record, collected in recovery mode
+    #[error("{0}")]
+    TxtRecordError(Box<RecordError>),
     /// CSV parse error
-    #[error("CSV parse error: {0}")]
-    CsvParseError(#[from] csv::Error),
+    #[error("{0}")]
+    CsvParseError(Box<ParseError>),
+    /// CSV write error
+    #[error("CSV error: {0}")]
+    CsvError(#[from] csv::Error),
     /// Bin parse error
-    #[error("Bin parse error: {0}")]
-    BinParseError(#[from] BinParseError),
+    #[error("{0}")]
+    BinParseError(Box<BinParseError>),
     /// Bin parse error array
     #[error("Bin parse array error: {0}")]
     BinArrayError(#[from] std::array::TryFromSliceError),
     /// Bin parse error UTF-8
     #[error("Bin parse UTF-8 error: {0}")]
     BinUtf8Error(#[from] std::string::FromUtf8Error),
+    /// JSON or NDJSON record error, reading goes on with the next record
+    #[error("{0}")]
+    JsonParseError(Box<ParseError>),
+    /// JSON array structure error, the next record cannot be found
+    #[error("{0}")]
+    JsonSyntaxError(Box<ParseError>),
+    /// JSON write error
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+    /// camt.053 entry error, reading goes on with the next entry
+    #[error("{0}")]
+    CamtParseError(Box<ParseError>),
+    /// Record that cannot be represented in the output format
+    #[error("{format} mapping error: TX_ID {tx_id}: {reason}")]
+    MappingError {
+        /// Output format
+        format: crate::DataFormat,
+        /// TX_ID of the record
+        tx_id: u64,
+        /// What cannot be mapped
+        reason: String,
+    },
+    /// MT940 statement line error, reading goes on with the next `:61:` line
+    #[error("{0}")]
+    Mt940ParseError(Box<ParseError>),
+    /// OFX transaction error, reading goes on with the next `STMTTRN`
+    #[error("{0}")]
+    OfxParseError(Box<ParseError>),
+    /// QIF transaction error, reading goes on with the next transaction
+    #[error("{0}")]
+    QifParseError(Box<ParseError>),
+    /// BAI2 transaction detail error, reading goes on with the next `16` record
+    #[error("{0}")]
+    Bai2ParseError(Box<ParseError>),
+    /// BAI2 record order or control total error, the rest of the file cannot be trusted
+    #[error("{0}")]
+    Bai2StructureError(Box<ParseError>),
+    /// Fixed-width record error, reading goes on with the next line
+    #[error("{0}")]
+    FixedWidthParseError(Box<ParseError>),
+    /// Invalid fixed-width layout definition
+    #[error("Fixed-width layout error: {0}")]
+    LayoutError(String),
+    /// Arrow IPC or RecordBatch schema error, reading cannot go on
+    #[cfg(feature = "arrow")]
+    #[error("Arrow error: {0}")]
+    ArrowError(#[from] arrow_schema::ArrowError),
+    /// Arrow row that is not a valid record, reading goes on with the next row
+    #[cfg(feature = "arrow")]
+    #[error("{0}")]
+    ArrowRecordError(Box<ParseError>),
+    /// Parquet file or schema error, reading cannot go on
+    #[cfg(feature = "parquet")]
+    #[error("{0}")]
+    ParquetError(#[from] parquet::errors::ParquetError),
+    /// Parquet row that is not a valid record, reading goes on with the next row
+    #[cfg(feature = "parquet")]
+    #[error("{0}")]
+    ParquetRecordError(Box<ParseError>),
+    /// SQLite database or query error, reading cannot go on
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    SqliteError(#[from] rusqlite::Error),
+    /// SQLite row that is not a valid record, reading goes on with the next row
+    #[cfg(feature = "sqlite")]
+    #[error("{0}")]
+    SqliteRecordError(Box<ParseError>),
+    /// XLSX workbook error
+    #[cfg(feature = "xlsx")]
+    #[error("XLSX error: {0}")]
+    XlsxError(#[from] rust_xlsxwriter::XlsxError),
+    /// Format that can only be written
+    #[error("{0} format cannot be read")]
+    UnreadableFormatError(crate::DataFormat),
+    /// Malformed XML, the next record cannot be found
+    #[error("{0}")]
+    XmlError(Box<ParseError>),
+    /// Money or currency error
+    #[error("Money error: {0}")]
+    MoneyError(String),
+    /// Duplicate TX_ID with the fail policy
+    #[error(
+        "Duplicate TX_ID: {}",
+        .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+    )]
+    DuplicateTxIdError(Vec<crate::Duplicate>),
+    /// Negative amount in a format that allows only non-negative amounts
+    #[error(
+        "Negative amount error: TX_ID {tx_id} has AMOUNT {amount}, {format} format allows only non-negative amounts"
+    )]
+    NegativeAmountError {
+        /// TX_ID of the record
+        tx_id: u64,
+        /// AMOUNT of the record
+        amount: i64,
+        /// Format that cannot hold the amount
+        format: crate::DataFormat,
+    },
 }
 
-/// Bin parse error type
+/// Bin parse error type.
+///
+/// `location.byte` is the absolute offset of the record header (`MAGIC`),
+/// `location.record` is the record ordinal and `location.field` is the field that failed.
 #[derive(Debug, Error)]
 pub enum BinParseError {
     /// Bin parse magic number error
-    #[error("Bin parse magic number error: {0}")]
-    MagicNumberError(String),
-    /// Bin parse version error
-    #[error("Bin parse transaction error: {0}")]
-    TransactionError(String),
+    #[error("{location}: invalid magic number {bytes:02X?}, expected \"YPBN\"")]
+    MagicNumberError {
+        /// Record location
+        location: Location,
+        /// Bytes read instead of `MAGIC`
+        bytes: Vec<u8>,
+    },
+    /// Bin parse transaction error, invalid TX_TYPE or STATUS
+    #[error("{location}: invalid value {bytes:02X?}")]
+    TransactionError {
+        /// Record location
+        location: Location,
+        /// Raw field bytes
+        bytes: Vec<u8>,
+    },
     /// Bin parse description error
-    #[error("Bin parse description error: {0}")]
-    DescriptionError(String),
+    #[error("{location}: {reason} {bytes:02X?}")]
+    DescriptionError {
+        /// Record location
+        location: Location,
+        /// What is wrong with the description
+        reason: String,
+        /// Raw field bytes
+        bytes: Vec<u8>,
+    },
     /// Bin parse message size error
-    #[error("Bin parse message size error: {0}")]
-    MessageSizeError(String),
+    #[error("{location}: invalid record size {size}, expected {min}..={max} bytes {bytes:02X?}")]
+    MessageSizeError {
+        /// Record location
+        location: Location,
+        /// Record size from `RECORD_SIZE`
+        size: usize,
+        /// Minimum record size
+        min: usize,
+        /// Maximum record size
+        max: usize,
+        /// Raw `RECORD_SIZE` bytes
+        bytes: Vec<u8>,
+    },
+    /// Bin parse record extension error
+    #[error("{location}: {reason} {bytes:02X?}")]
+    ExtensionError {
+        /// Record location
+        location: Location,
+        /// What is wrong with the extension
+        reason: String,
+        /// Raw extension bytes
+        bytes: Vec<u8>,
+    },
+    /// File ends in the middle of a record
+    #[error("{location}: truncated record, expected {expected} bytes, found {}", bytes.len())]
+    TruncatedRecordError {
+        /// Record location
+        location: Location,
+        /// Expected number of bytes
+        expected: usize,
+        /// Bytes read before the end of file
+        bytes: Vec<u8>,
+    },
+    /// Corrupted bytes skipped in recovery mode up to the next `MAGIC` or the end of file
+    #[error("{cause}; skipped bytes {}..{end}", cause.location().byte.unwrap_or_default())]
+    SkippedBytes {
+        /// Error that broke the record
+        cause: Box<BinParseError>,
+        /// Offset of the next record header or the end of file, exclusive
+        end: u64,
+    },
+}
+
+impl BinParseError {
+    /// Record location
+    pub fn location(&self) -> &Location {
+        match self {
+            BinParseError::MagicNumberError { location, .. }
+            | BinParseError::TransactionError { location, .. }
+            | BinParseError::DescriptionError { location, .. }
+            | BinParseError::MessageSizeError { location, .. }
+            | BinParseError::ExtensionError { location, .. }
+            | BinParseError::TruncatedRecordError { location, .. } => location,
+            BinParseError::SkippedBytes { cause, .. } => cause.location(),
+        }
+    }
+
+    /// Raw bytes that failed to parse
+    pub fn bytes(&self) -> &[u8] {
+        match self {
+            BinParseError::MagicNumberError { bytes, .. }
+            | BinParseError::TransactionError { bytes, .. }
+            | BinParseError::DescriptionError { bytes, .. }
+            | BinParseError::MessageSizeError { bytes, .. }
+            | BinParseError::ExtensionError { bytes, .. }
+            | BinParseError::TruncatedRecordError { bytes, .. } => bytes,
+            BinParseError::SkippedBytes { cause, .. } => cause.bytes(),
+        }
+    }
+}
+
+impl AppError {
+    /// The error concerns a single record and reading can go on with the next one
+    pub fn is_record_error(&self) -> bool {
+        match self {
+            AppError::TxtParseError(_)
+            | AppError::TxtRecordError(_)
+            | AppError::CsvParseError(_)
+            | AppError::JsonParseError(_)
+            | AppError::CamtParseError(_)
+            | AppError::Mt940ParseError(_)
+            | AppError::OfxParseError(_)
+            | AppError::QifParseError(_)
+            | AppError::Bai2ParseError(_)
+            | AppError::FixedWidthParseError(_)
+            | AppError::BinParseError(_) => true,
+            #[cfg(feature = "arrow")]
+            AppError::ArrowRecordError(_) => true,
+            #[cfg(feature = "parquet")]
+            AppError::ParquetRecordError(_) => true,
+            #[cfg(feature = "sqlite")]
+            AppError::SqliteRecordError(_) => true,
+            _ => false,
+        }
+    }
+}
+
+impl From<BinParseError> for AppError {
+    fn from(value: BinParseError) -> Self {
+        AppError::BinParseError(Box::new(value))
+    }
+}
+
+/// Location of a parse error in the source
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Location {
+    /// Source name, usually the file name
+    pub source: Option<String>,
+    /// Line number, starting from 1
+    pub line: Option<u64>,
+    /// Record ordinal, starting from 1
+    pub record: Option<u64>,
+    /// Field name
+    pub field: Option<String>,
+    /// Byte offset from the start of the source
+    pub byte: Option<u64>,
 }
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source.as_deref().unwrap_or("<input>"))?;
+        // Номер строки достаточен для поиска, иначе выводим номер записи и смещение
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+        } else {
+            if let Some(record) = self.record {
+                write!(f, ": record {}", record)?;
+            }
+            if let Some(byte) = self.byte {
+                write!(f, ": byte {}", byte)?;
+            }
+        }
+        if let Some(field) = &self.field {
+            write!(f, ": {}", field)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse error with its location, rendered as `records.txt:1043: TX_ID: invalid integer "12a"`
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("{location}: {message}")]
+pub struct ParseError {
+    /// Where the error occurred
+    pub location: Location,
+    /// What went wrong
+    pub message: String,
+}
+
+/// All errors of one rejected record together with its raw text for quarantine
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordError {
+    /// Record location, `line` is the first line of the record
+    pub location: Location,
+    /// Errors of individual lines, at least one
+    pub errors: Vec<ParseError>,
+    /// Raw record text, each line ends with `\n`
+    pub text: String,
+}
+
+impl Display for RecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: invalid record", self.location)?;
+        if let Some(record) = self.location.record {
+            write!(f, " {}", record)?;
+        }
+        write!(f, ", {} error(s)", self.errors.len())?;
+        for error in &self.errors {
+            write!(f, "\n  {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RecordError {}
@@ -1,26 +1,80 @@
 #![deny(unreachable_pub)]
 #![warn(missing_docs)]
 
-//! Модуль конвертации записей банковсковских транзакций, в нем реализованы 3 типа файла:
+//! Модуль конвертации записей банковсковских транзакций, в нем реализованы 16 типов файла:
 //! 1. Текстовый формат
 //! 2. Бинарный формат
 //! 3. CSV формат
+//! 4. JSON массив
+//! 5. NDJSON, по одному JSON объекту в строке
+//! 6. Банковская выписка ISO 20022 camt.053 (XML)
+//! 7. Выписка SWIFT MT940
+//! 8. Выписка OFX 2.x (XML)
+//! 9. Экспорт QIF для программ учета личных финансов
+//! 10. Выписка BAI2 для казначейских систем
+//! 11. Записи фиксированной ширины с настраиваемой раскладкой колонок
+//! 12. Колоночный формат Apache Parquet, включается feature `parquet`
+//! 13. Файл Arrow IPC, включается feature `arrow`
+//! 14. Поток Arrow IPC, включается feature `arrow`
+//! 15. Файл базы SQLite, включается feature `sqlite`
+//! 16. Книга XLSX для табличных процессоров, только запись, включается feature `xlsx`
+//!
+//! С feature `arrow` записи также преобразуются в Arrow `RecordBatch` и обратно без файлов.
+//! С feature `sqlite` записи пишутся в таблицу открытой базы SQLite и читаются из таблицы
+//! или результата произвольного `SELECT`.
 //!
 //! Реализовано преобразование каждого типа в другой.
 
 /// Модуль ошибок
 pub mod error;
+/// Модуль проверки записей на соответствие правилам спецификаций, которые не проверяются
+/// при разборе форматов: согласованность типа транзакции и счетов, ненулевая сумма и т.п.
+pub mod validator;
 use enum_display::EnumDisplay;
-use error::Result;
+use error::{AppError, Result};
 
 mod convertor;
-pub use convertor::Message;
-use convertor::{BinYPBankRecord, CsvYPBankRecord, TxtYPBankRecord};
+mod duplicates;
+#[cfg(feature = "arrow")]
+pub use arrow_array::RecordBatch;
+#[cfg(feature = "parquet")]
+pub use convertor::RowGroupSize;
+pub use convertor::{
+    Alignment, AmountPolicy, Currency, FixedWidthField, FixedWidthLayout, Message, Money,
+    RecordField, StatusTransaction, TxIdStrategy, TypeTransaction,
+};
+#[cfg(feature = "arrow")]
+use convertor::{ArrowRecordReader, ArrowRecordWriter, ArrowYPBankRecord, IpcFormat};
+use convertor::{
+    Bai2RecordReader, Bai2RecordWriter, Bai2YPBankRecord, BinRecordReader, BinRecordWriter,
+    BinYPBankRecord, Camt053RecordReader, Camt053RecordWriter, Camt053YPBankRecord,
+    CsvRecordReader, CsvRecordWriter, CsvYPBankRecord, FixedWidthRecordReader,
+    FixedWidthRecordWriter, FixedWidthYPBankRecord, JsonRecordReader, JsonRecordWriter,
+    JsonYPBankRecord, Mt940RecordReader, Mt940RecordWriter, Mt940YPBankRecord, NdjsonRecordReader,
+    NdjsonRecordWriter, NdjsonYPBankRecord, OfxRecordReader, OfxRecordWriter, OfxYPBankRecord,
+    QifRecordReader, QifRecordWriter, QifYPBankRecord, TxtRecordReader, TxtRecordWriter,
+    TxtYPBankRecord,
+};
+#[cfg(feature = "parquet")]
+use convertor::{ParquetRecordReader, ParquetRecordWriter, ParquetYPBankRecord};
+#[cfg(feature = "sqlite")]
+use convertor::{SqliteRecordReader, SqliteRecordWriter, SqliteYPBankRecord};
+#[cfg(feature = "sqlite")]
+pub use convertor::{SqliteTable, read_sqlite, write_sqlite};
+#[cfg(feature = "xlsx")]
+use convertor::{XlsxRecordWriter, XlsxYPBankRecord};
+#[cfg(feature = "arrow")]
+pub use convertor::{from_record_batch, record_batch_schema, to_record_batch};
+use duplicates::Occurrences;
+pub use duplicates::{Duplicate, DuplicatePolicy, find_duplicates};
+#[cfg(feature = "sqlite")]
+pub use rusqlite::Connection;
 
 use crate::convertor::BankRecord;
 
-/// Форматы данных
-#[derive(Debug, EnumDisplay, Clone, Copy)]
+/// Форматы данных. Набор зависит от включенных features и пополняется
+#[derive(Debug, EnumDisplay, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum DataFormat {
     /// Текстовый формат
     TXT,
@@ -28,6 +82,53 @@ pub enum DataFormat {
     BIN,
     /// CSV формат
     CSV,
+    /// JSON массив
+    JSON,
+    /// NDJSON, по одному JSON объекту в строке
+    NDJSON,
+    /// Выписка ISO 20022 camt.053
+    CAMT053,
+    /// Выписка SWIFT MT940
+    MT940,
+    /// Выписка OFX 2.x
+    OFX,
+    /// Экспорт QIF
+    QIF,
+    /// Выписка BAI2
+    BAI2,
+    /// Записи фиксированной ширины
+    FIXEDWIDTH,
+    /// Apache Parquet
+    #[cfg(feature = "parquet")]
+    PARQUET,
+    /// Файл Arrow IPC
+    #[cfg(feature = "arrow")]
+    ARROW,
+    /// Поток Arrow IPC
+    #[cfg(feature = "arrow")]
+    ARROWSTREAM,
+    /// Файл базы SQLite
+    #[cfg(feature = "sqlite")]
+    SQLITE,
+    /// Книга XLSX, только запись
+    #[cfg(feature = "xlsx")]
+    XLSX,
+}
+
+impl DataFormat {
+    /// Формат требует валюту записи или пишет сумму в единицах валюты
+    fn uses_default_currency(&self) -> bool {
+        match self {
+            DataFormat::CAMT053
+            | DataFormat::MT940
+            | DataFormat::OFX
+            | DataFormat::QIF
+            | DataFormat::BAI2 => true,
+            #[cfg(feature = "xlsx")]
+            DataFormat::XLSX => true,
+            _ => false,
+        }
+    }
 }
 
 /// Тип записи банковского счета
@@ -39,10 +140,627 @@ pub enum BankRecordConvertor {
     BIN(BinYPBankRecord),
     /// CSV формат
     CSV(CsvYPBankRecord),
+    /// JSON массив
+    JSON(JsonYPBankRecord),
+    /// NDJSON формат
+    NDJSON(NdjsonYPBankRecord),
+    /// Выписка camt.053
+    CAMT053(Camt053YPBankRecord),
+    /// Выписка MT940
+    MT940(Mt940YPBankRecord),
+    /// Выписка OFX
+    OFX(OfxYPBankRecord),
+    /// Экспорт QIF
+    QIF(QifYPBankRecord),
+    /// Выписка BAI2
+    BAI2(Bai2YPBankRecord),
+    /// Записи фиксированной ширины
+    FIXEDWIDTH(FixedWidthYPBankRecord),
+    /// Apache Parquet
+    #[cfg(feature = "parquet")]
+    PARQUET(ParquetYPBankRecord),
+    /// Файл Arrow IPC
+    #[cfg(feature = "arrow")]
+    ARROW(ArrowYPBankRecord),
+    /// Поток Arrow IPC
+    #[cfg(feature = "arrow")]
+    ARROWSTREAM(ArrowYPBankRecord),
+    /// Файл базы SQLite
+    #[cfg(feature = "sqlite")]
+    SQLITE(SqliteYPBankRecord),
+    /// Книга XLSX
+    #[cfg(feature = "xlsx")]
+    XLSX(XlsxYPBankRecord),
+}
+
+/// Потоковый читатель записей, возвращает записи по одной без загрузки всего файла в память
+pub enum RecordReader<R: std::io::Read> {
+    /// Текстовый формат
+    TXT(TxtRecordReader<R>),
+    /// Бинарный формат
+    BIN(BinRecordReader<R>),
+    /// CSV формат
+    CSV(CsvRecordReader<R>),
+    /// JSON массив
+    JSON(JsonRecordReader<R>),
+    /// NDJSON формат
+    NDJSON(NdjsonRecordReader<R>),
+    /// Выписка camt.053
+    CAMT053(Camt053RecordReader<R>),
+    /// Выписка MT940
+    MT940(Mt940RecordReader<R>),
+    /// Выписка OFX
+    OFX(OfxRecordReader<R>),
+    /// Экспорт QIF
+    QIF(QifRecordReader<R>),
+    /// Выписка BAI2
+    BAI2(Bai2RecordReader<R>),
+    /// Записи фиксированной ширины
+    FIXEDWIDTH(FixedWidthRecordReader<R>),
+    /// Apache Parquet
+    #[cfg(feature = "parquet")]
+    PARQUET(ParquetRecordReader<R>),
+    /// Файл Arrow IPC
+    #[cfg(feature = "arrow")]
+    ARROW(ArrowRecordReader<R>),
+    /// Поток Arrow IPC
+    #[cfg(feature = "arrow")]
+    ARROWSTREAM(ArrowRecordReader<R>),
+    /// Файл базы SQLite
+    #[cfg(feature = "sqlite")]
+    SQLITE(SqliteRecordReader<R>),
+}
+
+impl<R: std::io::Read> RecordReader<R> {
+    /// Создание читателя для заданного формата, XLSX только записывается
+    pub fn new(r: R, format: &DataFormat) -> Result<Self> {
+        let reader = match format {
+            DataFormat::TXT => RecordReader::TXT(TxtRecordReader::new(r)),
+            DataFormat::BIN => RecordReader::BIN(BinRecordReader::new(r)),
+            DataFormat::CSV => RecordReader::CSV(CsvRecordReader::new(r)),
+            DataFormat::JSON => RecordReader::JSON(JsonRecordReader::new(r)),
+            DataFormat::NDJSON => RecordReader::NDJSON(NdjsonRecordReader::new(r)),
+            DataFormat::CAMT053 => RecordReader::CAMT053(Camt053RecordReader::new(r)),
+            DataFormat::MT940 => RecordReader::MT940(Mt940RecordReader::new(r)),
+            DataFormat::OFX => RecordReader::OFX(OfxRecordReader::new(r)),
+            DataFormat::QIF => RecordReader::QIF(QifRecordReader::new(r)),
+            DataFormat::BAI2 => RecordReader::BAI2(Bai2RecordReader::new(r)),
+            DataFormat::FIXEDWIDTH => RecordReader::FIXEDWIDTH(FixedWidthRecordReader::new(r)),
+            #[cfg(feature = "parquet")]
+            DataFormat::PARQUET => RecordReader::PARQUET(ParquetRecordReader::new(r)),
+            #[cfg(feature = "arrow")]
+            DataFormat::ARROW => RecordReader::ARROW(ArrowRecordReader::new(r, IpcFormat::File)),
+            #[cfg(feature = "arrow")]
+            DataFormat::ARROWSTREAM => {
+                RecordReader::ARROWSTREAM(ArrowRecordReader::new(r, IpcFormat::Stream))
+            }
+            #[cfg(feature = "sqlite")]
+            DataFormat::SQLITE => RecordReader::SQLITE(SqliteRecordReader::new(r)),
+            #[cfg(feature = "xlsx")]
+            DataFormat::XLSX => return Err(AppError::UnreadableFormatError(DataFormat::XLSX)),
+        };
+        Ok(reader)
+    }
+
+    /// Имя источника (обычно имя файла) для сообщений об ошибках разбора
+    pub fn with_source(self, source: impl Into<String>) -> Self {
+        match self {
+            RecordReader::TXT(reader) => RecordReader::TXT(reader.with_source(source)),
+            RecordReader::BIN(reader) => RecordReader::BIN(reader.with_source(source)),
+            RecordReader::CSV(reader) => RecordReader::CSV(reader.with_source(source)),
+            RecordReader::JSON(reader) => RecordReader::JSON(reader.with_source(source)),
+            RecordReader::NDJSON(reader) => RecordReader::NDJSON(reader.with_source(source)),
+            RecordReader::CAMT053(reader) => RecordReader::CAMT053(reader.with_source(source)),
+            RecordReader::MT940(reader) => RecordReader::MT940(reader.with_source(source)),
+            RecordReader::OFX(reader) => RecordReader::OFX(reader.with_source(source)),
+            RecordReader::QIF(reader) => RecordReader::QIF(reader.with_source(source)),
+            RecordReader::BAI2(reader) => RecordReader::BAI2(reader.with_source(source)),
+            RecordReader::FIXEDWIDTH(reader) => {
+                RecordReader::FIXEDWIDTH(reader.with_source(source))
+            }
+            #[cfg(feature = "parquet")]
+            RecordReader::PARQUET(reader) => RecordReader::PARQUET(reader.with_source(source)),
+            #[cfg(feature = "arrow")]
+            RecordReader::ARROW(reader) => RecordReader::ARROW(reader.with_source(source)),
+            #[cfg(feature = "arrow")]
+            RecordReader::ARROWSTREAM(reader) => {
+                RecordReader::ARROWSTREAM(reader.with_source(source))
+            }
+            #[cfg(feature = "sqlite")]
+            RecordReader::SQLITE(reader) => RecordReader::SQLITE(reader.with_source(source)),
+        }
+    }
+
+    /// Получение TX_ID для QIF, остальные форматы хранят TX_ID в каждой записи
+    pub fn with_tx_id_strategy(self, tx_id_strategy: TxIdStrategy) -> Self {
+        match self {
+            RecordReader::QIF(reader) => {
+                RecordReader::QIF(reader.with_tx_id_strategy(tx_id_strategy))
+            }
+            reader => reader,
+        }
+    }
+
+    /// Раскладка записей фиксированной ширины, остальные форматы не имеют колонок
+    pub fn with_fixed_width_layout(self, layout: FixedWidthLayout) -> Self {
+        match self {
+            RecordReader::FIXEDWIDTH(reader) => {
+                RecordReader::FIXEDWIDTH(reader.with_layout(layout))
+            }
+            reader => reader,
+        }
+    }
+
+    /// Таблица или запрос для чтения SQLite, остальные форматы не содержат таблиц
+    #[cfg(feature = "sqlite")]
+    pub fn with_sqlite_table(self, table: SqliteTable) -> Self {
+        match self {
+            RecordReader::SQLITE(reader) => RecordReader::SQLITE(reader.with_table(table)),
+            reader => reader,
+        }
+    }
+
+    /// Режим восстановления: BIN ищет следующий заголовок `YPBN`, TXT собирает ошибки записи
+    /// в одну. Какие ошибки пропускают запись в остальных форматах, описано в их спецификациях.
+    pub fn with_recovery(self, recovery: bool) -> Self {
+        match self {
+            RecordReader::TXT(reader) => RecordReader::TXT(reader.with_recovery(recovery)),
+            RecordReader::BIN(reader) => RecordReader::BIN(reader.with_recovery(recovery)),
+            RecordReader::CSV(reader) => RecordReader::CSV(reader),
+            RecordReader::JSON(reader) => RecordReader::JSON(reader),
+            RecordReader::NDJSON(reader) => RecordReader::NDJSON(reader),
+            RecordReader::CAMT053(reader) => RecordReader::CAMT053(reader),
+            RecordReader::MT940(reader) => RecordReader::MT940(reader),
+            RecordReader::OFX(reader) => RecordReader::OFX(reader),
+            RecordReader::QIF(reader) => RecordReader::QIF(reader),
+            RecordReader::BAI2(reader) => RecordReader::BAI2(reader),
+            RecordReader::FIXEDWIDTH(reader) => RecordReader::FIXEDWIDTH(reader),
+            #[cfg(feature = "parquet")]
+            RecordReader::PARQUET(reader) => RecordReader::PARQUET(reader),
+            #[cfg(feature = "arrow")]
+            RecordReader::ARROW(reader) => RecordReader::ARROW(reader),
+            #[cfg(feature = "arrow")]
+            RecordReader::ARROWSTREAM(reader) => RecordReader::ARROWSTREAM(reader),
+            #[cfg(feature = "sqlite")]
+            RecordReader::SQLITE(reader) => RecordReader::SQLITE(reader),
+        }
+    }
+}
+
+impl<R: std::io::Read> Iterator for RecordReader<R> {
+    type Item = Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            RecordReader::TXT(reader) => reader.next(),
+            RecordReader::BIN(reader) => reader.next(),
+            RecordReader::CSV(reader) => reader.next(),
+            RecordReader::JSON(reader) => reader.next(),
+            RecordReader::NDJSON(reader) => reader.next(),
+            RecordReader::CAMT053(reader) => reader.next(),
+            RecordReader::MT940(reader) => reader.next(),
+            RecordReader::OFX(reader) => reader.next(),
+            RecordReader::QIF(reader) => reader.next(),
+            RecordReader::BAI2(reader) => reader.next(),
+            RecordReader::FIXEDWIDTH(reader) => reader.next(),
+            #[cfg(feature = "parquet")]
+            RecordReader::PARQUET(reader) => reader.next(),
+            #[cfg(feature = "arrow")]
+            RecordReader::ARROW(reader) => reader.next(),
+            #[cfg(feature = "arrow")]
+            RecordReader::ARROWSTREAM(reader) => reader.next(),
+            #[cfg(feature = "sqlite")]
+            RecordReader::SQLITE(reader) => reader.next(),
+        }
+    }
+}
+
+/// Потоковый писатель записей, принимает записи по одной без накопления в памяти
+pub enum RecordWriter<W: std::io::Write> {
+    /// Текстовый формат
+    TXT(TxtRecordWriter<W>),
+    /// Бинарный формат
+    BIN(BinRecordWriter<W>),
+    /// CSV формат
+    CSV(CsvRecordWriter<W>),
+    /// JSON массив
+    JSON(JsonRecordWriter<W>),
+    /// NDJSON формат
+    NDJSON(NdjsonRecordWriter<W>),
+    /// Выписка camt.053
+    CAMT053(Camt053RecordWriter<W>),
+    /// Выписка MT940
+    MT940(Mt940RecordWriter<W>),
+    /// Выписка OFX
+    OFX(OfxRecordWriter<W>),
+    /// Экспорт QIF
+    QIF(QifRecordWriter<W>),
+    /// Выписка BAI2
+    BAI2(Bai2RecordWriter<W>),
+    /// Записи фиксированной ширины
+    FIXEDWIDTH(FixedWidthRecordWriter<W>),
+    /// Apache Parquet
+    #[cfg(feature = "parquet")]
+    PARQUET(ParquetRecordWriter<W>),
+    /// Файл Arrow IPC
+    #[cfg(feature = "arrow")]
+    ARROW(ArrowRecordWriter<W>),
+    /// Поток Arrow IPC
+    #[cfg(feature = "arrow")]
+    ARROWSTREAM(ArrowRecordWriter<W>),
+    /// Файл базы SQLite
+    #[cfg(feature = "sqlite")]
+    SQLITE(SqliteRecordWriter<W>),
+    /// Книга XLSX
+    #[cfg(feature = "xlsx")]
+    XLSX(XlsxRecordWriter<W>),
+}
+
+impl<W: std::io::Write> RecordWriter<W> {
+    /// Создание писателя для заданного формата
+    pub fn new(writer: W, format: &DataFormat) -> Self {
+        match format {
+            DataFormat::TXT => RecordWriter::TXT(TxtRecordWriter::new(writer)),
+            DataFormat::BIN => RecordWriter::BIN(BinRecordWriter::new(writer)),
+            DataFormat::CSV => RecordWriter::CSV(CsvRecordWriter::new(writer)),
+            DataFormat::JSON => RecordWriter::JSON(JsonRecordWriter::new(writer)),
+            DataFormat::NDJSON => RecordWriter::NDJSON(NdjsonRecordWriter::new(writer)),
+            DataFormat::CAMT053 => RecordWriter::CAMT053(Camt053RecordWriter::new(writer)),
+            DataFormat::MT940 => RecordWriter::MT940(Mt940RecordWriter::new(writer)),
+            DataFormat::OFX => RecordWriter::OFX(OfxRecordWriter::new(writer)),
+            DataFormat::QIF => RecordWriter::QIF(QifRecordWriter::new(writer)),
+            DataFormat::BAI2 => RecordWriter::BAI2(Bai2RecordWriter::new(writer)),
+            DataFormat::FIXEDWIDTH => RecordWriter::FIXEDWIDTH(FixedWidthRecordWriter::new(writer)),
+            #[cfg(feature = "parquet")]
+            DataFormat::PARQUET => RecordWriter::PARQUET(ParquetRecordWriter::new(writer)),
+            #[cfg(feature = "arrow")]
+            DataFormat::ARROW => {
+                RecordWriter::ARROW(ArrowRecordWriter::new(writer, IpcFormat::File))
+            }
+            #[cfg(feature = "arrow")]
+            DataFormat::ARROWSTREAM => {
+                RecordWriter::ARROWSTREAM(ArrowRecordWriter::new(writer, IpcFormat::Stream))
+            }
+            #[cfg(feature = "sqlite")]
+            DataFormat::SQLITE => RecordWriter::SQLITE(SqliteRecordWriter::new(writer)),
+            #[cfg(feature = "xlsx")]
+            DataFormat::XLSX => RecordWriter::XLSX(XlsxRecordWriter::new(writer)),
+        }
+    }
+
+    /// Запись одной записи
+    pub fn write_message(&mut self, message: &Message) -> Result<()> {
+        match self {
+            RecordWriter::TXT(writer) => writer.write_message(message),
+            RecordWriter::BIN(writer) => writer.write_message(message),
+            RecordWriter::CSV(writer) => writer.write_message(message),
+            RecordWriter::JSON(writer) => writer.write_message(message),
+            RecordWriter::NDJSON(writer) => writer.write_message(message),
+            RecordWriter::CAMT053(writer) => writer.write_message(message),
+            RecordWriter::MT940(writer) => writer.write_message(message),
+            RecordWriter::OFX(writer) => writer.write_message(message),
+            RecordWriter::QIF(writer) => writer.write_message(message),
+            RecordWriter::BAI2(writer) => writer.write_message(message),
+            RecordWriter::FIXEDWIDTH(writer) => writer.write_message(message),
+            #[cfg(feature = "parquet")]
+            RecordWriter::PARQUET(writer) => writer.write_message(message),
+            #[cfg(feature = "arrow")]
+            RecordWriter::ARROW(writer) => writer.write_message(message),
+            #[cfg(feature = "arrow")]
+            RecordWriter::ARROWSTREAM(writer) => writer.write_message(message),
+            #[cfg(feature = "sqlite")]
+            RecordWriter::SQLITE(writer) => writer.write_message(message),
+            #[cfg(feature = "xlsx")]
+            RecordWriter::XLSX(writer) => writer.write_message(message),
+        }
+    }
+
+    /// Правило записи отрицательных сумм в TXT, CSV, camt.053, MT940, OFX, QIF и BAI2, для BIN, JSON, NDJSON,
+    /// записей фиксированной ширины и Parquet не используется
+    pub fn with_amount_policy(self, amount_policy: AmountPolicy) -> Self {
+        match self {
+            RecordWriter::TXT(writer) => {
+                RecordWriter::TXT(writer.with_amount_policy(amount_policy))
+            }
+            RecordWriter::BIN(writer) => RecordWriter::BIN(writer),
+            RecordWriter::CSV(writer) => {
+                RecordWriter::CSV(writer.with_amount_policy(amount_policy))
+            }
+            RecordWriter::JSON(writer) => RecordWriter::JSON(writer),
+            RecordWriter::NDJSON(writer) => RecordWriter::NDJSON(writer),
+            RecordWriter::CAMT053(writer) => {
+                RecordWriter::CAMT053(writer.with_amount_policy(amount_policy))
+            }
+            RecordWriter::MT940(writer) => {
+                RecordWriter::MT940(writer.with_amount_policy(amount_policy))
+            }
+            RecordWriter::OFX(writer) => {
+                RecordWriter::OFX(writer.with_amount_policy(amount_policy))
+            }
+            RecordWriter::QIF(writer) => {
+                RecordWriter::QIF(writer.with_amount_policy(amount_policy))
+            }
+            RecordWriter::BAI2(writer) => {
+                RecordWriter::BAI2(writer.with_amount_policy(amount_policy))
+            }
+            RecordWriter::FIXEDWIDTH(writer) => RecordWriter::FIXEDWIDTH(writer),
+            #[cfg(feature = "parquet")]
+            RecordWriter::PARQUET(writer) => RecordWriter::PARQUET(writer),
+            #[cfg(feature = "arrow")]
+            RecordWriter::ARROW(writer) => RecordWriter::ARROW(writer),
+            #[cfg(feature = "arrow")]
+            RecordWriter::ARROWSTREAM(writer) => RecordWriter::ARROWSTREAM(writer),
+            #[cfg(feature = "sqlite")]
+            RecordWriter::SQLITE(writer) => RecordWriter::SQLITE(writer),
+            #[cfg(feature = "xlsx")]
+            RecordWriter::XLSX(writer) => RecordWriter::XLSX(writer),
+        }
+    }
+
+    /// Время создания выписки camt.053, OFX и BAI2 в миллисекундах, по умолчанию время последней
+    /// записи. Остальные форматы не хранят время создания
+    pub fn with_created(self, created: u64) -> Self {
+        match self {
+            RecordWriter::CAMT053(writer) => RecordWriter::CAMT053(writer.with_created(created)),
+            RecordWriter::OFX(writer) => RecordWriter::OFX(writer.with_created(created)),
+            RecordWriter::BAI2(writer) => RecordWriter::BAI2(writer.with_created(created)),
+            writer => writer,
+        }
+    }
+
+    /// Размер групп строк для Parquet, остальные форматы не делятся на группы
+    #[cfg(feature = "parquet")]
+    pub fn with_row_group_size(self, row_group_size: RowGroupSize) -> Self {
+        match self {
+            RecordWriter::PARQUET(writer) => {
+                RecordWriter::PARQUET(writer.with_row_group_size(row_group_size))
+            }
+            writer => writer,
+        }
+    }
+
+    /// Раскладка записей фиксированной ширины, остальные форматы не имеют колонок
+    pub fn with_fixed_width_layout(self, layout: FixedWidthLayout) -> Self {
+        match self {
+            RecordWriter::FIXEDWIDTH(writer) => {
+                RecordWriter::FIXEDWIDTH(writer.with_layout(layout))
+            }
+            writer => writer,
+        }
+    }
+
+    /// Таблица для записи SQLite, остальные форматы не содержат таблиц
+    #[cfg(feature = "sqlite")]
+    pub fn with_sqlite_table(self, table: SqliteTable) -> Self {
+        match self {
+            RecordWriter::SQLITE(writer) => RecordWriter::SQLITE(writer.with_table(table)),
+            writer => writer,
+        }
+    }
+
+    /// Завершение записи: сброс буферов и возврат исходного писателя
+    pub fn finish(self) -> Result<W> {
+        match self {
+            RecordWriter::TXT(writer) => writer.finish(),
+            RecordWriter::BIN(writer) => writer.finish(),
+            RecordWriter::CSV(writer) => writer.finish(),
+            RecordWriter::JSON(writer) => writer.finish(),
+            RecordWriter::NDJSON(writer) => writer.finish(),
+            RecordWriter::CAMT053(writer) => writer.finish(),
+            RecordWriter::MT940(writer) => writer.finish(),
+            RecordWriter::OFX(writer) => writer.finish(),
+            RecordWriter::QIF(writer) => writer.finish(),
+            RecordWriter::BAI2(writer) => writer.finish(),
+            RecordWriter::FIXEDWIDTH(writer) => writer.finish(),
+            #[cfg(feature = "parquet")]
+            RecordWriter::PARQUET(writer) => writer.finish(),
+            #[cfg(feature = "arrow")]
+            RecordWriter::ARROW(writer) => writer.finish(),
+            #[cfg(feature = "arrow")]
+            RecordWriter::ARROWSTREAM(writer) => writer.finish(),
+            #[cfg(feature = "sqlite")]
+            RecordWriter::SQLITE(writer) => writer.finish(),
+            #[cfg(feature = "xlsx")]
+            RecordWriter::XLSX(writer) => writer.finish(),
+        }
+    }
+}
+
+/// Параметры конвертации. Создается через [`ConvertOptions::default`],
+/// поля задаются присваиванием: набор полей зависит от features и пополняется
+#[derive(Debug, Default, Clone)]
+#[non_exhaustive]
+pub struct ConvertOptions {
+    /// Правило записи отрицательных сумм
+    pub amount_policy: AmountPolicy,
+    /// Имя входного файла для сообщений об ошибках разбора
+    pub source: Option<String>,
+    /// Пропускать поврежденные записи вместо остановки конвертации
+    pub recover: bool,
+    /// Правило обработки повторяющихся TX_ID
+    pub duplicates: DuplicatePolicy,
+    /// Валюта записей без CURRENCY при записи camt.053, MT940, OFX, QIF, BAI2 и XLSX,
+    /// остальные форматы пишут записи без валюты как есть
+    pub default_currency: Option<Currency>,
+    /// Время создания выписок camt.053, OFX и BAI2 в миллисекундах, по умолчанию время последней записи
+    pub created: Option<u64>,
+    /// Получение TX_ID при чтении QIF
+    pub tx_id_strategy: TxIdStrategy,
+    /// Раскладка колонок записей фиксированной ширины при чтении и записи
+    pub fixed_width_layout: FixedWidthLayout,
+    /// Размер групп строк при записи Parquet
+    #[cfg(feature = "parquet")]
+    pub row_group_size: RowGroupSize,
+    /// Таблица при записи SQLite, таблица или запрос при чтении
+    #[cfg(feature = "sqlite")]
+    pub sqlite_table: SqliteTable,
+}
+
+/// Результат конвертации
+#[derive(Debug, Default)]
+pub struct ConvertReport {
+    /// Количество записанных записей
+    pub records: u64,
+    /// Ошибки пропущенных записей в режиме восстановления
+    pub diagnostics: Vec<AppError>,
+    /// Повторяющиеся TX_ID, номера считаются среди прочитанных записей
+    pub duplicates: Vec<Duplicate>,
+}
+
+/// Потоковая конвертация из одного формата в другой.
+/// В памяти одновременно находится не более одной записи.
+pub fn convert_stream<R: std::io::Read, W: std::io::Write>(
+    reader: R,
+    input_format: &DataFormat,
+    writer: W,
+    output_format: &DataFormat,
+) -> Result<()> {
+    convert_stream_with(
+        reader,
+        input_format,
+        writer,
+        output_format,
+        &ConvertOptions::default(),
+    )
+    .map(|_| ())
+}
+
+/// Потоковая конвертация с параметрами.
+/// Правила [`DuplicatePolicy::Reject`] и [`DuplicatePolicy::KeepLast`] требуют всех записей,
+/// поэтому с ними записи накапливаются в памяти и пишутся после чтения.
+/// [`DuplicatePolicy::Fail`] останавливает конвертацию на первом повторе.
+pub fn convert_stream_with<R: std::io::Read, W: std::io::Write>(
+    reader: R,
+    input_format: &DataFormat,
+    writer: W,
+    output_format: &DataFormat,
+    options: &ConvertOptions,
+) -> Result<ConvertReport> {
+    let mut reader = RecordReader::new(reader, input_format)?
+        .with_recovery(options.recover)
+        .with_tx_id_strategy(options.tx_id_strategy)
+        .with_fixed_width_layout(options.fixed_width_layout.clone());
+    if let Some(source) = &options.source {
+        reader = reader.with_source(source.as_str());
+    }
+    #[cfg(feature = "sqlite")]
+    {
+        reader = reader.with_sqlite_table(options.sqlite_table.clone());
+    }
+    let mut writer = RecordWriter::new(writer, output_format)
+        .with_amount_policy(options.amount_policy)
+        .with_fixed_width_layout(options.fixed_width_layout.clone());
+    if let Some(created) = options.created {
+        writer = writer.with_created(created);
+    }
+    #[cfg(feature = "parquet")]
+    {
+        writer = writer.with_row_group_size(options.row_group_size);
+    }
+    #[cfg(feature = "sqlite")]
+    {
+        writer = writer.with_sqlite_table(options.sqlite_table.clone());
+    }
+    let mut report = ConvertReport::default();
+    let mut occurrences = Occurrences::default();
+    let mut buffer = Vec::new();
+    let mut read = 0;
+    for message in reader {
+        let mut message = match message {
+            Ok(message) => message,
+            Err(e) if options.recover && e.is_record_error() => {
+                report.diagnostics.push(e);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        if output_format.uses_default_currency() {
+            message.currency = message.currency.or(options.default_currency);
+        }
+        read += 1;
+
+        if !options.duplicates.is_streaming() {
+            buffer.push(message);
+            continue;
+        }
+        if options.duplicates != DuplicatePolicy::Allow && !occurrences.insert(message.tx_id, read)
+        {
+            if options.duplicates == DuplicatePolicy::Fail {
+                return Err(AppError::DuplicateTxIdError(occurrences.duplicates()));
+            }
+            continue;
+        }
+        writer.write_message(&message)?;
+        report.records += 1;
+    }
+
+    if options.duplicates.is_streaming() {
+        report.duplicates = occurrences.duplicates();
+    } else {
+        let (messages, duplicates) = options.duplicates.apply(buffer)?;
+        for message in &messages {
+            writer.write_message(message)?;
+            report.records += 1;
+        }
+        report.duplicates = duplicates;
+    }
+    writer.finish()?;
+    Ok(report)
 }
 
 /// Реализация BankRecord для BankRecordEnum
 impl BankRecordConvertor {
+    /// Пустой набор записей заданного формата
+    pub fn new(format: &DataFormat) -> Self {
+        match format {
+            DataFormat::TXT => BankRecordConvertor::TXT(TxtYPBankRecord::new()),
+            DataFormat::BIN => BankRecordConvertor::BIN(BinYPBankRecord::new()),
+            DataFormat::CSV => BankRecordConvertor::CSV(CsvYPBankRecord::new()),
+            DataFormat::JSON => BankRecordConvertor::JSON(JsonYPBankRecord::new()),
+            DataFormat::NDJSON => BankRecordConvertor::NDJSON(NdjsonYPBankRecord::new()),
+            DataFormat::CAMT053 => BankRecordConvertor::CAMT053(Camt053YPBankRecord::new()),
+            DataFormat::MT940 => BankRecordConvertor::MT940(Mt940YPBankRecord::new()),
+            DataFormat::OFX => BankRecordConvertor::OFX(OfxYPBankRecord::new()),
+            DataFormat::QIF => BankRecordConvertor::QIF(QifYPBankRecord::new()),
+            DataFormat::BAI2 => BankRecordConvertor::BAI2(Bai2YPBankRecord::new()),
+            DataFormat::FIXEDWIDTH => {
+                BankRecordConvertor::FIXEDWIDTH(FixedWidthYPBankRecord::new())
+            }
+            #[cfg(feature = "parquet")]
+            DataFormat::PARQUET => BankRecordConvertor::PARQUET(ParquetYPBankRecord::new()),
+            #[cfg(feature = "arrow")]
+            DataFormat::ARROW => {
+                BankRecordConvertor::ARROW(ArrowYPBankRecord::new(IpcFormat::File))
+            }
+            #[cfg(feature = "arrow")]
+            DataFormat::ARROWSTREAM => {
+                BankRecordConvertor::ARROWSTREAM(ArrowYPBankRecord::new(IpcFormat::Stream))
+            }
+            #[cfg(feature = "sqlite")]
+            DataFormat::SQLITE => BankRecordConvertor::SQLITE(SqliteYPBankRecord::new()),
+            #[cfg(feature = "xlsx")]
+            DataFormat::XLSX => BankRecordConvertor::XLSX(XlsxYPBankRecord::new()),
+        }
+    }
+
+    /// Чтение с восстановлением: поврежденные записи пропускаются, их ошибки возвращаются
+    /// вместе с уцелевшими записями. Ошибки ввода-вывода прерывают чтение.
+    pub fn from_read_recover<R: std::io::Read>(
+        r: R,
+        format: &DataFormat,
+    ) -> Result<(Self, Vec<AppError>)> {
+        let mut record = Self::new(format);
+        let mut diagnostics = Vec::new();
+        for message in RecordReader::new(r, format)?.with_recovery(true) {
+            match message {
+                Ok(message) => record.push(message),
+                Err(e) if e.is_record_error() => diagnostics.push(e),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok((record, diagnostics))
+    }
+
     /// Создание из файла
     pub fn from_read<R: std::io::Read>(r: R, format: &DataFormat) -> Result<Self> {
         match format {
@@ -58,21 +776,136 @@ impl BankRecordConvertor {
                 let record = CsvYPBankRecord::from_read(r)?;
                 Ok(BankRecordConvertor::CSV(record))
             }
+            DataFormat::JSON => {
+                let record = JsonYPBankRecord::from_read(r)?;
+                Ok(BankRecordConvertor::JSON(record))
+            }
+            DataFormat::NDJSON => {
+                let record = NdjsonYPBankRecord::from_read(r)?;
+                Ok(BankRecordConvertor::NDJSON(record))
+            }
+            DataFormat::CAMT053 => {
+                let record = Camt053YPBankRecord::from_read(r)?;
+                Ok(BankRecordConvertor::CAMT053(record))
+            }
+            DataFormat::MT940 => {
+                let record = Mt940YPBankRecord::from_read(r)?;
+                Ok(BankRecordConvertor::MT940(record))
+            }
+            DataFormat::OFX => {
+                let record = OfxYPBankRecord::from_read(r)?;
+                Ok(BankRecordConvertor::OFX(record))
+            }
+            DataFormat::QIF => {
+                let record = QifYPBankRecord::from_read(r)?;
+                Ok(BankRecordConvertor::QIF(record))
+            }
+            DataFormat::BAI2 => {
+                let record = Bai2YPBankRecord::from_read(r)?;
+                Ok(BankRecordConvertor::BAI2(record))
+            }
+            DataFormat::FIXEDWIDTH => {
+                let record = FixedWidthYPBankRecord::from_read(r)?;
+                Ok(BankRecordConvertor::FIXEDWIDTH(record))
+            }
+            #[cfg(feature = "parquet")]
+            DataFormat::PARQUET => {
+                let record = ParquetYPBankRecord::from_read(r)?;
+                Ok(BankRecordConvertor::PARQUET(record))
+            }
+            #[cfg(feature = "arrow")]
+            DataFormat::ARROW => {
+                let record = ArrowYPBankRecord::from_read(r, IpcFormat::File)?;
+                Ok(BankRecordConvertor::ARROW(record))
+            }
+            #[cfg(feature = "arrow")]
+            DataFormat::ARROWSTREAM => {
+                let record = ArrowYPBankRecord::from_read(r, IpcFormat::Stream)?;
+                Ok(BankRecordConvertor::ARROWSTREAM(record))
+            }
+            #[cfg(feature = "sqlite")]
+            DataFormat::SQLITE => {
+                let record = SqliteYPBankRecord::from_read(r)?;
+                Ok(BankRecordConvertor::SQLITE(record))
+            }
+            #[cfg(feature = "xlsx")]
+            DataFormat::XLSX => Err(AppError::UnreadableFormatError(DataFormat::XLSX)),
         }
     }
-    /// Конвертация в другой формат
+    /// Формат набора записей
+    pub fn format(&self) -> DataFormat {
+        match self {
+            BankRecordConvertor::TXT(_) => DataFormat::TXT,
+            BankRecordConvertor::BIN(_) => DataFormat::BIN,
+            BankRecordConvertor::CSV(_) => DataFormat::CSV,
+            BankRecordConvertor::JSON(_) => DataFormat::JSON,
+            BankRecordConvertor::NDJSON(_) => DataFormat::NDJSON,
+            BankRecordConvertor::CAMT053(_) => DataFormat::CAMT053,
+            BankRecordConvertor::MT940(_) => DataFormat::MT940,
+            BankRecordConvertor::OFX(_) => DataFormat::OFX,
+            BankRecordConvertor::QIF(_) => DataFormat::QIF,
+            BankRecordConvertor::BAI2(_) => DataFormat::BAI2,
+            BankRecordConvertor::FIXEDWIDTH(_) => DataFormat::FIXEDWIDTH,
+            #[cfg(feature = "parquet")]
+            BankRecordConvertor::PARQUET(_) => DataFormat::PARQUET,
+            #[cfg(feature = "arrow")]
+            BankRecordConvertor::ARROW(_) => DataFormat::ARROW,
+            #[cfg(feature = "arrow")]
+            BankRecordConvertor::ARROWSTREAM(_) => DataFormat::ARROWSTREAM,
+            #[cfg(feature = "sqlite")]
+            BankRecordConvertor::SQLITE(_) => DataFormat::SQLITE,
+            #[cfg(feature = "xlsx")]
+            BankRecordConvertor::XLSX(_) => DataFormat::XLSX,
+        }
+    }
+
+    /// Конвертация в другой формат. Записи хранятся в общем виде [`Message`],
+    /// поэтому конвертация сводится к переносу записей в набор другого формата.
     pub fn convert_to(self, dataformat: &DataFormat) -> Self {
-        match (self, dataformat) {
-            (Self::TXT(record), DataFormat::TXT) => Self::TXT(record),
-            (Self::BIN(record), DataFormat::BIN) => Self::BIN(record),
-            (Self::CSV(record), DataFormat::CSV) => Self::CSV(record),
-            (Self::TXT(record), DataFormat::BIN) => Self::BIN(record.into()),
-            (Self::TXT(record), DataFormat::CSV) => Self::CSV(record.into()),
-            (Self::BIN(record), DataFormat::TXT) => Self::TXT(record.into()),
-            (Self::BIN(record), DataFormat::CSV) => Self::CSV(record.into()),
-            (Self::CSV(record), DataFormat::TXT) => Self::TXT(record.into()),
-            (Self::CSV(record), DataFormat::BIN) => Self::BIN(record.into()),
+        if self.format() == *dataformat {
+            return self;
+        }
+        let mut record = Self::new(dataformat);
+        for message in self.iter() {
+            record.push(message.clone());
+        }
+        record
+    }
+
+    /// Записи в виде Arrow RecordBatch со схемой [`record_batch_schema`]
+    #[cfg(feature = "arrow")]
+    pub fn to_record_batch(&self) -> Result<RecordBatch> {
+        to_record_batch(self.iter().as_slice())
+    }
+
+    /// Набор записей заданного формата из Arrow RecordBatch
+    #[cfg(feature = "arrow")]
+    pub fn from_record_batch(batch: &RecordBatch, format: &DataFormat) -> Result<Self> {
+        let mut record = Self::new(format);
+        for message in from_record_batch(batch)? {
+            record.push(message);
         }
+        Ok(record)
+    }
+
+    /// Запись в таблицу открытой базы SQLite, таблица и индексы создаются, если их нет
+    #[cfg(feature = "sqlite")]
+    pub fn to_sqlite(&self, connection: &Connection, table: &SqliteTable) -> Result<()> {
+        write_sqlite(connection, table, self.iter().as_slice())
+    }
+
+    /// Набор записей заданного формата из таблицы или запроса к открытой базе SQLite
+    #[cfg(feature = "sqlite")]
+    pub fn from_sqlite(
+        connection: &Connection,
+        table: &SqliteTable,
+        format: &DataFormat,
+    ) -> Result<Self> {
+        let mut record = Self::new(format);
+        for message in read_sqlite(connection, table)? {
+            record.push(message);
+        }
+        Ok(record)
     }
 
     /// Запись в файл
@@ -81,6 +914,24 @@ impl BankRecordConvertor {
             BankRecordConvertor::TXT(record) => record.write_to(writer),
             BankRecordConvertor::BIN(record) => record.write_to(writer),
             BankRecordConvertor::CSV(record) => record.write_to(writer),
+            BankRecordConvertor::JSON(record) => record.write_to(writer),
+            BankRecordConvertor::NDJSON(record) => record.write_to(writer),
+            BankRecordConvertor::CAMT053(record) => record.write_to(writer),
+            BankRecordConvertor::MT940(record) => record.write_to(writer),
+            BankRecordConvertor::OFX(record) => record.write_to(writer),
+            BankRecordConvertor::QIF(record) => record.write_to(writer),
+            BankRecordConvertor::BAI2(record) => record.write_to(writer),
+            BankRecordConvertor::FIXEDWIDTH(record) => record.write_to(writer),
+            #[cfg(feature = "parquet")]
+            BankRecordConvertor::PARQUET(record) => record.write_to(writer),
+            #[cfg(feature = "arrow")]
+            BankRecordConvertor::ARROW(record) => record.write_to(writer),
+            #[cfg(feature = "arrow")]
+            BankRecordConvertor::ARROWSTREAM(record) => record.write_to(writer),
+            #[cfg(feature = "sqlite")]
+            BankRecordConvertor::SQLITE(record) => record.write_to(writer),
+            #[cfg(feature = "xlsx")]
+            BankRecordConvertor::XLSX(record) => record.write_to(writer),
         }
     }
     /// Добавление записи в конец
@@ -89,6 +940,24 @@ impl BankRecordConvertor {
             BankRecordConvertor::TXT(record) => record.push(value),
             BankRecordConvertor::BIN(record) => record.push(value),
             BankRecordConvertor::CSV(record) => record.push(value),
+            BankRecordConvertor::JSON(record) => record.push(value),
+            BankRecordConvertor::NDJSON(record) => record.push(value),
+            BankRecordConvertor::CAMT053(record) => record.push(value),
+            BankRecordConvertor::MT940(record) => record.push(value),
+            BankRecordConvertor::OFX(record) => record.push(value),
+            BankRecordConvertor::QIF(record) => record.push(value),
+            BankRecordConvertor::BAI2(record) => record.push(value),
+            BankRecordConvertor::FIXEDWIDTH(record) => record.push(value),
+            #[cfg(feature = "parquet")]
+            BankRecordConvertor::PARQUET(record) => record.push(value),
+            #[cfg(feature = "arrow")]
+            BankRecordConvertor::ARROW(record) => record.push(value),
+            #[cfg(feature = "arrow")]
+            BankRecordConvertor::ARROWSTREAM(record) => record.push(value),
+            #[cfg(feature = "sqlite")]
+            BankRecordConvertor::SQLITE(record) => record.push(value),
+            #[cfg(feature = "xlsx")]
+            BankRecordConvertor::XLSX(record) => record.push(value),
         }
     }
     /// Количество записей
@@ -97,6 +966,24 @@ impl BankRecordConvertor {
             BankRecordConvertor::TXT(record) => record.len(),
             BankRecordConvertor::BIN(record) => record.len(),
             BankRecordConvertor::CSV(record) => record.len(),
+            BankRecordConvertor::JSON(record) => record.len(),
+            BankRecordConvertor::NDJSON(record) => record.len(),
+            BankRecordConvertor::CAMT053(record) => record.len(),
+            BankRecordConvertor::MT940(record) => record.len(),
+            BankRecordConvertor::OFX(record) => record.len(),
+            BankRecordConvertor::QIF(record) => record.len(),
+            BankRecordConvertor::BAI2(record) => record.len(),
+            BankRecordConvertor::FIXEDWIDTH(record) => record.len(),
+            #[cfg(feature = "parquet")]
+            BankRecordConvertor::PARQUET(record) => record.len(),
+            #[cfg(feature = "arrow")]
+            BankRecordConvertor::ARROW(record) => record.len(),
+            #[cfg(feature = "arrow")]
+            BankRecordConvertor::ARROWSTREAM(record) => record.len(),
+            #[cfg(feature = "sqlite")]
+            BankRecordConvertor::SQLITE(record) => record.len(),
+            #[cfg(feature = "xlsx")]
+            BankRecordConvertor::XLSX(record) => record.len(),
         }
     }
     /// Проверка на пустоту
@@ -105,6 +992,24 @@ impl BankRecordConvertor {
             BankRecordConvertor::TXT(record) => record.is_empty(),
             BankRecordConvertor::BIN(record) => record.is_empty(),
             BankRecordConvertor::CSV(record) => record.is_empty(),
+            BankRecordConvertor::JSON(record) => record.is_empty(),
+            BankRecordConvertor::NDJSON(record) => record.is_empty(),
+            BankRecordConvertor::CAMT053(record) => record.is_empty(),
+            BankRecordConvertor::MT940(record) => record.is_empty(),
+            BankRecordConvertor::OFX(record) => record.is_empty(),
+            BankRecordConvertor::QIF(record) => record.is_empty(),
+            BankRecordConvertor::BAI2(record) => record.is_empty(),
+            BankRecordConvertor::FIXEDWIDTH(record) => record.is_empty(),
+            #[cfg(feature = "parquet")]
+            BankRecordConvertor::PARQUET(record) => record.is_empty(),
+            #[cfg(feature = "arrow")]
+            BankRecordConvertor::ARROW(record) => record.is_empty(),
+            #[cfg(feature = "arrow")]
+            BankRecordConvertor::ARROWSTREAM(record) => record.is_empty(),
+            #[cfg(feature = "sqlite")]
+            BankRecordConvertor::SQLITE(record) => record.is_empty(),
+            #[cfg(feature = "xlsx")]
+            BankRecordConvertor::XLSX(record) => record.is_empty(),
         }
     }
     /// Удаление последней записи
@@ -113,14 +1018,65 @@ impl BankRecordConvertor {
             BankRecordConvertor::TXT(record) => record.pop(),
             BankRecordConvertor::BIN(record) => record.pop(),
             BankRecordConvertor::CSV(record) => record.pop(),
+            BankRecordConvertor::JSON(record) => record.pop(),
+            BankRecordConvertor::NDJSON(record) => record.pop(),
+            BankRecordConvertor::CAMT053(record) => record.pop(),
+            BankRecordConvertor::MT940(record) => record.pop(),
+            BankRecordConvertor::OFX(record) => record.pop(),
+            BankRecordConvertor::QIF(record) => record.pop(),
+            BankRecordConvertor::BAI2(record) => record.pop(),
+            BankRecordConvertor::FIXEDWIDTH(record) => record.pop(),
+            #[cfg(feature = "parquet")]
+            BankRecordConvertor::PARQUET(record) => record.pop(),
+            #[cfg(feature = "arrow")]
+            BankRecordConvertor::ARROW(record) => record.pop(),
+            #[cfg(feature = "arrow")]
+            BankRecordConvertor::ARROWSTREAM(record) => record.pop(),
+            #[cfg(feature = "sqlite")]
+            BankRecordConvertor::SQLITE(record) => record.pop(),
+            #[cfg(feature = "xlsx")]
+            BankRecordConvertor::XLSX(record) => record.pop(),
         }
     }
+    /// Повторяющиеся TX_ID
+    pub fn duplicates(&self) -> Vec<Duplicate> {
+        find_duplicates(self.iter())
+    }
+
+    /// Обработка повторяющихся TX_ID, возвращает оставшиеся записи и найденные повторы
+    pub fn deduplicate(self, policy: DuplicatePolicy) -> Result<(Self, Vec<Duplicate>)> {
+        let mut record = Self::new(&self.format());
+        let (messages, duplicates) = policy.apply(self.iter().cloned().collect())?;
+        for message in messages {
+            record.push(message);
+        }
+        Ok((record, duplicates))
+    }
+
     /// Итератор по записям
     pub fn iter(&self) -> std::slice::Iter<'_, Message> {
         match self {
             BankRecordConvertor::TXT(record) => record.iter(),
             BankRecordConvertor::BIN(record) => record.iter(),
             BankRecordConvertor::CSV(record) => record.iter(),
+            BankRecordConvertor::JSON(record) => record.iter(),
+            BankRecordConvertor::NDJSON(record) => record.iter(),
+            BankRecordConvertor::CAMT053(record) => record.iter(),
+            BankRecordConvertor::MT940(record) => record.iter(),
+            BankRecordConvertor::OFX(record) => record.iter(),
+            BankRecordConvertor::QIF(record) => record.iter(),
+            BankRecordConvertor::BAI2(record) => record.iter(),
+            BankRecordConvertor::FIXEDWIDTH(record) => record.iter(),
+            #[cfg(feature = "parquet")]
+            BankRecordConvertor::PARQUET(record) => record.iter(),
+            #[cfg(feature = "arrow")]
+            BankRecordConvertor::ARROW(record) => record.iter(),
+            #[cfg(feature = "arrow")]
+            BankRecordConvertor::ARROWSTREAM(record) => record.iter(),
+            #[cfg(feature = "sqlite")]
+            BankRecordConvertor::SQLITE(record) => record.iter(),
+            #[cfg(feature = "xlsx")]
+            BankRecordConvertor::XLSX(record) => record.iter(),
         }
     }
 }
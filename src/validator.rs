@@ -0,0 +1,388 @@
+use std::fmt::Display;
+
+use enum_display::EnumDisplay;
+
+use crate::{BankRecordConvertor, Message, TypeTransaction, convertor::MAX_DESCRIPTION_LEN};
+
+/// Серьезность нарушения
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, EnumDisplay)]
+#[enum_display(case = "Lower")]
+pub enum Severity {
+    /// Подозрительная запись, обработка может продолжаться
+    Warning,
+    /// Запись противоречит спецификации
+    Error,
+}
+
+/// Правило проверки одной записи
+pub trait Rule {
+    /// Имя правила в отчете
+    fn name(&self) -> &str;
+
+    /// Проверка записи, возвращает описание нарушения
+    fn check(&self, message: &Message) -> Option<String>;
+}
+
+/// DEPOSIT имеет FROM_USER_ID 0, WITHDRAWAL - TO_USER_ID 0
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AccountConsistency;
+
+impl Rule for AccountConsistency {
+    fn name(&self) -> &str {
+        "account-consistency"
+    }
+
+    fn check(&self, message: &Message) -> Option<String> {
+        match message.tx_type {
+            TypeTransaction::Deposit if message.from_user_id != 0 => Some(format!(
+                "DEPOSIT must have FROM_USER_ID 0, found {}",
+                message.from_user_id
+            )),
+            TypeTransaction::Withdrawal if message.to_user_id != 0 => Some(format!(
+                "WITHDRAWAL must have TO_USER_ID 0, found {}",
+                message.to_user_id
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// TRANSFER имеет ненулевые счета отправителя и получателя
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TransferAccounts;
+
+impl Rule for TransferAccounts {
+    fn name(&self) -> &str {
+        "transfer-accounts"
+    }
+
+    fn check(&self, message: &Message) -> Option<String> {
+        (message.tx_type == TypeTransaction::Transfer
+            && (message.from_user_id == 0 || message.to_user_id == 0))
+            .then(|| {
+                format!(
+                    "TRANSFER must have non-zero accounts, found FROM_USER_ID {} and TO_USER_ID {}",
+                    message.from_user_id, message.to_user_id
+                )
+            })
+    }
+}
+
+/// AMOUNT не равен нулю
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NonZeroAmount;
+
+impl Rule for NonZeroAmount {
+    fn name(&self) -> &str {
+        "non-zero-amount"
+    }
+
+    fn check(&self, message: &Message) -> Option<String> {
+        (message.amount == 0).then(|| "AMOUNT is 0".to_string())
+    }
+}
+
+/// Перевод не на тот же счет
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SelfTransfer;
+
+impl Rule for SelfTransfer {
+    fn name(&self) -> &str {
+        "self-transfer"
+    }
+
+    fn check(&self, message: &Message) -> Option<String> {
+        (message.tx_type == TypeTransaction::Transfer && message.from_user_id == message.to_user_id)
+            .then(|| format!("TRANSFER to the same account {}", message.from_user_id))
+    }
+}
+
+/// TIMESTAMP в заданном диапазоне, миллисекунды Unix epoch, границы включаются
+#[derive(Debug, Clone)]
+pub struct TimestampRange {
+    range: std::ops::RangeInclusive<u64>,
+}
+
+impl TimestampRange {
+    /// Правило для диапазона `min..=max`
+    pub fn new(min: u64, max: u64) -> Self {
+        Self { range: min..=max }
+    }
+}
+
+impl Rule for TimestampRange {
+    fn name(&self) -> &str {
+        "timestamp-range"
+    }
+
+    fn check(&self, message: &Message) -> Option<String> {
+        (!self.range.contains(&message.timestamp)).then(|| {
+            format!(
+                "TIMESTAMP {} is outside {}..={}",
+                message.timestamp,
+                self.range.start(),
+                self.range.end()
+            )
+        })
+    }
+}
+
+/// Длина DESCRIPTION в байтах UTF-8 не больше заданной
+#[derive(Debug, Clone, Copy)]
+pub struct DescriptionLength {
+    max: usize,
+}
+
+impl DescriptionLength {
+    /// Правило с наибольшей длиной `max` байт
+    pub fn new(max: usize) -> Self {
+        Self { max }
+    }
+}
+
+/// По умолчанию описание должно помещаться в запись бинарного формата
+impl Default for DescriptionLength {
+    fn default() -> Self {
+        Self::new(MAX_DESCRIPTION_LEN)
+    }
+}
+
+impl Rule for DescriptionLength {
+    fn name(&self) -> &str {
+        "description-length"
+    }
+
+    fn check(&self, message: &Message) -> Option<String> {
+        let len = message.description.len();
+        (len > self.max).then(|| format!("DESCRIPTION is {} bytes, max {}", len, self.max))
+    }
+}
+
+/// Нарушение правила в записи
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// Порядковый номер записи, начиная с 1
+    pub record: u64,
+    /// TX_ID записи
+    pub tx_id: u64,
+    /// Имя правила
+    pub rule: String,
+    /// Серьезность нарушения
+    pub severity: Severity,
+    /// Описание нарушения
+    pub message: String,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "record {}: TX_ID {}: {}: {}: {}",
+            self.record, self.tx_id, self.severity, self.rule, self.message
+        )
+    }
+}
+
+/// Результат проверки набора записей
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ValidationReport {
+    /// Нарушения в порядке записей
+    pub violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    /// Нет нарушений с серьезностью [`Severity::Error`]
+    pub fn is_valid(&self) -> bool {
+        self.count(Severity::Error) == 0
+    }
+
+    /// Количество нарушений заданной серьезности
+    pub fn count(&self, severity: Severity) -> usize {
+        self.violations
+            .iter()
+            .filter(|violation| violation.severity == severity)
+            .count()
+    }
+}
+
+/// Набор правил с серьезностью нарушений
+pub struct Validator {
+    rules: Vec<(Box<dyn Rule>, Severity)>,
+}
+
+impl Validator {
+    /// Проверка без правил
+    pub fn new() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Добавление правила, нарушения которого получают серьезность `severity`
+    pub fn with_rule(mut self, rule: impl Rule + 'static, severity: Severity) -> Self {
+        self.rules.push((Box::new(rule), severity));
+        self
+    }
+
+    /// Проверка одной записи, `record` - ее порядковый номер
+    pub fn validate_message(&self, record: u64, message: &Message) -> Vec<Violation> {
+        self.rules
+            .iter()
+            .filter_map(|(rule, severity)| {
+                rule.check(message).map(|text| Violation {
+                    record,
+                    tx_id: message.tx_id,
+                    rule: rule.name().to_string(),
+                    severity: *severity,
+                    message: text,
+                })
+            })
+            .collect()
+    }
+
+    /// Проверка всех записей набора
+    pub fn validate(&self, records: &BankRecordConvertor) -> ValidationReport {
+        let violations = records
+            .iter()
+            .zip(1..)
+            .flat_map(|(message, record)| self.validate_message(record, message))
+            .collect();
+        ValidationReport { violations }
+    }
+}
+
+/// Правила спецификаций: счета DEPOSIT и WITHDRAWAL - ошибка, нулевой счет TRANSFER,
+/// нулевая сумма, перевод самому себе и слишком длинное описание - предупреждения.
+/// Диапазон TIMESTAMP зависит от применения и добавляется через [`Validator::with_rule`].
+impl Default for Validator {
+    fn default() -> Self {
+        Self::new()
+            .with_rule(AccountConsistency, Severity::Error)
+            .with_rule(TransferAccounts, Severity::Warning)
+            .with_rule(NonZeroAmount, Severity::Warning)
+            .with_rule(SelfTransfer, Severity::Warning)
+            .with_rule(DescriptionLength::default(), Severity::Warning)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+    use crate::{DataFormat, StatusTransaction};
+
+    fn message(tx_type: TypeTransaction, from_user_id: u64, to_user_id: u64) -> Message {
+        Message {
+            tx_id: 1,
+            tx_type,
+            from_user_id,
+            to_user_id,
+            amount: 100,
+            timestamp: 1633036860000,
+            status: StatusTransaction::Success,
+            description: "a".to_string(),
+            currency: None,
+        }
+    }
+
+    #[test]
+    fn test_example_files_are_valid() {
+        for (path, format) in [
+            (
+                "tests/data/examples_file/records_example.txt",
+                DataFormat::TXT,
+            ),
+            (
+                "tests/data/examples_file/records_example.bin",
+                DataFormat::BIN,
+            ),
+            (
+                "tests/data/examples_file/records_example.csv",
+                DataFormat::CSV,
+            ),
+        ] {
+            let records =
+                BankRecordConvertor::from_read(File::open(path).unwrap(), &format).unwrap();
+            let report = Validator::default().validate(&records);
+            assert!(report.is_valid(), "{path}: {:?}", report.violations);
+        }
+    }
+
+    #[test]
+    fn test_default_rules() {
+        let mut records = BankRecordConvertor::new(&DataFormat::TXT);
+        records.push(message(TypeTransaction::Deposit, 0, 2));
+        records.push(message(TypeTransaction::Deposit, 3, 2));
+        records.push(message(TypeTransaction::Withdrawal, 2, 4));
+        records.push(Message {
+            amount: 0,
+            ..message(TypeTransaction::Transfer, 5, 5)
+        });
+        records.push(Message {
+            description: "x".repeat(MAX_DESCRIPTION_LEN + 1),
+            ..message(TypeTransaction::Transfer, 1, 2)
+        });
+        records.push(message(TypeTransaction::Transfer, 0, 2));
+
+        let report = Validator::default().validate(&records);
+        let actual = report
+            .violations
+            .iter()
+            .map(|v| (v.record, v.rule.as_str(), v.severity))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actual,
+            [
+                (2, "account-consistency", Severity::Error),
+                (3, "account-consistency", Severity::Error),
+                (4, "non-zero-amount", Severity::Warning),
+                (4, "self-transfer", Severity::Warning),
+                (5, "description-length", Severity::Warning),
+                (6, "transfer-accounts", Severity::Warning),
+            ]
+        );
+        assert!(!report.is_valid());
+        assert_eq!(report.count(Severity::Warning), 4);
+        assert_eq!(
+            report.violations[0].to_string(),
+            "record 2: TX_ID 1: error: account-consistency: DEPOSIT must have FROM_USER_ID 0, found 3"
+        );
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        struct FailedStatus;
+
+        impl Rule for FailedStatus {
+            fn name(&self) -> &str {
+                "failed-status"
+            }
+
+            fn check(&self, message: &Message) -> Option<String> {
+                (message.status == StatusTransaction::Failure).then(|| "FAILURE".to_string())
+            }
+        }
+
+        let validator = Validator::new()
+            .with_rule(
+                TimestampRange::new(1_600_000_000_000, 1_700_000_000_000),
+                Severity::Error,
+            )
+            .with_rule(FailedStatus, Severity::Warning);
+
+        let ok = message(TypeTransaction::Deposit, 0, 2);
+        assert!(validator.validate_message(1, &ok).is_empty());
+
+        let bad = Message {
+            timestamp: 1,
+            status: StatusTransaction::Failure,
+            ..ok
+        };
+        let violations = validator.validate_message(7, &bad);
+        assert_eq!(violations.len(), 2);
+        assert_eq!(
+            violations[0].message,
+            "TIMESTAMP 1 is outside 1600000000000..=1700000000000"
+        );
+        assert_eq!(violations[1].severity, Severity::Warning);
+    }
+}
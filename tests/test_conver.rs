@@ -3,24 +3,39 @@ use rstest::rstest;
 
 use std::{fs::File, path::PathBuf};
 
-use bank_record_converter::{BankRecordConvertor, DataFormat};
+use bank_record_converter::{
+    BankRecordConvertor, ConvertOptions, Currency, DataFormat, DuplicatePolicy, FixedWidthLayout,
+    Message, RecordReader, RecordWriter, StatusTransaction, TxIdStrategy, convert_stream,
+    convert_stream_with,
+    error::AppError,
+    find_duplicates,
+    validator::{AccountConsistency, Rule, TransferAccounts},
+};
+
+fn format_from_path(path: &std::path::Path) -> DataFormat {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("txt") => DataFormat::TXT,
+        Some("bin") => DataFormat::BIN,
+        Some("csv") => DataFormat::CSV,
+        Some("json") => DataFormat::JSON,
+        Some("ndjson") => DataFormat::NDJSON,
+        _ => panic!("Unknown file extension"),
+    }
+}
 
 #[rstest]
 #[case(DataFormat::TXT)]
 #[case(DataFormat::BIN)]
 #[case(DataFormat::CSV)]
+#[case(DataFormat::JSON)]
+#[case(DataFormat::NDJSON)]
 fn test_convert(
     #[case] target_format: DataFormat,
     #[files("tests/data/examples_file/*")] path: PathBuf,
 ) {
     let test_path = path.with_extension(target_format.to_string().to_lowercase());
 
-    let inut_format = match path.extension().and_then(|ext| ext.to_str()) {
-        Some("txt") => DataFormat::TXT,
-        Some("bin") => DataFormat::BIN,
-        Some("csv") => DataFormat::CSV,
-        _ => panic!("Unknown file extension"),
-    };
+    let inut_format = format_from_path(&path);
 
     println!(
         "Convert file '{:?}' and compare with '{:?}'",
@@ -39,3 +54,612 @@ fn test_convert(
 
     assert_eq!(new_data, test_data);
 }
+
+#[rstest]
+fn test_record_reader(#[files("tests/data/examples_file/*")] path: PathBuf) {
+    let format = format_from_path(&path);
+
+    let stream_data = RecordReader::new(File::open(&path).unwrap(), &format)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    let data = BankRecordConvertor::from_read(File::open(&path).unwrap(), &format).unwrap();
+
+    assert_eq!(stream_data, data.iter().cloned().collect::<Vec<_>>());
+}
+
+#[rstest]
+fn test_record_writer(#[files("tests/data/examples_file/*")] path: PathBuf) {
+    let format = format_from_path(&path);
+
+    let data = BankRecordConvertor::from_read(File::open(&path).unwrap(), &format).unwrap();
+
+    let mut expected = Vec::new();
+    data.write_to(&mut expected).unwrap();
+
+    let mut writer = RecordWriter::new(Vec::new(), &format);
+    for message in RecordReader::new(File::open(&path).unwrap(), &format).unwrap() {
+        writer.write_message(&message.unwrap()).unwrap();
+    }
+    let actual = writer.finish().unwrap();
+
+    assert_eq!(actual, expected);
+}
+
+#[rstest]
+#[case(DataFormat::TXT)]
+#[case(DataFormat::BIN)]
+#[case(DataFormat::CSV)]
+#[case(DataFormat::JSON)]
+#[case(DataFormat::NDJSON)]
+fn test_convert_stream(
+    #[case] target_format: DataFormat,
+    #[files("tests/data/examples_file/*")] path: PathBuf,
+) {
+    let input_format = format_from_path(&path);
+
+    let mut expected = Vec::new();
+    BankRecordConvertor::from_read(File::open(&path).unwrap(), &input_format)
+        .unwrap()
+        .convert_to(&target_format)
+        .write_to(&mut expected)
+        .unwrap();
+
+    let mut actual = Vec::new();
+    convert_stream(
+        File::open(&path).unwrap(),
+        &input_format,
+        &mut actual,
+        &target_format,
+    )
+    .unwrap();
+
+    assert_eq!(actual, expected);
+}
+
+/// Запись и чтение одного сообщения через формат
+fn round_trip(message: &Message, format: &DataFormat) -> Message {
+    let mut writer = RecordWriter::new(Vec::new(), format);
+    writer.write_message(message).unwrap();
+    let buf = writer.finish().unwrap();
+
+    let mut messages = RecordReader::new(buf.as_slice(), format)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(messages.len(), 1);
+    messages.remove(0)
+}
+
+#[rstest]
+#[case("")]
+#[case("plain text")]
+#[case("\"quoted\"")]
+#[case("He said \"hi\", then left")]
+#[case("\"")]
+#[case("a, b, c")]
+#[case("line 1\nline 2\n\nline 4")]
+#[case("back\\slash \\n \\\"")]
+fn test_description_round_trip(#[case] description: &str) {
+    let mut message = RecordReader::new(
+        File::open("tests/data/examples_file/records_example.txt").unwrap(),
+        &DataFormat::TXT,
+    )
+    .unwrap()
+    .next()
+    .unwrap()
+    .unwrap();
+    message.description = description.to_string();
+    message.currency = Some(Currency::from_code("EUR").unwrap());
+
+    let mut actual = message.clone();
+    for format in [
+        DataFormat::TXT,
+        DataFormat::BIN,
+        DataFormat::CSV,
+        DataFormat::CAMT053,
+        DataFormat::TXT,
+    ] {
+        actual = round_trip(&actual, &format);
+        assert_eq!(actual, message, "description changed after {format}");
+    }
+}
+
+#[rstest]
+#[case(DuplicatePolicy::Allow, Some(vec![1, 2, 1, 3, 2]))]
+#[case(DuplicatePolicy::Reject, Some(vec![3]))]
+#[case(DuplicatePolicy::KeepFirst, Some(vec![1, 2, 3]))]
+#[case(DuplicatePolicy::KeepLast, Some(vec![1, 3, 2]))]
+#[case(DuplicatePolicy::Fail, None)]
+fn test_convert_stream_duplicates(
+    #[case] policy: DuplicatePolicy,
+    #[case] expected: Option<Vec<u64>>,
+) {
+    let mut messages = RecordReader::new(
+        File::open("tests/data/examples_file/records_example.txt").unwrap(),
+        &DataFormat::TXT,
+    )
+    .unwrap()
+    .take(5)
+    .collect::<Result<Vec<_>, _>>()
+    .unwrap();
+    for (message, tx_id) in messages.iter_mut().zip([1, 2, 1, 3, 2]) {
+        message.tx_id = tx_id;
+    }
+    let mut writer = RecordWriter::new(Vec::new(), &DataFormat::BIN);
+    for message in &messages {
+        writer.write_message(message).unwrap();
+    }
+    let input = writer.finish().unwrap();
+
+    let mut options = ConvertOptions::default();
+    options.duplicates = policy;
+    let mut output = Vec::new();
+    let report = convert_stream_with(
+        input.as_slice(),
+        &DataFormat::BIN,
+        &mut output,
+        &DataFormat::CSV,
+        &options,
+    );
+
+    let Some(expected) = expected else {
+        assert!(matches!(report, Err(AppError::DuplicateTxIdError(_))));
+        return;
+    };
+    let report = report.unwrap();
+    let actual = RecordReader::new(output.as_slice(), &DataFormat::CSV)
+        .unwrap()
+        .map(|message| message.unwrap().tx_id)
+        .collect::<Vec<_>>();
+    assert_eq!(actual, expected);
+    assert_eq!(report.records, expected.len() as u64);
+    if policy != DuplicatePolicy::Allow {
+        assert_eq!(report.duplicates, find_duplicates(&messages));
+    }
+}
+
+#[test]
+fn test_convert_stream_camt053_default_currency() {
+    let input = std::fs::read("tests/data/examples_file/records_example.txt").unwrap();
+
+    // Без CURRENCY запись в camt.053 невозможна
+    let err = convert_stream(
+        input.as_slice(),
+        &DataFormat::TXT,
+        Vec::new(),
+        &DataFormat::CAMT053,
+    )
+    .unwrap_err();
+    assert!(matches!(err, AppError::MappingError { .. }));
+
+    let mut options = ConvertOptions::default();
+    options.default_currency = Some(Currency::from_code("EUR").unwrap());
+    let mut output = Vec::new();
+    let report = convert_stream_with(
+        input.as_slice(),
+        &DataFormat::TXT,
+        &mut output,
+        &DataFormat::CAMT053,
+        &options,
+    )
+    .unwrap();
+
+    let expected = BankRecordConvertor::from_read(input.as_slice(), &DataFormat::TXT).unwrap();
+    let actual = BankRecordConvertor::from_read(output.as_slice(), &DataFormat::CAMT053).unwrap();
+    assert_eq!(report.records, expected.len() as u64);
+    assert_eq!(actual.len(), expected.len());
+    // Выписки camt.053 упорядочены по счетам
+    let sorted = |data: &BankRecordConvertor| {
+        let mut messages = data.iter().cloned().collect::<Vec<_>>();
+        messages.sort_by_key(|message| message.tx_id);
+        messages
+    };
+    for (actual, expected) in sorted(&actual).iter().zip(&sorted(&expected)) {
+        assert_eq!(actual.currency, options.default_currency);
+        assert_eq!(
+            actual,
+            &Message {
+                currency: actual.currency,
+                ..expected.clone()
+            }
+        );
+    }
+
+    // Форматам без обязательной валюты записи передаются без CURRENCY
+    let mut output = Vec::new();
+    convert_stream_with(
+        input.as_slice(),
+        &DataFormat::TXT,
+        &mut output,
+        &DataFormat::NDJSON,
+        &options,
+    )
+    .unwrap();
+    let actual = BankRecordConvertor::from_read(output.as_slice(), &DataFormat::NDJSON).unwrap();
+    assert_eq!(actual.convert_to(&DataFormat::TXT), expected);
+}
+
+#[test]
+fn test_convert_stream_mt940() {
+    let input = std::fs::read("tests/data/examples_file/records_example.txt").unwrap();
+    let mut options = ConvertOptions::default();
+    options.default_currency = Some(Currency::from_code("EUR").unwrap());
+    let mut output = Vec::new();
+    convert_stream_with(
+        input.as_slice(),
+        &DataFormat::TXT,
+        &mut output,
+        &DataFormat::MT940,
+        &options,
+    )
+    .unwrap();
+
+    // Записи сгруппированы по счетам, поэтому порядок восстанавливается по TX_ID
+    let mut expected = RecordReader::new(input.as_slice(), &DataFormat::TXT)
+        .unwrap()
+        .map(|message| Message {
+            currency: options.default_currency,
+            ..message.unwrap()
+        })
+        .collect::<Vec<_>>();
+    let mut actual = RecordReader::new(output.as_slice(), &DataFormat::MT940)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    expected.sort_by_key(|message| message.tx_id);
+    actual.sort_by_key(|message| message.tx_id);
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_write_ofx() {
+    // В OFX попадают только проведенные транзакции
+    let input = std::fs::read("tests/data/examples_file/records_example.txt").unwrap();
+    let expected = RecordReader::new(input.as_slice(), &DataFormat::TXT)
+        .unwrap()
+        .map(|message| Message {
+            currency: Some(Currency::from_code("USD").unwrap()),
+            ..message.unwrap()
+        })
+        .filter(|message| message.status == StatusTransaction::Success)
+        .collect::<Vec<_>>();
+    assert!(!expected.is_empty());
+
+    let mut writer = RecordWriter::new(Vec::new(), &DataFormat::OFX);
+    for message in &expected {
+        writer.write_message(message).unwrap();
+    }
+    let output = writer.finish().unwrap();
+
+    let mut actual = RecordReader::new(output.as_slice(), &DataFormat::OFX)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let mut expected = expected;
+    expected.sort_by_key(|message| message.tx_id);
+    actual.sort_by_key(|message| message.tx_id);
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_convert_stream_qif() {
+    // QIF хранит только дату, а тип восстанавливается по счетам
+    let input = std::fs::read("tests/data/examples_file/records_example.txt").unwrap();
+    let messages = RecordReader::new(input.as_slice(), &DataFormat::TXT)
+        .unwrap()
+        .map(|message| message.unwrap())
+        .filter(|message| message.status == StatusTransaction::Success)
+        .filter(|message| AccountConsistency.check(message).is_none())
+        .filter(|message| TransferAccounts.check(message).is_none())
+        .filter(|message| !message.description.contains(['\r', '\n']))
+        .map(|message| Message {
+            timestamp: message.timestamp - message.timestamp % 86_400_000,
+            ..message
+        })
+        .collect::<Vec<_>>();
+    assert!(!messages.is_empty());
+    let mut writer = RecordWriter::new(Vec::new(), &DataFormat::QIF);
+    for message in &messages {
+        writer
+            .write_message(&Message {
+                currency: Some(Currency::from_code("USD").unwrap()),
+                ..message.clone()
+            })
+            .unwrap();
+    }
+    let qif = writer.finish().unwrap();
+
+    let mut options = ConvertOptions::default();
+    options.tx_id_strategy = TxIdStrategy::Number;
+    let mut output = Vec::new();
+    convert_stream_with(
+        qif.as_slice(),
+        &DataFormat::QIF,
+        &mut output,
+        &DataFormat::NDJSON,
+        &options,
+    )
+    .unwrap();
+
+    let mut expected = messages
+        .into_iter()
+        .map(|message| Message {
+            currency: Some(Currency::from_code("USD").unwrap()),
+            ..message
+        })
+        .collect::<Vec<_>>();
+    let mut actual = RecordReader::new(output.as_slice(), &DataFormat::NDJSON)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    expected.sort_by_key(|message| message.tx_id);
+    actual.sort_by_key(|message| message.tx_id);
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_convert_stream_bai2() {
+    // BAI2 хранит время с точностью до минуты, а тип восстанавливается по коду и счетам
+    let input = std::fs::read("tests/data/examples_file/records_example.txt").unwrap();
+    let messages = RecordReader::new(input.as_slice(), &DataFormat::TXT)
+        .unwrap()
+        .map(|message| message.unwrap())
+        .filter(|message| message.status == StatusTransaction::Success)
+        .filter(|message| AccountConsistency.check(message).is_none())
+        .filter(|message| TransferAccounts.check(message).is_none())
+        .filter(|message| !message.description.starts_with('/'))
+        .filter(|message| !message.description.ends_with(char::is_whitespace))
+        .filter(|message| !message.description.contains(['\r', '\n']))
+        .map(|message| Message {
+            timestamp: message.timestamp - message.timestamp % 60_000,
+            currency: Some(Currency::from_code("USD").unwrap()),
+            ..message
+        })
+        .collect::<Vec<_>>();
+    assert!(!messages.is_empty());
+    let mut writer = RecordWriter::new(Vec::new(), &DataFormat::BAI2);
+    for message in &messages {
+        writer.write_message(message).unwrap();
+    }
+    let bai2 = writer.finish().unwrap();
+
+    let mut output = Vec::new();
+    convert_stream_with(
+        bai2.as_slice(),
+        &DataFormat::BAI2,
+        &mut output,
+        &DataFormat::NDJSON,
+        &ConvertOptions::default(),
+    )
+    .unwrap();
+
+    let mut expected = messages;
+    let mut actual = RecordReader::new(output.as_slice(), &DataFormat::NDJSON)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    expected.sort_by_key(|message| message.tx_id);
+    actual.sort_by_key(|message| message.tx_id);
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_convert_stream_fixed_width() {
+    // Колонки раскладки вмещают записи примера, кроме многострочных и длинных описаний
+    let layout: FixedWidthLayout = "
+        TX_ID,0,20,0,right
+        TX_TYPE,20,10, ,left
+        FROM_USER_ID,30,20,0,right
+        TO_USER_ID,50,20,0,right
+        AMOUNT,70,20,0,right
+        TIMESTAMP,90,20,0,right
+        STATUS,110,7, ,left
+        CURRENCY,117,3, ,left
+        DESCRIPTION,120,200, ,left
+    "
+    .parse()
+    .unwrap();
+    let input = std::fs::read("tests/data/examples_file/records_example.txt").unwrap();
+    let messages = RecordReader::new(input.as_slice(), &DataFormat::TXT)
+        .unwrap()
+        .map(|message| message.unwrap())
+        .filter(|message| {
+            !message.description.contains(['\r', '\n'])
+                && !message.description.ends_with(' ')
+                && message.description.chars().count() <= 200
+        })
+        .enumerate()
+        .map(|(i, message)| Message {
+            currency: (i % 2 == 0).then(|| Currency::from_code("EUR").unwrap()),
+            ..message
+        })
+        .collect::<Vec<_>>();
+    assert!(!messages.is_empty());
+    let mut writer = RecordWriter::new(Vec::new(), &DataFormat::NDJSON);
+    for message in &messages {
+        writer.write_message(message).unwrap();
+    }
+    let ndjson = writer.finish().unwrap();
+
+    let mut options = ConvertOptions::default();
+    options.fixed_width_layout = layout;
+    let mut fixed_width = Vec::new();
+    convert_stream_with(
+        ndjson.as_slice(),
+        &DataFormat::NDJSON,
+        &mut fixed_width,
+        &DataFormat::FIXEDWIDTH,
+        &options,
+    )
+    .unwrap();
+    assert!(
+        String::from_utf8(fixed_width.clone())
+            .unwrap()
+            .lines()
+            .all(|line| line.chars().count() == 320)
+    );
+
+    let mut output = Vec::new();
+    convert_stream_with(
+        fixed_width.as_slice(),
+        &DataFormat::FIXEDWIDTH,
+        &mut output,
+        &DataFormat::NDJSON,
+        &options,
+    )
+    .unwrap();
+    let actual = RecordReader::new(output.as_slice(), &DataFormat::NDJSON)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(actual, messages);
+}
+
+#[cfg(feature = "parquet")]
+#[rstest]
+#[case(DataFormat::TXT)]
+#[case(DataFormat::BIN)]
+#[case(DataFormat::CSV)]
+fn test_convert_parquet(#[case] target_format: DataFormat) {
+    // Parquet из файла примера конвертируется обратно в остальные форматы без потерь
+    let path = PathBuf::from("tests/data/examples_file/records_example.txt");
+    let mut parquet = Vec::new();
+    BankRecordConvertor::from_read(File::open(&path).unwrap(), &DataFormat::TXT)
+        .unwrap()
+        .convert_to(&DataFormat::PARQUET)
+        .write_to(&mut parquet)
+        .unwrap();
+
+    let new_data = BankRecordConvertor::from_read(parquet.as_slice(), &DataFormat::PARQUET)
+        .unwrap()
+        .convert_to(&target_format);
+
+    let test_path = path.with_extension(target_format.to_string().to_lowercase());
+    let test_data =
+        BankRecordConvertor::from_read(File::open(test_path).unwrap(), &target_format).unwrap();
+    assert_eq!(new_data, test_data);
+}
+
+#[cfg(feature = "arrow")]
+#[rstest]
+#[case(DataFormat::ARROW)]
+#[case(DataFormat::ARROWSTREAM)]
+fn test_convert_arrow(#[case] format: DataFormat) {
+    // Arrow IPC из файла примера конвертируется обратно в TXT без потерь
+    let path = PathBuf::from("tests/data/examples_file/records_example.txt");
+    let mut arrow = Vec::new();
+    convert_stream(
+        File::open(&path).unwrap(),
+        &DataFormat::TXT,
+        &mut arrow,
+        &format,
+    )
+    .unwrap();
+
+    let new_data = BankRecordConvertor::from_read(arrow.as_slice(), &format)
+        .unwrap()
+        .convert_to(&DataFormat::TXT);
+    let test_data =
+        BankRecordConvertor::from_read(File::open(&path).unwrap(), &DataFormat::TXT).unwrap();
+    assert_eq!(new_data, test_data);
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn test_record_batch_round_trip() {
+    let path = PathBuf::from("tests/data/examples_file/records_example.bin");
+    let data =
+        BankRecordConvertor::from_read(File::open(&path).unwrap(), &DataFormat::BIN).unwrap();
+
+    let batch = data.to_record_batch().unwrap();
+    assert_eq!(batch.schema(), bank_record_converter::record_batch_schema());
+    assert_eq!(batch.num_rows(), data.iter().len());
+
+    let new_data = BankRecordConvertor::from_record_batch(&batch, &DataFormat::BIN).unwrap();
+    assert_eq!(new_data, data);
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn test_convert_sqlite() {
+    // Файл базы из файла примера читается обратно без потерь
+    let path = PathBuf::from("tests/data/examples_file/records_example.txt");
+    let mut sqlite = Vec::new();
+    convert_stream(
+        File::open(&path).unwrap(),
+        &DataFormat::TXT,
+        &mut sqlite,
+        &DataFormat::SQLITE,
+    )
+    .unwrap();
+
+    let new_data = BankRecordConvertor::from_read(sqlite.as_slice(), &DataFormat::SQLITE)
+        .unwrap()
+        .convert_to(&DataFormat::TXT);
+    let test_data =
+        BankRecordConvertor::from_read(File::open(&path).unwrap(), &DataFormat::TXT).unwrap();
+    assert_eq!(new_data, test_data);
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn test_sqlite_connection() {
+    use bank_record_converter::{Connection, SqliteTable};
+
+    let path = PathBuf::from("tests/data/examples_file/records_example.bin");
+    let data =
+        BankRecordConvertor::from_read(File::open(&path).unwrap(), &DataFormat::BIN).unwrap();
+    let connection = Connection::open_in_memory().unwrap();
+    let table = SqliteTable::default();
+    data.to_sqlite(&connection, &table).unwrap();
+
+    let new_data = BankRecordConvertor::from_sqlite(&connection, &table, &DataFormat::BIN).unwrap();
+    assert_eq!(new_data, data);
+
+    // Запрос выбирает записи одного счета по индексу
+    let user_id = data.iter().next().unwrap().from_user_id;
+    let query = SqliteTable {
+        query: Some(format!(
+            "SELECT * FROM transactions WHERE from_user_id = {} ORDER BY timestamp, rowid",
+            user_id
+        )),
+        ..SqliteTable::default()
+    };
+    let selected = BankRecordConvertor::from_sqlite(&connection, &query, &DataFormat::TXT).unwrap();
+    let mut expected = data
+        .iter()
+        .filter(|message| message.from_user_id == user_id)
+        .cloned()
+        .collect::<Vec<_>>();
+    expected.sort_by_key(|message| message.timestamp);
+    assert!(!expected.is_empty());
+    assert_eq!(selected.iter().cloned().collect::<Vec<_>>(), expected);
+}
+
+#[cfg(feature = "xlsx")]
+#[test]
+fn test_convert_xlsx() {
+    // XLSX только записывается
+    let path = PathBuf::from("tests/data/examples_file/records_example.txt");
+    let mut xlsx = Vec::new();
+    convert_stream(
+        File::open(&path).unwrap(),
+        &DataFormat::TXT,
+        &mut xlsx,
+        &DataFormat::XLSX,
+    )
+    .unwrap();
+    assert!(xlsx.starts_with(b"PK"));
+
+    let error = BankRecordConvertor::from_read(xlsx.as_slice(), &DataFormat::XLSX).unwrap_err();
+    assert!(matches!(
+        error,
+        AppError::UnreadableFormatError(DataFormat::XLSX)
+    ));
+    let error = RecordReader::new(xlsx.as_slice(), &DataFormat::XLSX)
+        .err()
+        .unwrap();
+    assert_eq!(error.to_string(), "XLSX format cannot be read");
+}